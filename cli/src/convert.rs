@@ -0,0 +1,55 @@
+use crate::{AnyResult, Target};
+use lib::save::convert::convert_dir;
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Engine to convert the saves to
+    #[arg(long, value_enum)]
+    to: Target,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Save directory of the original game
+    from_dir: PathBuf,
+
+    /// Save directory to write the converted saves into
+    to_dir: PathBuf,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let report = convert_dir(&args.from_dir, &args.to_dir, args.to.into())?;
+
+    let mut out = stdout().lock();
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &report)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    for path in &report.written {
+        writeln!(out, "Wrote {}", path.display())?;
+    }
+    for (path, reason) in &report.skipped {
+        writeln!(out, "Skipped {}: {reason}", path.display())?;
+    }
+    if !report.plugin_classes.is_empty() {
+        let classes = report
+            .plugin_classes
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "Plugin classes the target game needs: {}",
+            classes.join(", ")
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::AnyResult;
+use lib::{decrypt::decrypt_game, encryption_key::EncryptionKey};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Key as 32 hex digits, instead of the one in System.json
+    #[arg(long, short = 'k')]
+    encryption_key: Option<String>,
+
+    /// Copy the decrypted project here instead of replacing the encrypted
+    /// files in place
+    #[arg(long, short = 'o')]
+    out: Option<PathBuf>,
+
+    /// The game directory, or any path inside it
+    game_dir: PathBuf,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let key = match args.encryption_key {
+        Some(key) => Some(EncryptionKey::from_hex_str(&key)?),
+        None => None,
+    };
+
+    let report = decrypt_game(&args.game_dir, args.out.as_deref(), key)?;
+
+    eprintln!(
+        "Decrypted {} files, copied {} into {}",
+        report.decrypted,
+        report.copied,
+        report.project_dir.display()
+    );
+    if let Some(path) = &report.project_file {
+        eprintln!("Wrote {}", path.display());
+    }
+    for (path, reason) in &report.failed {
+        eprintln!("Failed {}: {reason}", path.display());
+    }
+    for flag in &report.kept_flags {
+        eprintln!("Left {flag} set in System.json, since some of those files are still encrypted");
+    }
+
+    if report.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} files couldn't be handled", report.failed.len()).into())
+    }
+}
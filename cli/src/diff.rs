@@ -0,0 +1,49 @@
+use crate::{AnyResult, read_save};
+use lib::{
+    data::DataSource,
+    save::{
+        diff::{Names, diff},
+        extension::Registry,
+    },
+};
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Game data directory (www/data) or the game's directory, used to name
+    /// switches, variables, items and maps
+    #[arg(long, short = 'd')]
+    data: Option<PathBuf>,
+
+    /// Print the diff as JSON
+    #[arg(long)]
+    json: bool,
+
+    before: PathBuf,
+    after: PathBuf,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let names = match &args.data {
+        Some(dir) => Names::load(&DataSource::open(dir))?,
+        None => Names::default(),
+    };
+
+    let before = read_save(&args.before)?;
+    let after = read_save(&args.after)?;
+
+    let diff = diff(&before, &after, &names, &Registry::default())?;
+
+    let mut out = stdout().lock();
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &diff)?;
+        writeln!(out)?;
+    } else {
+        write!(out, "{diff}")?;
+    }
+
+    Ok(())
+}
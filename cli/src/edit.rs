@@ -0,0 +1,109 @@
+use crate::{AnyResult, read_save};
+use lib::save::{
+    Json,
+    patch::{Patch, delete_path, get_path, set_path},
+};
+use std::{
+    io::{Write, stdout},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, clap::Args)]
+pub struct GetArgs {
+    /// Print compact JSON instead of pretty printing
+    #[arg(long)]
+    compact: bool,
+
+    save: PathBuf,
+
+    /// JSON Pointer (`/party/_gold`) or JSONPath (`party._gold`)
+    path: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SetArgs {
+    /// Write the result here instead of over the save
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+
+    save: PathBuf,
+
+    /// JSON Pointer (`/party/_gold`) or JSONPath (`party._gold`)
+    path: String,
+
+    /// New value as JSON, so strings need quotes: '"Harold"'
+    value: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct DeleteArgs {
+    /// Write the result here instead of over the save
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+
+    save: PathBuf,
+
+    /// JSON Pointer (`/party/_gold`) or JSONPath (`party._gold`)
+    path: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PatchArgs {
+    /// Write the result here instead of over the save
+    #[arg(long, short = 'o')]
+    output: Option<PathBuf>,
+
+    save: PathBuf,
+
+    /// JSON Patch (RFC 6902) files, applied in order
+    #[arg(required = true)]
+    patches: Vec<PathBuf>,
+}
+
+fn write_save(save: &Json, path: &Path, output: Option<&Path>) -> AnyResult<()> {
+    save.write_file(output.unwrap_or(path))?;
+    Ok(())
+}
+
+pub fn get(args: GetArgs) -> AnyResult<()> {
+    let save = read_save(&args.save)?;
+    let value = get_path(&save, &args.path)?;
+
+    let mut out = stdout().lock();
+    if args.compact {
+        serde_json::to_writer(&mut out, value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut out, value)?;
+    }
+    writeln!(out)?;
+
+    Ok(())
+}
+
+pub fn set(args: SetArgs) -> AnyResult<()> {
+    let mut save = read_save(&args.save)?;
+    let value = serde_json::from_str(&args.value)
+        .map_err(|e| format!("Value must be JSON (quote strings): {e}"))?;
+
+    set_path(&mut save, &args.path, value)?;
+    write_save(&save, &args.save, args.output.as_deref())
+}
+
+pub fn delete(args: DeleteArgs) -> AnyResult<()> {
+    let mut save = read_save(&args.save)?;
+    delete_path(&mut save, &args.path)?;
+    write_save(&save, &args.save, args.output.as_deref())
+}
+
+pub fn patch(args: PatchArgs) -> AnyResult<()> {
+    let mut save = read_save(&args.save)?;
+
+    for path in &args.patches {
+        let patch: Patch = std::fs::read_to_string(path)?.parse()?;
+        patch
+            .apply(&mut save)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+
+    write_save(&save, &args.save, args.output.as_deref())
+}
@@ -0,0 +1,90 @@
+use crate::AnyResult;
+use lib::data::{
+    Database, Table,
+    event::{CommonEvent, Troop},
+    map::Map,
+    print::Printer,
+};
+use std::{
+    io::{Write, stdout},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, clap::Args)]
+#[command(group(clap::ArgGroup::new("source").required(true)))]
+pub struct Args {
+    /// Game data directory (www/data)
+    #[arg(long, short = 'd')]
+    data: PathBuf,
+
+    /// Print the events of MapXXX.json
+    #[arg(long, group = "source")]
+    map: Option<u32>,
+
+    /// Print a common event, or all of them with 0
+    #[arg(long, group = "source")]
+    common_event: Option<u32>,
+
+    /// Print the pages of a troop, or all of them with 0
+    #[arg(long, group = "source")]
+    troop: Option<u32>,
+}
+
+fn selected<T>(table: &Table<T>, id: u32) -> Vec<(usize, &T)> {
+    table
+        .iter()
+        .filter(|(x, _)| id == 0 || *x == id as usize)
+        .collect()
+}
+
+fn load_database(data_dir: &Path) -> Option<Database> {
+    Database::load(data_dir)
+        .inspect_err(|e| eprintln!("Printing ids without names: {e}"))
+        .ok()
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let db = load_database(&args.data);
+    let printer = Printer::new(db.as_ref());
+    let mut out = stdout().lock();
+
+    if let Some(map_id) = args.map {
+        let map = Map::load(&args.data, map_id)?;
+        for (id, event) in map.events.iter() {
+            writeln!(out, "EV{id:03} {} ({},{})", event.name, event.x, event.y)?;
+            for (i, page) in event.pages.iter().enumerate() {
+                writeln!(out, "  Page {}", i + 1)?;
+                for line in printer.list(&page.list).lines() {
+                    writeln!(out, "    {line}")?;
+                }
+            }
+        }
+    } else if let Some(id) = args.common_event {
+        let events = match &db {
+            Some(db) => db.common_events.clone(),
+            None => Table::<CommonEvent>::read_file(&args.data.join("CommonEvents.json"))?,
+        };
+        for (id, event) in selected(&events, id) {
+            writeln!(out, "Common Event {id:04} {}", event.name)?;
+            for line in printer.list(&event.list).lines() {
+                writeln!(out, "  {line}")?;
+            }
+        }
+    } else if let Some(id) = args.troop {
+        let troops = match &db {
+            Some(db) => db.troops.clone(),
+            None => Table::<Troop>::read_file(&args.data.join("Troops.json"))?,
+        };
+        for (id, troop) in selected(&troops, id) {
+            writeln!(out, "Troop {id:04} {}", troop.name)?;
+            for (i, page) in troop.pages.iter().enumerate() {
+                writeln!(out, "  Page {}", i + 1)?;
+                for line in printer.list(&page.list).lines() {
+                    writeln!(out, "    {line}")?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::AnyResult;
+use lib::local_storage::{LocalStorage, export, import_files};
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// List the saves in a `Local Storage` directory
+    List {
+        /// `Local Storage` or `Local Storage/leveldb` directory
+        storage: PathBuf,
+    },
+    /// Write the saves out as .rpgsave files
+    Export {
+        /// Page origin to export, needed when several games have saves
+        #[arg(long, short = 'o')]
+        origin: Option<String>,
+
+        /// `Local Storage` or `Local Storage/leveldb` directory
+        storage: PathBuf,
+
+        /// Directory to write the saves into
+        save_dir: PathBuf,
+    },
+    /// Write save files into local storage, replacing the saves there.
+    /// The game or browser must be closed.
+    Import {
+        /// Page origin to import into, needed unless exactly one game has saves
+        #[arg(long, short = 'o')]
+        origin: Option<String>,
+
+        /// `Local Storage` or `Local Storage/leveldb` directory
+        storage: PathBuf,
+
+        /// Save files, named like `file1.rpgsave` or `global.rpgsave`
+        #[arg(required = true)]
+        saves: Vec<PathBuf>,
+    },
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    match args.command {
+        Command::List { storage } => {
+            let storage = LocalStorage::read_dir(&storage)?;
+
+            let mut out = stdout().lock();
+            for save in storage.saves(None) {
+                let status = match save.json() {
+                    Ok(_) => "ok".to_string(),
+                    Err(e) => format!("unreadable: {e}"),
+                };
+                writeln!(out, "{}\t{}\t{status}", save.origin, save.key)?;
+            }
+        }
+        Command::Export {
+            origin,
+            storage,
+            save_dir,
+        } => {
+            let storage = LocalStorage::read_dir(&storage)?;
+            let origin = match &origin {
+                Some(origin) => origin.as_str(),
+                None => storage.single_save_origin()?,
+            };
+
+            for path in export(&storage, origin, &save_dir)? {
+                eprintln!("Wrote {}", path.display());
+            }
+        }
+        Command::Import {
+            origin,
+            storage: storage_dir,
+            saves,
+        } => {
+            let origin = match origin {
+                Some(origin) => origin,
+                None => LocalStorage::read_dir(&storage_dir)?
+                    .single_save_origin()?
+                    .to_string(),
+            };
+
+            import_files(&storage_dir, &origin, &saves)?;
+            eprintln!("Imported {} saves into {origin}", saves.len());
+        }
+    }
+
+    Ok(())
+}
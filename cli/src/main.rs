@@ -0,0 +1,109 @@
+mod convert;
+mod decrypt;
+mod diff;
+mod edit;
+mod events;
+mod local_storage;
+mod package;
+mod plugins;
+mod translate;
+mod unpack;
+mod validate;
+mod xref;
+
+use clap::{Parser, Subcommand};
+use lib::save::{Format, Json};
+use std::path::Path;
+
+type AnyError = Box<dyn std::error::Error>;
+type AnyResult<T> = Result<T, AnyError>;
+
+#[derive(Debug, Parser)]
+#[command(version, about, arg_required_else_help(true))]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Convert saves between MV and MZ
+    Convert(convert::Args),
+    /// Decrypt a deployed game's assets into a project the editor can open
+    Decrypt(decrypt::Args),
+    /// Remove a value from a save
+    Delete(edit::DeleteArgs),
+    /// Compare two save files
+    Diff(diff::Args),
+    /// Print event lists from the game data the way the editor shows them
+    Events(events::Args),
+    /// Write the game's text to a .po or .csv file for translating
+    Extract(translate::ExtractArgs),
+    /// Print a value from a save
+    Get(edit::GetArgs),
+    /// Write the translations of a .po or .csv file into the game data
+    Inject(translate::InjectArgs),
+    /// Read saves from a browser or NW.js localStorage database
+    LocalStorage(local_storage::Args),
+    /// Compress a directory of .json files back into saves
+    Pack(unpack::PackArgs),
+    /// List or extract the files bundled in package.nw or an Enigma Virtual
+    /// Box executable
+    Package(package::Args),
+    /// Apply JSON Patch files to a save
+    Patch(edit::PatchArgs),
+    /// List the plugins in plugins.js, or turn them on and off and set
+    /// their parameters
+    Plugins(plugins::Args),
+    /// Set a value in a save
+    Set(edit::SetArgs),
+    /// Write the saves in a directory out as pretty printed .json files
+    Unpack(unpack::UnpackArgs),
+    /// Check a save for references to things missing from the game data
+    Validate(validate::Args),
+    /// Find the events that use a switch, variable, self switch or item
+    Xref(xref::Args),
+}
+
+/// Save format as a command line value
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Target {
+    Mv,
+    Mz,
+}
+impl From<Target> for Format {
+    fn from(value: Target) -> Self {
+        match value {
+            Target::Mv => Self::Mv,
+            Target::Mz => Self::Mz,
+        }
+    }
+}
+
+fn read_save(path: &Path) -> AnyResult<Json> {
+    Json::read_file(path).map_err(|e| format!("Failed to read {}: {e}", path.display()).into())
+}
+
+fn main() -> AnyResult<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Command::Convert(args) => convert::run(args),
+        Command::Decrypt(args) => decrypt::run(args),
+        Command::Delete(args) => edit::delete(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Events(args) => events::run(args),
+        Command::Extract(args) => translate::extract(args),
+        Command::Get(args) => edit::get(args),
+        Command::Inject(args) => translate::inject(args),
+        Command::LocalStorage(args) => local_storage::run(args),
+        Command::Pack(args) => unpack::pack(args),
+        Command::Package(args) => package::run(args),
+        Command::Patch(args) => edit::patch(args),
+        Command::Plugins(args) => plugins::run(args),
+        Command::Set(args) => edit::set(args),
+        Command::Unpack(args) => unpack::unpack(args),
+        Command::Validate(args) => validate::run(args),
+        Command::Xref(args) => xref::run(args),
+    }
+}
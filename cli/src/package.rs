@@ -0,0 +1,43 @@
+use crate::AnyResult;
+use lib::package::Package;
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// package.nw, or an executable with the game bundled in it
+    package: PathBuf,
+
+    /// Directory to extract the files into. Without it, the files are listed.
+    out_dir: Option<PathBuf>,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let package = Package::open(&args.package)?;
+
+    let Some(out_dir) = args.out_dir else {
+        let mut out = stdout().lock();
+        for path in package.files() {
+            writeln!(out, "{path}")?;
+        }
+        return Ok(());
+    };
+
+    let report = package.extract(&out_dir)?;
+    eprintln!(
+        "Extracted {} files into {}",
+        report.written.len(),
+        out_dir.display()
+    );
+    for (path, reason) in &report.failed {
+        eprintln!("Failed {path}: {reason}");
+    }
+
+    if report.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} files couldn't be extracted", report.failed.len()).into())
+    }
+}
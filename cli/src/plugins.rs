@@ -0,0 +1,72 @@
+use crate::AnyResult;
+use lib::plugins::Plugins;
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Turn a plugin on
+    #[arg(long, value_name = "PLUGIN")]
+    enable: Vec<String>,
+
+    /// Turn a plugin off
+    #[arg(long, value_name = "PLUGIN")]
+    disable: Vec<String>,
+
+    /// Set a parameter: `Plugin:param=value`
+    #[arg(long, value_name = "PLUGIN:PARAM=VALUE")]
+    set: Vec<String>,
+
+    /// Print the plugins as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// Path to js/plugins.js
+    file: PathBuf,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let mut plugins = Plugins::read_file(&args.file)?;
+
+    for name in &args.enable {
+        plugins.set_status(name, true)?;
+    }
+    for name in &args.disable {
+        plugins.set_status(name, false)?;
+    }
+    for assignment in &args.set {
+        let (plugin, rest) = assignment
+            .split_once(':')
+            .ok_or_else(|| format!("Expected Plugin:param=value, got {assignment}"))?;
+        let (param, value) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("Expected Plugin:param=value, got {assignment}"))?;
+        plugins.get_mut(plugin)?.set_parameter(param, value);
+    }
+
+    if !args.enable.is_empty() || !args.disable.is_empty() || !args.set.is_empty() {
+        plugins.write_file(&args.file)?;
+        eprintln!("Wrote {}", args.file.display());
+        return Ok(());
+    }
+
+    let mut out = stdout().lock();
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &plugins.plugins)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    for plugin in &plugins.plugins {
+        let status = if plugin.status { "on " } else { "off" };
+        writeln!(out, "[{status}] {} - {}", plugin.name, plugin.description)?;
+        for (name, value) in &plugin.parameters {
+            let value = value
+                .as_str()
+                .map_or_else(|| value.to_string(), str::to_string);
+            writeln!(out, "        {name} = {value}")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::AnyResult;
+use lib::translation::{self, csv, po};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, clap::Args)]
+pub struct ExtractArgs {
+    /// Game data directory (www/data)
+    #[arg(long, short = 'd')]
+    data: PathBuf,
+
+    /// .po or .csv file to write
+    output: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct InjectArgs {
+    /// Game data directory (www/data)
+    #[arg(long, short = 'd')]
+    data: PathBuf,
+
+    /// Directory to write the translated files into, instead of replacing
+    /// them in the data directory
+    #[arg(long, short = 'o')]
+    out: Option<PathBuf>,
+
+    /// Translated .po or .csv file
+    input: PathBuf,
+}
+
+fn is_csv(path: &Path) -> AnyResult<bool> {
+    match path.extension().and_then(|x| x.to_str()) {
+        Some(x) if x.eq_ignore_ascii_case("csv") => Ok(true),
+        Some(x) if x.eq_ignore_ascii_case("po") || x.eq_ignore_ascii_case("pot") => Ok(false),
+        _ => Err(format!("{} isn't a .po or .csv file", path.display()).into()),
+    }
+}
+
+pub fn extract(args: ExtractArgs) -> AnyResult<()> {
+    let entries = translation::extract(&args.data)?;
+    let text = if is_csv(&args.output)? {
+        csv::write(&entries)
+    } else {
+        po::write(&entries)
+    };
+    std::fs::write(&args.output, text)?;
+
+    eprintln!(
+        "Wrote {} strings to {}",
+        entries.len(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+pub fn inject(args: InjectArgs) -> AnyResult<()> {
+    let text = std::fs::read_to_string(&args.input)?;
+    let translations = if is_csv(&args.input)? {
+        csv::read(&text)?
+    } else {
+        po::read(&text)?
+    };
+
+    let out = args.out.as_deref().unwrap_or(&args.data);
+    let report = translation::inject(&args.data, out, &translations)?;
+
+    for path in &report.written {
+        eprintln!("Wrote {}", path.display());
+    }
+    for context in &report.outdated {
+        eprintln!("Skipped {context}: the original text has changed");
+    }
+    for context in &report.missing {
+        eprintln!("Skipped {context}: no longer in the data");
+    }
+    for context in &report.duplicated {
+        eprintln!("{context} is translated more than once, used the last one");
+    }
+    eprintln!("Applied {} translations", report.applied);
+    Ok(())
+}
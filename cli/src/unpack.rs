@@ -0,0 +1,39 @@
+use crate::{AnyResult, Target};
+use lib::save::unpack::{pack_dir, unpack_dir};
+use std::path::PathBuf;
+
+#[derive(Debug, clap::Args)]
+pub struct UnpackArgs {
+    /// Directory with global, config and fileN saves
+    save_dir: PathBuf,
+
+    /// Directory to write the .json files into
+    json_dir: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PackArgs {
+    /// Format of the written saves
+    #[arg(long, value_enum, default_value = "mv")]
+    format: Target,
+
+    /// Directory with global.json, config.json and fileN.json
+    json_dir: PathBuf,
+
+    /// Directory to write the saves into
+    save_dir: PathBuf,
+}
+
+pub fn unpack(args: UnpackArgs) -> AnyResult<()> {
+    for path in unpack_dir(&args.save_dir, &args.json_dir)? {
+        eprintln!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+pub fn pack(args: PackArgs) -> AnyResult<()> {
+    for path in pack_dir(&args.json_dir, &args.save_dir, args.format.into())? {
+        eprintln!("Wrote {}", path.display());
+    }
+    Ok(())
+}
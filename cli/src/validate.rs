@@ -0,0 +1,53 @@
+use crate::{AnyResult, read_save};
+use lib::{
+    data::{DataSource, Database},
+    save::validate::{fix, validate},
+};
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// Game data directory (www/data), or the game's directory for games
+    /// bundled in a package
+    #[arg(long, short = 'd')]
+    data: PathBuf,
+
+    /// Strip invalid references and write the save back
+    #[arg(long)]
+    fix: bool,
+
+    /// Print the issues as JSON
+    #[arg(long)]
+    json: bool,
+
+    save: PathBuf,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let source = DataSource::open(&args.data);
+    let db = Database::load_from(&source)?;
+    let mut save = read_save(&args.save)?;
+
+    let issues = validate(&save, &db, &source);
+
+    let mut out = stdout().lock();
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &issues)?;
+        writeln!(out)?;
+    } else {
+        for issue in &issues {
+            writeln!(out, "{issue}")?;
+        }
+    }
+
+    if args.fix && !issues.is_empty() {
+        let fixed = fix(&mut save, &issues);
+        save.write_file(&args.save)?;
+        eprintln!("Fixed {fixed} of {} issues", issues.len());
+    }
+
+    Ok(())
+}
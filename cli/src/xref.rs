@@ -0,0 +1,72 @@
+use crate::AnyResult;
+use lib::data::{
+    Database,
+    xref::{Index, Target},
+};
+use std::{
+    io::{Write, stdout},
+    path::PathBuf,
+};
+
+#[derive(Debug, clap::Args)]
+#[command(group(clap::ArgGroup::new("target").required(true)))]
+pub struct Args {
+    /// Game data directory (www/data)
+    #[arg(long, short = 'd')]
+    data: PathBuf,
+
+    #[arg(long, group = "target")]
+    switch: Option<u32>,
+
+    #[arg(long, group = "target")]
+    variable: Option<u32>,
+
+    /// A to D
+    #[arg(long, group = "target")]
+    self_switch: Option<String>,
+
+    #[arg(long, group = "target")]
+    item: Option<u32>,
+
+    /// Print the references as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub fn run(args: Args) -> AnyResult<()> {
+    let target = if let Some(id) = args.switch {
+        Target::Switch(id)
+    } else if let Some(id) = args.variable {
+        Target::Variable(id)
+    } else if let Some(ch) = args.self_switch {
+        Target::SelfSwitch(ch.to_uppercase())
+    } else if let Some(id) = args.item {
+        Target::Item(id)
+    } else {
+        unreachable!("clap requires a target")
+    };
+
+    let db = Database::load(&args.data)?;
+    let index = Index::build(&args.data, &db);
+    for (map_id, e) in &index.unreadable_maps {
+        eprintln!("Skipped Map{map_id:03}: {e}");
+    }
+    for command in &index.unreadable_commands {
+        eprintln!("Skipped {command}");
+    }
+
+    let references = index.find(&target).collect::<Vec<_>>();
+    let mut out = stdout().lock();
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &references)?;
+        writeln!(out)?;
+    } else if references.is_empty() {
+        writeln!(out, "No references to {target}")?;
+    } else {
+        for reference in references {
+            writeln!(out, "{reference}")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1327 @@
+//! Event commands decoded from their numeric codes and parameter arrays, the
+//! layouts `Game_Interpreter` reads them with.
+//!
+//! Commands that span several lines (text, comments, scripts, choices and
+//! branches) stay one line per [`EventCommand`], the way they're stored.
+
+use super::{
+    common::AudioFile,
+    event::{EventCommand, MoveCommand, MoveRoute},
+};
+use crate::error::Error;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Reads the parameters of one command, with errors naming the code and index
+struct Params<'a> {
+    code: u32,
+    values: &'a [Value],
+}
+impl Params<'_> {
+    fn error(&self, i: usize, expected: &str) -> Error {
+        Error::Data(format!(
+            "Command {}: parameter {i} should be {expected}",
+            self.code
+        ))
+    }
+
+    fn get(&self, i: usize) -> Result<&Value, Error> {
+        self.values
+            .get(i)
+            .ok_or_else(|| Error::Data(format!("Command {}: missing parameter {i}", self.code)))
+    }
+
+    fn int(&self, i: usize) -> Result<i64, Error> {
+        let value = self.get(i)?;
+        value
+            .as_i64()
+            .or_else(|| value.as_f64().map(|x| x as i64))
+            .ok_or_else(|| self.error(i, "a number"))
+    }
+
+    /// For parameters that older versions of the editor didn't write
+    fn int_or(&self, i: usize, default: i64) -> Result<i64, Error> {
+        match self.values.get(i) {
+            None | Some(Value::Null) => Ok(default),
+            Some(_) => self.int(i),
+        }
+    }
+
+    fn id(&self, i: usize) -> Result<u32, Error> {
+        u32::try_from(self.int(i)?).map_err(|_| self.error(i, "an id"))
+    }
+
+    fn bool(&self, i: usize) -> Result<bool, Error> {
+        match self.get(i)? {
+            Value::Bool(x) => Ok(*x),
+            Value::Number(_) => Ok(self.int(i)? != 0),
+            _ => Err(self.error(i, "a bool")),
+        }
+    }
+
+    fn bool_or(&self, i: usize, default: bool) -> Result<bool, Error> {
+        match self.values.get(i) {
+            None | Some(Value::Null) => Ok(default),
+            Some(_) => self.bool(i),
+        }
+    }
+
+    /// Switches store ON as 0 and OFF as 1
+    fn on(&self, i: usize) -> Result<bool, Error> {
+        Ok(self.int(i)? == 0)
+    }
+
+    fn string(&self, i: usize) -> Result<String, Error> {
+        self.get(i)?
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| self.error(i, "a string"))
+    }
+
+    fn decode<T: DeserializeOwned>(&self, i: usize) -> Result<T, Error> {
+        T::deserialize(self.get(i)?)
+            .map_err(|e| Error::Data(format!("Command {}: parameter {i}: {e}", self.code)))
+    }
+
+    /// `(operand type, operand)`, with type 0 a constant and 1 a variable
+    fn operand(&self, i: usize) -> Result<Operand, Error> {
+        match self.int(i)? {
+            0 => Ok(Operand::Constant(self.int(i + 1)?)),
+            _ => Ok(Operand::Variable(self.id(i + 1)?)),
+        }
+    }
+
+    /// `(designation, id)`, with designation 0 a fixed actor and 1 a variable
+    fn actor(&self, i: usize) -> Result<ActorRef, Error> {
+        match self.int(i)? {
+            0 => Ok(ActorRef::Fixed(self.id(i + 1)?)),
+            _ => Ok(ActorRef::Variable(self.id(i + 1)?)),
+        }
+    }
+
+    /// `(designation, x, y)`. Designation 2 means another character, whose
+    /// id is in place of x.
+    fn position(&self, i: usize) -> Result<Position, Error> {
+        match self.int(i)? {
+            0 => Ok(Position::Direct {
+                x: self.int(i + 1)?,
+                y: self.int(i + 2)?,
+            }),
+            1 => Ok(Position::Variables {
+                x: self.id(i + 1)?,
+                y: self.id(i + 2)?,
+            }),
+            _ => Ok(Position::Character(self.int(i + 1)?)),
+        }
+    }
+
+    /// `(designation, map id, x, y)`
+    fn location(&self, i: usize) -> Result<Location, Error> {
+        match self.int(i)? {
+            0 => Ok(Location::Direct {
+                map_id: self.id(i + 1)?,
+                x: self.int(i + 2)?,
+                y: self.int(i + 3)?,
+            }),
+            _ => Ok(Location::Variables {
+                map_id: self.id(i + 1)?,
+                x: self.id(i + 2)?,
+                y: self.id(i + 3)?,
+            }),
+        }
+    }
+
+    fn comparison(&self, i: usize, table: &[Comparison]) -> Result<Comparison, Error> {
+        usize::try_from(self.int(i)?)
+            .ok()
+            .and_then(|x| table.get(x).copied())
+            .ok_or_else(|| self.error(i, "a comparison"))
+    }
+}
+
+/// A number given directly or read from a variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Constant(i64),
+    Variable(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    GreaterOrEqual,
+    LessOrEqual,
+    Greater,
+    Less,
+    NotEqual,
+}
+impl Comparison {
+    /// Conditional Branch on a variable
+    const VARIABLE: &[Self] = &[
+        Self::Equal,
+        Self::GreaterOrEqual,
+        Self::LessOrEqual,
+        Self::Greater,
+        Self::Less,
+        Self::NotEqual,
+    ];
+    /// Conditional Branch on gold
+    const GOLD: &[Self] = &[Self::GreaterOrEqual, Self::LessOrEqual, Self::Less];
+    /// Conditional Branch on the timer
+    const TIMER: &[Self] = &[Self::GreaterOrEqual, Self::LessOrEqual];
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Equal => "=",
+            Self::GreaterOrEqual => "≥",
+            Self::LessOrEqual => "≤",
+            Self::Greater => ">",
+            Self::Less => "<",
+            Self::NotEqual => "≠",
+        }
+    }
+}
+
+/// Actor targeted by Change HP, Change State and similar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActorRef {
+    /// 0 is the entire party
+    Fixed(u32),
+    Variable(u32),
+}
+
+/// A position on the current map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Direct {
+        x: i64,
+        y: i64,
+    },
+    /// Variable ids holding x and y
+    Variables {
+        x: u32,
+        y: u32,
+    },
+    /// Where a character is: -1 the player, 0 this event, otherwise an event id
+    Character(i64),
+}
+
+/// A position on any map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Direct {
+        map_id: u32,
+        x: i64,
+        y: i64,
+    },
+    /// Variable ids holding the map id, x and y
+    Variables {
+        map_id: u32,
+        x: u32,
+        y: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActorCheck {
+    InParty,
+    Name(String),
+    Class(u32),
+    Skill(u32),
+    Weapon(u32),
+    Armor(u32),
+    State(u32),
+}
+
+/// Condition of a Conditional Branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Switch {
+        switch_id: u32,
+        on: bool,
+    },
+    Variable {
+        variable_id: u32,
+        comparison: Comparison,
+        operand: Operand,
+    },
+    SelfSwitch {
+        ch: String,
+        on: bool,
+    },
+    Timer {
+        comparison: Comparison,
+        seconds: i64,
+    },
+    Actor {
+        actor_id: u32,
+        check: ActorCheck,
+    },
+    /// Appeared if `state_id` is `None`, otherwise affected by the state
+    Enemy {
+        enemy_index: i64,
+        state_id: Option<u32>,
+    },
+    Character {
+        character_id: i64,
+        direction: u32,
+    },
+    Gold {
+        comparison: Comparison,
+        amount: i64,
+    },
+    Item(u32),
+    Weapon {
+        weapon_id: u32,
+        include_equipment: bool,
+    },
+    Armor {
+        armor_id: u32,
+        include_equipment: bool,
+    },
+    Button(String),
+    Script(String),
+    Vehicle(u32),
+}
+impl Condition {
+    fn decode(p: &Params) -> Result<Self, Error> {
+        Ok(match p.int(0)? {
+            0 => Self::Switch {
+                switch_id: p.id(1)?,
+                on: p.on(2)?,
+            },
+            1 => Self::Variable {
+                variable_id: p.id(1)?,
+                operand: p.operand(2)?,
+                comparison: p.comparison(4, Comparison::VARIABLE)?,
+            },
+            2 => Self::SelfSwitch {
+                ch: p.string(1)?,
+                on: p.on(2)?,
+            },
+            3 => Self::Timer {
+                comparison: p.comparison(1, Comparison::TIMER)?,
+                seconds: p.int(2)?,
+            },
+            4 => {
+                let actor_id = p.id(1)?;
+                let check = match p.int(2)? {
+                    0 => ActorCheck::InParty,
+                    1 => ActorCheck::Name(p.string(3)?),
+                    2 => ActorCheck::Class(p.id(3)?),
+                    3 => ActorCheck::Skill(p.id(3)?),
+                    4 => ActorCheck::Weapon(p.id(3)?),
+                    5 => ActorCheck::Armor(p.id(3)?),
+                    _ => ActorCheck::State(p.id(3)?),
+                };
+                Self::Actor { actor_id, check }
+            }
+            5 => Self::Enemy {
+                enemy_index: p.int(1)?,
+                state_id: match p.int(2)? {
+                    0 => None,
+                    _ => Some(p.id(3)?),
+                },
+            },
+            6 => Self::Character {
+                character_id: p.int(1)?,
+                direction: p.id(2)?,
+            },
+            7 => Self::Gold {
+                amount: p.int(1)?,
+                comparison: p.comparison(2, Comparison::GOLD)?,
+            },
+            8 => Self::Item(p.id(1)?),
+            9 => Self::Weapon {
+                weapon_id: p.id(1)?,
+                include_equipment: p.bool_or(2, false)?,
+            },
+            10 => Self::Armor {
+                armor_id: p.id(1)?,
+                include_equipment: p.bool_or(2, false)?,
+            },
+            11 => Self::Button(p.string(1)?),
+            12 => Self::Script(p.string(1)?),
+            13 => Self::Vehicle(p.id(1)?),
+            _ => return Err(p.error(0, "a condition type")),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableOperation {
+    Set,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+impl VariableOperation {
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Set => "=",
+            Self::Add => "+=",
+            Self::Sub => "-=",
+            Self::Mul => "*=",
+            Self::Div => "/=",
+            Self::Mod => "%=",
+        }
+    }
+}
+
+/// Right hand side of Control Variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VariableOperand {
+    Constant(i64),
+    Variable(u32),
+    Random {
+        min: i64,
+        max: i64,
+    },
+    /// Item counts, actor stats, map data and so on. `kind` picks which and
+    /// the params mean what `Game_Interpreter.gameDataOperand` makes of them.
+    GameData {
+        kind: u32,
+        param1: i64,
+        param2: i64,
+    },
+    Script(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TroopRef {
+    Direct(u32),
+    Variable(u32),
+    /// Same as a random encounter on the current map
+    RandomEncounter,
+}
+
+/// Subject of Force Action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Battler {
+    Enemy(i64),
+    Actor(u32),
+}
+
+/// One entry of a shop's goods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShopGood {
+    /// 0 item, 1 weapon, 2 armor
+    pub kind: u32,
+    pub id: u32,
+    /// `None` for the database price
+    pub price: Option<i64>,
+}
+impl ShopGood {
+    fn decode(p: &Params) -> Result<Self, Error> {
+        Ok(Self {
+            kind: p.id(0)?,
+            id: p.id(1)?,
+            price: match p.int_or(2, 0)? {
+                0 => None,
+                _ => Some(p.int(3)?),
+            },
+        })
+    }
+}
+
+/// Show Picture and Move Picture
+#[derive(Debug, Clone, PartialEq)]
+pub struct PictureSettings {
+    /// 0 upper left, 1 center
+    pub origin: u32,
+    pub position: Position,
+    pub scale_x: i64,
+    pub scale_y: i64,
+    pub opacity: i64,
+    /// 0 normal, 1 additive, 2 multiply, 3 screen
+    pub blend_mode: u32,
+}
+impl PictureSettings {
+    fn decode(p: &Params) -> Result<Self, Error> {
+        Ok(Self {
+            origin: p.id(2)?,
+            position: p.position(3)?,
+            scale_x: p.int(6)?,
+            scale_y: p.int(7)?,
+            opacity: p.int(8)?,
+            blend_mode: p.id(9)?,
+        })
+    }
+}
+
+/// An event command with its parameters decoded. Names follow the editor.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Last command of every list and branch
+    End,
+
+    // Message
+    ShowText {
+        face_name: String,
+        face_index: u32,
+        /// 0 window, 1 dim, 2 transparent
+        background: u32,
+        /// 0 top, 1 middle, 2 bottom
+        position: u32,
+        /// MZ
+        speaker: Option<String>,
+    },
+    /// A line of Show Text
+    Text(String),
+    ShowChoices {
+        choices: Vec<String>,
+        /// -2 own branch, -1 disallowed, otherwise a choice index
+        cancel_type: i64,
+        /// -1 none, otherwise a choice index
+        default_type: i64,
+        /// 0 left, 1 middle, 2 right
+        position: u32,
+        background: u32,
+    },
+    When {
+        index: u32,
+        text: String,
+    },
+    WhenCancel,
+    /// End of Show Choices
+    ChoicesEnd,
+    InputNumber {
+        variable_id: u32,
+        digits: u32,
+    },
+    SelectItem {
+        variable_id: u32,
+        /// 1 regular, 2 key item, 3 and 4 hidden A and B
+        item_type: u32,
+    },
+    ShowScrollingText {
+        speed: i64,
+        no_fast_forward: bool,
+    },
+    /// A line of Show Scrolling Text
+    ScrollingText(String),
+
+    // Flow control
+    Comment(String),
+    /// Following lines of a comment
+    CommentMore(String),
+    If(Condition),
+    Else,
+    /// End of a Conditional Branch
+    EndIf,
+    Loop,
+    RepeatAbove,
+    BreakLoop,
+    ExitEventProcessing,
+    CommonEvent(u32),
+    Label(String),
+    JumpToLabel(String),
+
+    // Game progression
+    ControlSwitches {
+        start: u32,
+        end: u32,
+        on: bool,
+    },
+    ControlVariables {
+        start: u32,
+        end: u32,
+        operation: VariableOperation,
+        operand: VariableOperand,
+    },
+    ControlSelfSwitch {
+        ch: String,
+        on: bool,
+    },
+    /// `None` stops the timer
+    ControlTimer(Option<i64>),
+
+    // Party
+    ChangeGold {
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeItems {
+        item_id: u32,
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeWeapons {
+        weapon_id: u32,
+        increase: bool,
+        amount: Operand,
+        include_equipment: bool,
+    },
+    ChangeArmors {
+        armor_id: u32,
+        increase: bool,
+        amount: Operand,
+        include_equipment: bool,
+    },
+    ChangePartyMember {
+        actor_id: u32,
+        add: bool,
+        initialize: bool,
+    },
+
+    // System settings
+    ChangeBattleBgm(AudioFile),
+    ChangeVictoryMe(AudioFile),
+    ChangeSaveAccess(bool),
+    ChangeMenuAccess(bool),
+    ChangeEncounter(bool),
+    ChangeFormationAccess(bool),
+    ChangeWindowColor(Vec<i64>),
+    ChangeDefeatMe(AudioFile),
+    ChangeVehicleBgm {
+        vehicle: u32,
+        bgm: AudioFile,
+    },
+
+    // Movement
+    TransferPlayer {
+        location: Location,
+        /// 0 retain, otherwise 2 down, 4 left, 6 right, 8 up
+        direction: u32,
+        /// 0 black, 1 white, 2 none
+        fade: u32,
+    },
+    SetVehicleLocation {
+        vehicle: u32,
+        location: Location,
+    },
+    SetEventLocation {
+        character_id: i64,
+        /// [`Position::Character`] exchanges the two events
+        position: Position,
+        direction: u32,
+    },
+    ScrollMap {
+        direction: u32,
+        distance: i64,
+        speed: u32,
+    },
+    SetMovementRoute {
+        character_id: i64,
+        route: MoveRoute,
+    },
+    /// A step of Set Movement Route, shown in the list but not run
+    MoveRouteStep(MoveCommand),
+    GetOnOffVehicle,
+
+    // Character
+    ChangeTransparency(bool),
+    ShowAnimation {
+        character_id: i64,
+        animation_id: u32,
+        wait: bool,
+    },
+    ShowBalloonIcon {
+        character_id: i64,
+        balloon_id: u32,
+        wait: bool,
+    },
+    EraseEvent,
+    ChangePlayerFollowers(bool),
+    GatherFollowers,
+
+    // Screen
+    FadeoutScreen,
+    FadeinScreen,
+    TintScreen {
+        tone: Vec<i64>,
+        duration: i64,
+        wait: bool,
+    },
+    FlashScreen {
+        color: Vec<i64>,
+        duration: i64,
+        wait: bool,
+    },
+    ShakeScreen {
+        power: i64,
+        speed: i64,
+        duration: i64,
+        wait: bool,
+    },
+    Wait(i64),
+
+    // Picture and weather
+    ShowPicture {
+        picture_id: u32,
+        name: String,
+        settings: PictureSettings,
+    },
+    MovePicture {
+        picture_id: u32,
+        settings: PictureSettings,
+        duration: i64,
+        wait: bool,
+    },
+    RotatePicture {
+        picture_id: u32,
+        speed: i64,
+    },
+    TintPicture {
+        picture_id: u32,
+        tone: Vec<i64>,
+        duration: i64,
+        wait: bool,
+    },
+    ErasePicture(u32),
+    SetWeatherEffect {
+        /// "none", "rain", "storm" or "snow"
+        kind: String,
+        power: i64,
+        duration: i64,
+        wait: bool,
+    },
+
+    // Audio and video
+    PlayBgm(AudioFile),
+    FadeoutBgm(i64),
+    SaveBgm,
+    ResumeBgm,
+    PlayBgs(AudioFile),
+    FadeoutBgs(i64),
+    PlayMe(AudioFile),
+    PlaySe(AudioFile),
+    StopSe,
+    PlayMovie(String),
+
+    // Map
+    ChangeMapNameDisplay(bool),
+    ChangeTileset(u32),
+    ChangeBattleBackground {
+        back1: String,
+        back2: String,
+    },
+    ChangeParallax {
+        name: String,
+        loop_x: bool,
+        loop_y: bool,
+        scroll_x: i64,
+        scroll_y: i64,
+    },
+    GetLocationInfo {
+        variable_id: u32,
+        /// 0 terrain tag, 1 event id, 2-5 tile id of layers 1-4, 6 region id
+        info_type: u32,
+        position: Position,
+    },
+
+    // Scene control
+    BattleProcessing {
+        troop: TroopRef,
+        can_escape: bool,
+        can_lose: bool,
+    },
+    IfWin,
+    IfEscape,
+    IfLose,
+    /// End of Battle Processing's branches
+    BattleEnd,
+    ShopProcessing {
+        good: ShopGood,
+        purchase_only: bool,
+    },
+    /// Further goods of Shop Processing
+    ShopGood(ShopGood),
+    NameInput {
+        actor_id: u32,
+        max_characters: u32,
+    },
+    OpenMenuScreen,
+    OpenSaveScreen,
+    GameOver,
+    ReturnToTitleScreen,
+
+    // Actor
+    ChangeHp {
+        actor: ActorRef,
+        increase: bool,
+        amount: Operand,
+        allow_knockout: bool,
+    },
+    ChangeMp {
+        actor: ActorRef,
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeTp {
+        actor: ActorRef,
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeState {
+        actor: ActorRef,
+        add: bool,
+        state_id: u32,
+    },
+    RecoverAll(ActorRef),
+    ChangeExp {
+        actor: ActorRef,
+        increase: bool,
+        amount: Operand,
+        show_level_up: bool,
+    },
+    ChangeLevel {
+        actor: ActorRef,
+        increase: bool,
+        amount: Operand,
+        show_level_up: bool,
+    },
+    ChangeParameter {
+        actor: ActorRef,
+        param_id: u32,
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeSkill {
+        actor: ActorRef,
+        learn: bool,
+        skill_id: u32,
+    },
+    ChangeEquipment {
+        actor_id: u32,
+        etype_id: u32,
+        item_id: u32,
+    },
+    ChangeName {
+        actor_id: u32,
+        name: String,
+    },
+    ChangeClass {
+        actor_id: u32,
+        class_id: u32,
+        keep_exp: bool,
+    },
+    ChangeActorImages {
+        actor_id: u32,
+        character_name: String,
+        character_index: u32,
+        face_name: String,
+        face_index: u32,
+        battler_name: String,
+    },
+    ChangeVehicleImage {
+        vehicle: u32,
+        character_name: String,
+        character_index: u32,
+    },
+    ChangeNickname {
+        actor_id: u32,
+        nickname: String,
+    },
+    ChangeProfile {
+        actor_id: u32,
+        profile: String,
+    },
+
+    // Battle, where enemy index -1 is the entire troop
+    ChangeEnemyHp {
+        enemy_index: i64,
+        increase: bool,
+        amount: Operand,
+        allow_knockout: bool,
+    },
+    ChangeEnemyMp {
+        enemy_index: i64,
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeEnemyTp {
+        enemy_index: i64,
+        increase: bool,
+        amount: Operand,
+    },
+    ChangeEnemyState {
+        enemy_index: i64,
+        add: bool,
+        state_id: u32,
+    },
+    EnemyRecoverAll(i64),
+    EnemyAppear(i64),
+    EnemyTransform {
+        enemy_index: i64,
+        enemy_id: u32,
+    },
+    ShowBattleAnimation {
+        enemy_index: i64,
+        animation_id: u32,
+        all_enemies: bool,
+    },
+    ForceAction {
+        subject: Battler,
+        skill_id: u32,
+        /// -2 last target, -1 random, otherwise an index
+        target_index: i64,
+    },
+    AbortBattle,
+
+    // Advanced
+    Script(String),
+    /// Following lines of a script
+    ScriptMore(String),
+    /// MV, the whole command line
+    PluginCommand(String),
+    /// MZ
+    PluginCommandMz {
+        plugin: String,
+        command: String,
+        /// Label shown in the editor
+        text: String,
+        args: Value,
+    },
+    /// Argument summary lines of an MZ plugin command
+    PluginCommandMzMore(String),
+
+    /// A code this doesn't know, from a newer engine or a plugin
+    Unknown {
+        code: u32,
+        parameters: Vec<Value>,
+    },
+}
+impl Command {
+    /// Decodes the parameters of `command`. Unknown codes are kept as
+    /// [`Command::Unknown`], only known codes with parameters that don't fit
+    /// their layout are an error.
+    pub fn decode(command: &EventCommand) -> Result<Self, Error> {
+        let p = Params {
+            code: command.code,
+            values: &command.parameters,
+        };
+        let increase = |i: usize| -> Result<bool, Error> { Ok(p.int(i)? == 0) };
+
+        Ok(match command.code {
+            0 => Self::End,
+            101 => Self::ShowText {
+                face_name: p.string(0)?,
+                face_index: p.id(1)?,
+                background: p.int_or(2, 0)? as u32,
+                position: p.int_or(3, 2)? as u32,
+                speaker: p.string(4).ok(),
+            },
+            401 => Self::Text(p.string(0)?),
+            102 => Self::ShowChoices {
+                choices: p.decode(0)?,
+                cancel_type: p.int(1)?,
+                default_type: p.int_or(2, 0)?,
+                position: p.int_or(3, 2)? as u32,
+                background: p.int_or(4, 0)? as u32,
+            },
+            402 => Self::When {
+                index: p.id(0)?,
+                text: p.string(1)?,
+            },
+            403 => Self::WhenCancel,
+            404 => Self::ChoicesEnd,
+            103 => Self::InputNumber {
+                variable_id: p.id(0)?,
+                digits: p.id(1)?,
+            },
+            104 => Self::SelectItem {
+                variable_id: p.id(0)?,
+                item_type: p.int_or(1, 2)? as u32,
+            },
+            105 => Self::ShowScrollingText {
+                speed: p.int(0)?,
+                no_fast_forward: p.bool(1)?,
+            },
+            405 => Self::ScrollingText(p.string(0)?),
+            108 => Self::Comment(p.string(0)?),
+            408 => Self::CommentMore(p.string(0)?),
+            111 => Self::If(Condition::decode(&p)?),
+            411 => Self::Else,
+            412 => Self::EndIf,
+            112 => Self::Loop,
+            413 => Self::RepeatAbove,
+            113 => Self::BreakLoop,
+            115 => Self::ExitEventProcessing,
+            117 => Self::CommonEvent(p.id(0)?),
+            118 => Self::Label(p.string(0)?),
+            119 => Self::JumpToLabel(p.string(0)?),
+            121 => Self::ControlSwitches {
+                start: p.id(0)?,
+                end: p.id(1)?,
+                on: p.on(2)?,
+            },
+            122 => Self::ControlVariables {
+                start: p.id(0)?,
+                end: p.id(1)?,
+                operation: match p.int(2)? {
+                    0 => VariableOperation::Set,
+                    1 => VariableOperation::Add,
+                    2 => VariableOperation::Sub,
+                    3 => VariableOperation::Mul,
+                    4 => VariableOperation::Div,
+                    5 => VariableOperation::Mod,
+                    _ => return Err(p.error(2, "an operation")),
+                },
+                operand: match p.int(3)? {
+                    0 => VariableOperand::Constant(p.int(4)?),
+                    1 => VariableOperand::Variable(p.id(4)?),
+                    2 => VariableOperand::Random {
+                        min: p.int(4)?,
+                        max: p.int(5)?,
+                    },
+                    3 => VariableOperand::GameData {
+                        kind: p.id(4)?,
+                        param1: p.int_or(5, 0)?,
+                        param2: p.int_or(6, 0)?,
+                    },
+                    4 => VariableOperand::Script(p.string(4)?),
+                    _ => return Err(p.error(3, "an operand type")),
+                },
+            },
+            123 => Self::ControlSelfSwitch {
+                ch: p.string(0)?,
+                on: p.on(1)?,
+            },
+            124 => Self::ControlTimer(match p.int(0)? {
+                0 => Some(p.int(1)?),
+                _ => None,
+            }),
+            125 => Self::ChangeGold {
+                increase: increase(0)?,
+                amount: p.operand(1)?,
+            },
+            126 => Self::ChangeItems {
+                item_id: p.id(0)?,
+                increase: increase(1)?,
+                amount: p.operand(2)?,
+            },
+            127 => Self::ChangeWeapons {
+                weapon_id: p.id(0)?,
+                increase: increase(1)?,
+                amount: p.operand(2)?,
+                include_equipment: p.bool_or(4, false)?,
+            },
+            128 => Self::ChangeArmors {
+                armor_id: p.id(0)?,
+                increase: increase(1)?,
+                amount: p.operand(2)?,
+                include_equipment: p.bool_or(4, false)?,
+            },
+            129 => Self::ChangePartyMember {
+                actor_id: p.id(0)?,
+                add: p.int(1)? == 0,
+                initialize: p.bool_or(2, false)?,
+            },
+            132 => Self::ChangeBattleBgm(p.decode(0)?),
+            133 => Self::ChangeVictoryMe(p.decode(0)?),
+            134 => Self::ChangeSaveAccess(p.int(0)? == 1),
+            135 => Self::ChangeMenuAccess(p.int(0)? == 1),
+            136 => Self::ChangeEncounter(p.int(0)? == 1),
+            137 => Self::ChangeFormationAccess(p.int(0)? == 1),
+            138 => Self::ChangeWindowColor(p.decode(0)?),
+            139 => Self::ChangeDefeatMe(p.decode(0)?),
+            140 => Self::ChangeVehicleBgm {
+                vehicle: p.id(0)?,
+                bgm: p.decode(1)?,
+            },
+            201 => Self::TransferPlayer {
+                location: p.location(0)?,
+                direction: p.int_or(4, 0)? as u32,
+                fade: p.int_or(5, 0)? as u32,
+            },
+            202 => Self::SetVehicleLocation {
+                vehicle: p.id(0)?,
+                location: p.location(1)?,
+            },
+            203 => Self::SetEventLocation {
+                character_id: p.int(0)?,
+                position: p.position(1)?,
+                direction: p.int_or(4, 0)? as u32,
+            },
+            204 => Self::ScrollMap {
+                direction: p.id(0)?,
+                distance: p.int(1)?,
+                speed: p.id(2)?,
+            },
+            205 => Self::SetMovementRoute {
+                character_id: p.int(0)?,
+                route: p.decode(1)?,
+            },
+            505 => Self::MoveRouteStep(p.decode(0)?),
+            206 => Self::GetOnOffVehicle,
+            211 => Self::ChangeTransparency(p.on(0)?),
+            212 => Self::ShowAnimation {
+                character_id: p.int(0)?,
+                animation_id: p.id(1)?,
+                wait: p.bool(2)?,
+            },
+            213 => Self::ShowBalloonIcon {
+                character_id: p.int(0)?,
+                balloon_id: p.id(1)?,
+                wait: p.bool(2)?,
+            },
+            214 => Self::EraseEvent,
+            216 => Self::ChangePlayerFollowers(p.on(0)?),
+            217 => Self::GatherFollowers,
+            221 => Self::FadeoutScreen,
+            222 => Self::FadeinScreen,
+            223 => Self::TintScreen {
+                tone: p.decode(0)?,
+                duration: p.int(1)?,
+                wait: p.bool(2)?,
+            },
+            224 => Self::FlashScreen {
+                color: p.decode(0)?,
+                duration: p.int(1)?,
+                wait: p.bool(2)?,
+            },
+            225 => Self::ShakeScreen {
+                power: p.int(0)?,
+                speed: p.int(1)?,
+                duration: p.int(2)?,
+                wait: p.bool(3)?,
+            },
+            230 => Self::Wait(p.int(0)?),
+            231 => Self::ShowPicture {
+                picture_id: p.id(0)?,
+                name: p.string(1)?,
+                settings: PictureSettings::decode(&p)?,
+            },
+            232 => Self::MovePicture {
+                picture_id: p.id(0)?,
+                settings: PictureSettings::decode(&p)?,
+                duration: p.int(10)?,
+                wait: p.bool(11)?,
+            },
+            233 => Self::RotatePicture {
+                picture_id: p.id(0)?,
+                speed: p.int(1)?,
+            },
+            234 => Self::TintPicture {
+                picture_id: p.id(0)?,
+                tone: p.decode(1)?,
+                duration: p.int(2)?,
+                wait: p.bool(3)?,
+            },
+            235 => Self::ErasePicture(p.id(0)?),
+            236 => Self::SetWeatherEffect {
+                kind: p.string(0)?,
+                power: p.int(1)?,
+                duration: p.int(2)?,
+                wait: p.bool(3)?,
+            },
+            241 => Self::PlayBgm(p.decode(0)?),
+            242 => Self::FadeoutBgm(p.int(0)?),
+            243 => Self::SaveBgm,
+            244 => Self::ResumeBgm,
+            245 => Self::PlayBgs(p.decode(0)?),
+            246 => Self::FadeoutBgs(p.int(0)?),
+            249 => Self::PlayMe(p.decode(0)?),
+            250 => Self::PlaySe(p.decode(0)?),
+            251 => Self::StopSe,
+            261 => Self::PlayMovie(p.string(0)?),
+            281 => Self::ChangeMapNameDisplay(p.on(0)?),
+            282 => Self::ChangeTileset(p.id(0)?),
+            283 => Self::ChangeBattleBackground {
+                back1: p.string(0)?,
+                back2: p.string(1)?,
+            },
+            284 => Self::ChangeParallax {
+                name: p.string(0)?,
+                loop_x: p.bool(1)?,
+                loop_y: p.bool(2)?,
+                scroll_x: p.int(3)?,
+                scroll_y: p.int(4)?,
+            },
+            285 => Self::GetLocationInfo {
+                variable_id: p.id(0)?,
+                info_type: p.id(1)?,
+                position: p.position(2)?,
+            },
+            301 => Self::BattleProcessing {
+                troop: match p.int(0)? {
+                    0 => TroopRef::Direct(p.id(1)?),
+                    1 => TroopRef::Variable(p.id(1)?),
+                    _ => TroopRef::RandomEncounter,
+                },
+                can_escape: p.bool(2)?,
+                can_lose: p.bool(3)?,
+            },
+            601 => Self::IfWin,
+            602 => Self::IfEscape,
+            603 => Self::IfLose,
+            604 => Self::BattleEnd,
+            302 => Self::ShopProcessing {
+                good: ShopGood::decode(&p)?,
+                purchase_only: p.bool_or(4, false)?,
+            },
+            605 => Self::ShopGood(ShopGood::decode(&p)?),
+            303 => Self::NameInput {
+                actor_id: p.id(0)?,
+                max_characters: p.id(1)?,
+            },
+            311 => Self::ChangeHp {
+                actor: p.actor(0)?,
+                increase: increase(2)?,
+                amount: p.operand(3)?,
+                allow_knockout: p.bool_or(5, false)?,
+            },
+            312 => Self::ChangeMp {
+                actor: p.actor(0)?,
+                increase: increase(2)?,
+                amount: p.operand(3)?,
+            },
+            326 => Self::ChangeTp {
+                actor: p.actor(0)?,
+                increase: increase(2)?,
+                amount: p.operand(3)?,
+            },
+            313 => Self::ChangeState {
+                actor: p.actor(0)?,
+                add: p.int(2)? == 0,
+                state_id: p.id(3)?,
+            },
+            314 => Self::RecoverAll(p.actor(0)?),
+            315 => Self::ChangeExp {
+                actor: p.actor(0)?,
+                increase: increase(2)?,
+                amount: p.operand(3)?,
+                show_level_up: p.bool_or(5, false)?,
+            },
+            316 => Self::ChangeLevel {
+                actor: p.actor(0)?,
+                increase: increase(2)?,
+                amount: p.operand(3)?,
+                show_level_up: p.bool_or(5, false)?,
+            },
+            317 => Self::ChangeParameter {
+                actor: p.actor(0)?,
+                param_id: p.id(2)?,
+                increase: increase(3)?,
+                amount: p.operand(4)?,
+            },
+            318 => Self::ChangeSkill {
+                actor: p.actor(0)?,
+                learn: p.int(2)? == 0,
+                skill_id: p.id(3)?,
+            },
+            319 => Self::ChangeEquipment {
+                actor_id: p.id(0)?,
+                etype_id: p.id(1)?,
+                item_id: p.id(2)?,
+            },
+            320 => Self::ChangeName {
+                actor_id: p.id(0)?,
+                name: p.string(1)?,
+            },
+            321 => Self::ChangeClass {
+                actor_id: p.id(0)?,
+                class_id: p.id(1)?,
+                keep_exp: p.bool_or(2, false)?,
+            },
+            322 => Self::ChangeActorImages {
+                actor_id: p.id(0)?,
+                character_name: p.string(1)?,
+                character_index: p.id(2)?,
+                face_name: p.string(3)?,
+                face_index: p.id(4)?,
+                battler_name: p.string(5)?,
+            },
+            323 => Self::ChangeVehicleImage {
+                vehicle: p.id(0)?,
+                character_name: p.string(1)?,
+                character_index: p.id(2)?,
+            },
+            324 => Self::ChangeNickname {
+                actor_id: p.id(0)?,
+                nickname: p.string(1)?,
+            },
+            325 => Self::ChangeProfile {
+                actor_id: p.id(0)?,
+                profile: p.string(1)?,
+            },
+            331 => Self::ChangeEnemyHp {
+                enemy_index: p.int(0)?,
+                increase: increase(1)?,
+                amount: p.operand(2)?,
+                allow_knockout: p.bool_or(4, false)?,
+            },
+            332 => Self::ChangeEnemyMp {
+                enemy_index: p.int(0)?,
+                increase: increase(1)?,
+                amount: p.operand(2)?,
+            },
+            342 => Self::ChangeEnemyTp {
+                enemy_index: p.int(0)?,
+                increase: increase(1)?,
+                amount: p.operand(2)?,
+            },
+            333 => Self::ChangeEnemyState {
+                enemy_index: p.int(0)?,
+                add: p.int(1)? == 0,
+                state_id: p.id(2)?,
+            },
+            334 => Self::EnemyRecoverAll(p.int(0)?),
+            335 => Self::EnemyAppear(p.int(0)?),
+            336 => Self::EnemyTransform {
+                enemy_index: p.int(0)?,
+                enemy_id: p.id(1)?,
+            },
+            337 => Self::ShowBattleAnimation {
+                enemy_index: p.int(0)?,
+                animation_id: p.id(1)?,
+                all_enemies: p.bool_or(2, false)?,
+            },
+            339 => Self::ForceAction {
+                subject: match p.int(0)? {
+                    0 => Battler::Enemy(p.int(1)?),
+                    _ => Battler::Actor(p.id(1)?),
+                },
+                skill_id: p.id(2)?,
+                target_index: p.int(3)?,
+            },
+            340 => Self::AbortBattle,
+            351 => Self::OpenMenuScreen,
+            352 => Self::OpenSaveScreen,
+            353 => Self::GameOver,
+            354 => Self::ReturnToTitleScreen,
+            355 => Self::Script(p.string(0)?),
+            655 => Self::ScriptMore(p.string(0)?),
+            356 => Self::PluginCommand(p.string(0)?),
+            357 => Self::PluginCommandMz {
+                plugin: p.string(0)?,
+                command: p.string(1)?,
+                text: p.string(2).unwrap_or_default(),
+                args: p.get(3).cloned().unwrap_or_default(),
+            },
+            657 => Self::PluginCommandMzMore(p.string(0)?),
+            code => Self::Unknown {
+                code,
+                parameters: command.parameters.clone(),
+            },
+        })
+    }
+
+    /// Whether this only continues the command above it (text lines, choice
+    /// branches, ends of blocks), which the editor doesn't mark with a ◆
+    pub fn is_continuation(&self) -> bool {
+        matches!(
+            self,
+            Self::Text(_)
+                | Self::ScrollingText(_)
+                | Self::CommentMore(_)
+                | Self::ScriptMore(_)
+                | Self::PluginCommandMzMore(_)
+                | Self::When { .. }
+                | Self::WhenCancel
+                | Self::ChoicesEnd
+                | Self::Else
+                | Self::EndIf
+                | Self::RepeatAbove
+                | Self::MoveRouteStep(_)
+                | Self::IfWin
+                | Self::IfEscape
+                | Self::IfLose
+                | Self::BattleEnd
+                | Self::ShopGood(_)
+        )
+    }
+}
@@ -0,0 +1,45 @@
+//! Pieces shared by several database files.
+
+use serde::{Deserialize, Serialize};
+
+/// `RPG.Trait`, on actors, classes, equipment, enemies and states
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Trait {
+    pub code: u32,
+    pub data_id: i32,
+    pub value: f64,
+}
+
+/// `RPG.Effect`, on skills and items
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Effect {
+    pub code: u32,
+    pub data_id: i32,
+    pub value1: f64,
+    pub value2: f64,
+}
+
+/// `RPG.Damage`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Damage {
+    pub critical: bool,
+    pub element_id: i32,
+    pub formula: String,
+    /// 0 is none, 1-6 are HP/MP damage, recover and drain
+    #[serde(rename = "type")]
+    pub kind: u32,
+    pub variance: i32,
+}
+
+/// `RPG.AudioFile`, a file name in `audio/<kind>` without the extension
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+#[serde(default)]
+pub struct AudioFile {
+    pub name: String,
+    pub pan: i32,
+    pub pitch: i32,
+    pub volume: i32,
+}
@@ -0,0 +1,271 @@
+//! Actors, classes, skills, items, equipment, enemies, states, animations
+//! and tilesets.
+
+use super::common::{AudioFile, Damage, Effect, Trait};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// `Actors.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Actor {
+    pub id: u32,
+    pub name: String,
+    pub nickname: String,
+    pub class_id: u32,
+    pub initial_level: u32,
+    pub max_level: u32,
+    pub character_name: String,
+    pub character_index: u32,
+    pub face_name: String,
+    pub face_index: u32,
+    pub battler_name: String,
+    /// Item id per equipment slot, 0 for empty
+    pub equips: Vec<u32>,
+    pub profile: String,
+    pub traits: Vec<Trait>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `RPG.Class.Learning`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Learning {
+    pub level: u32,
+    pub skill_id: u32,
+    pub note: String,
+}
+
+/// `Classes.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Class {
+    pub id: u32,
+    pub name: String,
+    /// Base, extra, acceleration A and B of the exp curve
+    pub exp_params: Vec<i32>,
+    /// Parameter value by level, for each of the 8 parameters
+    pub params: Vec<Vec<i32>>,
+    pub learnings: Vec<Learning>,
+    pub traits: Vec<Trait>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Skills.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Skill {
+    pub id: u32,
+    pub name: String,
+    pub icon_index: u32,
+    pub description: String,
+    pub stype_id: u32,
+    pub scope: u32,
+    pub occasion: u32,
+    pub speed: i32,
+    pub success_rate: i32,
+    pub repeats: u32,
+    pub tp_gain: i32,
+    pub hit_type: u32,
+    pub animation_id: i32,
+    pub mp_cost: i32,
+    pub tp_cost: i32,
+    pub message1: String,
+    pub message2: String,
+    pub required_wtype_id1: u32,
+    pub required_wtype_id2: u32,
+    pub damage: Damage,
+    pub effects: Vec<Effect>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Items.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Item {
+    pub id: u32,
+    pub name: String,
+    pub icon_index: u32,
+    pub description: String,
+    /// 1 regular, 2 key item, 3 and 4 hidden A and B
+    pub itype_id: u32,
+    pub price: i32,
+    pub consumable: bool,
+    pub scope: u32,
+    pub occasion: u32,
+    pub speed: i32,
+    pub success_rate: i32,
+    pub repeats: u32,
+    pub tp_gain: i32,
+    pub hit_type: u32,
+    pub animation_id: i32,
+    pub damage: Damage,
+    pub effects: Vec<Effect>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Weapons.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Weapon {
+    pub id: u32,
+    pub name: String,
+    pub icon_index: u32,
+    pub description: String,
+    pub wtype_id: u32,
+    pub etype_id: u32,
+    pub price: i32,
+    pub animation_id: i32,
+    pub params: Vec<i32>,
+    pub traits: Vec<Trait>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Armors.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Armor {
+    pub id: u32,
+    pub name: String,
+    pub icon_index: u32,
+    pub description: String,
+    pub atype_id: u32,
+    pub etype_id: u32,
+    pub price: i32,
+    pub params: Vec<i32>,
+    pub traits: Vec<Trait>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `RPG.Enemy.Action`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EnemyAction {
+    pub skill_id: u32,
+    pub condition_type: u32,
+    pub condition_param1: f64,
+    pub condition_param2: f64,
+    pub rating: u32,
+}
+
+/// `RPG.Enemy.DropItem`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DropItem {
+    /// 0 none, 1 item, 2 weapon, 3 armor
+    pub kind: u32,
+    pub data_id: u32,
+    pub denominator: u32,
+}
+
+/// `Enemies.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Enemy {
+    pub id: u32,
+    pub name: String,
+    pub battler_name: String,
+    pub battler_hue: i32,
+    pub params: Vec<i32>,
+    pub exp: i32,
+    pub gold: i32,
+    pub drop_items: Vec<DropItem>,
+    pub actions: Vec<EnemyAction>,
+    pub traits: Vec<Trait>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `States.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct State {
+    pub id: u32,
+    pub name: String,
+    pub icon_index: u32,
+    pub restriction: u32,
+    pub priority: i32,
+    pub motion: u32,
+    pub overlay: u32,
+    pub remove_at_battle_end: bool,
+    pub remove_by_restriction: bool,
+    pub auto_removal_timing: u32,
+    pub min_turns: u32,
+    pub max_turns: u32,
+    pub remove_by_damage: bool,
+    pub chance_by_damage: i32,
+    pub remove_by_walking: bool,
+    pub steps_to_remove: u32,
+    pub message1: String,
+    pub message2: String,
+    pub message3: String,
+    pub message4: String,
+    pub traits: Vec<Trait>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `Animations.json`. MV animations are cell sprites, MZ ones are Effekseer
+/// effects, so only the fields of each engine are filled in.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Animation {
+    pub id: u32,
+    pub name: String,
+    /// MV: 0 head, 1 center, 2 feet, 3 screen
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    /// MV
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation1_name: Option<String>,
+    /// MV
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation2_name: Option<String>,
+    /// MZ, a file in `effects`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect_name: Option<String>,
+    /// MZ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound_timings: Option<Vec<SoundTiming>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `RPG.Animation.Timing` in MV and `soundTimings` in MZ
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SoundTiming {
+    pub frame: u32,
+    pub se: Option<AudioFile>,
+}
+
+/// `Tilesets.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Tileset {
+    pub id: u32,
+    pub name: String,
+    /// 0 world, 1 area, 2 VX compatible
+    pub mode: u32,
+    /// Images for A1-A5 and B-E, empty where unused
+    pub tileset_names: Vec<String>,
+    /// Passage and terrain flags by tile id
+    pub flags: Vec<u32>,
+    pub note: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
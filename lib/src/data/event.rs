@@ -0,0 +1,98 @@
+//! Common events and troops, the database files that hold event commands.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// `RPG.EventCommand`, one line of an event's list. Parameters depend on the
+/// command code.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct EventCommand {
+    pub code: u32,
+    pub indent: u32,
+    pub parameters: Vec<Value>,
+}
+
+/// `RPG.MoveCommand`, one step of a movement route
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct MoveCommand {
+    pub code: u32,
+    pub parameters: Vec<Value>,
+}
+
+/// `RPG.MoveRoute`, used by event pages and Set Movement Route
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct MoveRoute {
+    pub list: Vec<MoveCommand>,
+    pub repeat: bool,
+    pub skippable: bool,
+    pub wait: bool,
+}
+
+/// `CommonEvents.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CommonEvent {
+    pub id: u32,
+    pub name: String,
+    /// 0 none, 1 autorun, 2 parallel
+    pub trigger: u32,
+    /// Switch that runs it for autorun and parallel triggers
+    pub switch_id: u32,
+    pub list: Vec<EventCommand>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// `RPG.Troop.Member`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TroopMember {
+    pub enemy_id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub hidden: bool,
+}
+
+/// `RPG.Troop.Conditions`, which are only checked when the matching
+/// `*Valid` flag is set
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TroopConditions {
+    pub turn_ending: bool,
+    pub turn_valid: bool,
+    pub turn_a: u32,
+    pub turn_b: u32,
+    pub enemy_valid: bool,
+    pub enemy_index: u32,
+    pub enemy_hp: u32,
+    pub actor_valid: bool,
+    pub actor_id: u32,
+    pub actor_hp: u32,
+    pub switch_valid: bool,
+    pub switch_id: u32,
+}
+
+/// `RPG.Troop.Page`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TroopPage {
+    pub conditions: TroopConditions,
+    /// 0 battle, 1 turn, 2 moment
+    pub span: u32,
+    pub list: Vec<EventCommand>,
+}
+
+/// `Troops.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Troop {
+    pub id: u32,
+    pub name: String,
+    pub members: Vec<TroopMember>,
+    pub pages: Vec<TroopPage>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,217 @@
+//! Maps (`MapXXX.json`) and `MapInfos.json`, the map tree shown in the editor.
+
+use super::{
+    Table,
+    common::AudioFile,
+    event::{EventCommand, MoveRoute},
+};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value};
+use std::path::{Path, PathBuf};
+
+/// `MapInfos.json`. The map itself is in `MapXXX.json`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MapInfo {
+    pub id: u32,
+    pub name: String,
+    /// 0 for maps at the top of the tree
+    pub parent_id: u32,
+    /// Position in the editor's tree, counting across the whole tree
+    pub order: u32,
+    pub expanded: bool,
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    #[serde(flatten)]
+    pub extra: JsonMap<String, Value>,
+}
+
+/// The map tree, from `MapInfos.json`. Map id 0 is the top of the tree.
+impl Table<MapInfo> {
+    /// The map `id` is under, `None` for maps at the top of the tree
+    pub fn parent(&self, id: usize) -> Option<&MapInfo> {
+        let parent_id = self.get(id)?.parent_id as usize;
+        self.get(parent_id).filter(|_| parent_id != id)
+    }
+
+    /// Maps directly under `id`, in the editor's order
+    pub fn children(&self, id: usize) -> Vec<&MapInfo> {
+        let mut children = self
+            .iter()
+            .filter(|(child_id, x)| *child_id != id && x.parent_id as usize == id)
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+        children.sort_by_key(|x| x.order);
+        children
+    }
+
+    /// `id` and the maps above it, from the top of the tree down
+    pub fn ancestors(&self, id: usize) -> Vec<&MapInfo> {
+        let mut ancestors = vec![];
+        let mut next = self.get(id);
+        while let Some(info) = next {
+            // A broken MapInfos.json could loop
+            if ancestors.iter().any(|x: &&MapInfo| x.id == info.id) {
+                break;
+            }
+            ancestors.push(info);
+            next = self.parent(info.id as usize);
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Every map with its depth, in the order of the editor's tree
+    pub fn tree(&self) -> Vec<(usize, &MapInfo)> {
+        let mut maps = self
+            .iter()
+            .map(|(id, x)| (self.ancestors(id).len() - 1, x))
+            .collect::<Vec<_>>();
+        maps.sort_by_key(|(_, x)| x.order);
+        maps
+    }
+
+    /// Names from the top of the tree down to `id`, like `Forest > Cave B1`
+    pub fn location(&self, id: usize) -> Option<String> {
+        let names = self
+            .ancestors(id)
+            .into_iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        (!names.is_empty()).then(|| names.join(" > "))
+    }
+}
+
+/// `RPG.EventPage.Conditions`, which are only checked when the matching
+/// `*Valid` flag is set
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PageConditions {
+    pub switch1_valid: bool,
+    pub switch1_id: u32,
+    pub switch2_valid: bool,
+    pub switch2_id: u32,
+    /// Variable `variable_id` >= `variable_value`
+    pub variable_valid: bool,
+    pub variable_id: u32,
+    pub variable_value: i64,
+    pub self_switch_valid: bool,
+    /// "A" to "D"
+    pub self_switch_ch: String,
+    pub item_valid: bool,
+    pub item_id: u32,
+    pub actor_valid: bool,
+    pub actor_id: u32,
+}
+
+/// `RPG.EventPage.Image`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EventImage {
+    /// Set instead of a character for tile events
+    pub tile_id: u32,
+    pub character_name: String,
+    pub character_index: u32,
+    pub direction: u32,
+    pub pattern: u32,
+}
+
+/// `RPG.EventPage`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EventPage {
+    pub conditions: PageConditions,
+    pub image: EventImage,
+    pub move_type: u32,
+    pub move_speed: u32,
+    pub move_frequency: u32,
+    pub move_route: MoveRoute,
+    pub walk_anime: bool,
+    pub step_anime: bool,
+    pub direction_fix: bool,
+    pub through: bool,
+    /// 0 below characters, 1 same as characters, 2 above characters
+    pub priority_type: u32,
+    /// 0 action button, 1 player touch, 2 event touch, 3 autorun, 4 parallel
+    pub trigger: u32,
+    pub list: Vec<EventCommand>,
+}
+
+/// `RPG.Event`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct MapEvent {
+    pub id: u32,
+    pub name: String,
+    pub note: String,
+    pub x: i32,
+    pub y: i32,
+    /// Checked last to first, the first page whose conditions are met is active
+    pub pages: Vec<EventPage>,
+    #[serde(flatten)]
+    pub extra: JsonMap<String, Value>,
+}
+
+/// `RPG.Map.Encounter`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Encounter {
+    pub troop_id: u32,
+    pub weight: u32,
+    /// Region ids the troop appears in, all of the map if empty
+    pub region_set: Vec<u32>,
+}
+
+/// `MapXXX.json`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Map {
+    pub display_name: String,
+    pub tileset_id: u32,
+    pub width: u32,
+    pub height: u32,
+    /// 0 no loop, 1 loop vertically, 2 horizontally, 3 both
+    pub scroll_type: u32,
+    pub autoplay_bgm: bool,
+    pub bgm: AudioFile,
+    pub autoplay_bgs: bool,
+    pub bgs: AudioFile,
+    pub disable_dashing: bool,
+    pub encounter_list: Vec<Encounter>,
+    pub encounter_step: u32,
+    pub specify_battleback: bool,
+    pub battleback1_name: String,
+    pub battleback2_name: String,
+    pub parallax_name: String,
+    pub parallax_loop_x: bool,
+    pub parallax_loop_y: bool,
+    pub parallax_sx: i32,
+    pub parallax_sy: i32,
+    pub parallax_show: bool,
+    pub note: String,
+    /// Tile ids, indexed by `(z * height + y) * width + x` for 6 layers
+    pub data: Vec<u32>,
+    pub events: Table<MapEvent>,
+    #[serde(flatten)]
+    pub extra: JsonMap<String, Value>,
+}
+impl Map {
+    /// `MapXXX.json`
+    pub fn file_name(map_id: u32) -> String {
+        format!("Map{map_id:03}.json")
+    }
+
+    /// `MapXXX.json` in `data_dir`
+    pub fn path(data_dir: &Path, map_id: u32) -> PathBuf {
+        data_dir.join(Self::file_name(map_id))
+    }
+
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        super::read_file(path)
+    }
+
+    pub fn load(data_dir: &Path, map_id: u32) -> Result<Self, Error> {
+        Self::read_file(&Self::path(data_dir, map_id))
+    }
+}
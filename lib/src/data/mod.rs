@@ -0,0 +1,147 @@
+//! The game's database (`www/data` in MV, `data` in MZ).
+//!
+//! Every file except `System.json` is an array indexed by id. Index 0 is
+//! always null, and so are the slots of entries deleted in the editor, so
+//! they're read into a [`Table`] the same way `global.rpgsave` is.
+//!
+//! Fields missing from a file (older MV versions write fewer of them) are
+//! defaulted, and fields these structs don't know about are kept in `extra`.
+
+pub mod command;
+pub mod common;
+pub mod database;
+pub mod event;
+pub mod map;
+pub mod print;
+pub mod system;
+pub mod xref;
+
+use crate::{error::Error, layout::GameLayout};
+use database::{Actor, Animation, Armor, Class, Enemy, Item, Skill, State, Tileset, Weapon};
+use event::{CommonEvent, Troop};
+use map::MapInfo;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::path::{Path, PathBuf};
+use system::System;
+
+/// Reads and decodes one database file
+pub fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let file = std::fs::read_to_string(path)
+        .map_err(|e| Error::Data(format!("Failed to read {}: {e}", path.display())))?;
+    serde_json::from_str(&file).map_err(|e| Error::Data(format!("{}: {e}", path.display())))
+}
+
+/// Where the database files are read from
+#[derive(Debug, Clone)]
+pub enum DataSource {
+    Dir(PathBuf),
+    /// The data directory of a game, whose files may be in its package
+    Game(GameLayout),
+}
+impl DataSource {
+    /// `path` itself if it has the database files, otherwise the game it's in.
+    /// Falls back to `path` when it isn't in a game either.
+    pub fn open(path: &Path) -> Self {
+        if path.join("System.json").is_file() {
+            return Self::Dir(path.to_path_buf());
+        }
+        GameLayout::find(path).map_or_else(|_| Self::Dir(path.to_path_buf()), Self::Game)
+    }
+
+    pub fn path(&self, file_name: &str) -> PathBuf {
+        match self {
+            Self::Dir(dir) => dir.join(file_name),
+            Self::Game(layout) => layout.data_dir().join(file_name),
+        }
+    }
+
+    /// Reads and decodes the database file `file_name`
+    pub fn read<T: DeserializeOwned>(&self, file_name: &str) -> Result<T, Error> {
+        let path = self.path(file_name);
+        let file = match self {
+            Self::Dir(_) => std::fs::read(&path)
+                .map_err(|e| Error::Data(format!("Failed to read {}: {e}", path.display())))?,
+            Self::Game(layout) => layout.read(&path)?,
+        };
+        serde_json::from_slice(&file).map_err(|e| Error::Data(format!("{}: {e}", path.display())))
+    }
+}
+
+/// A database file indexed by id, `None` where there's no entry
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct Table<T>(pub Vec<Option<T>>);
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+impl<T: DeserializeOwned> Table<T> {
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        read_file(path)
+    }
+}
+impl<T> Table<T> {
+    pub fn get(&self, id: usize) -> Option<&T> {
+        self.0.get(id).and_then(Option::as_ref)
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Every entry with its id, skipping empty slots
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(id, x)| Some((id, x.as_ref()?)))
+    }
+
+    /// Highest id the table has room for
+    pub fn max_id(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+}
+
+/// Every database file except the maps themselves
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    pub actors: Table<Actor>,
+    pub classes: Table<Class>,
+    pub skills: Table<Skill>,
+    pub items: Table<Item>,
+    pub weapons: Table<Weapon>,
+    pub armors: Table<Armor>,
+    pub enemies: Table<Enemy>,
+    pub troops: Table<Troop>,
+    pub states: Table<State>,
+    pub animations: Table<Animation>,
+    pub tilesets: Table<Tileset>,
+    pub common_events: Table<CommonEvent>,
+    pub system: System,
+    pub map_infos: Table<MapInfo>,
+}
+impl Database {
+    pub fn load(data_dir: &Path) -> Result<Self, Error> {
+        Self::load_from(&DataSource::Dir(data_dir.to_path_buf()))
+    }
+
+    pub fn load_from(source: &DataSource) -> Result<Self, Error> {
+        Ok(Self {
+            actors: source.read("Actors.json")?,
+            classes: source.read("Classes.json")?,
+            skills: source.read("Skills.json")?,
+            items: source.read("Items.json")?,
+            weapons: source.read("Weapons.json")?,
+            armors: source.read("Armors.json")?,
+            enemies: source.read("Enemies.json")?,
+            troops: source.read("Troops.json")?,
+            states: source.read("States.json")?,
+            animations: source.read("Animations.json")?,
+            tilesets: source.read("Tilesets.json")?,
+            common_events: source.read("CommonEvents.json")?,
+            system: source.read("System.json")?,
+            map_infos: source.read("MapInfos.json")?,
+        })
+    }
+}
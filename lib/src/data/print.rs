@@ -0,0 +1,1086 @@
+//! Renders event lists the way the editor shows them, one command per line,
+//! so event scripts can be read and diffed as text.
+
+use super::{
+    Database, Table,
+    command::{
+        ActorCheck, ActorRef, Battler, Command, Condition, Location, Operand, PictureSettings,
+        Position, ShopGood, TroopRef, VariableOperand,
+    },
+    common::AudioFile,
+    event::{EventCommand, MoveCommand},
+};
+use serde_json::Value;
+
+fn on_off(on: bool) -> &'static str {
+    if on { "ON" } else { "OFF" }
+}
+
+fn enabled_text(enabled: bool) -> &'static str {
+    if enabled { "Enable" } else { "Disable" }
+}
+
+fn direction(direction: u32) -> &'static str {
+    match direction {
+        2 => "Down",
+        4 => "Left",
+        6 => "Right",
+        8 => "Up",
+        _ => "Retain",
+    }
+}
+
+fn character(id: i64) -> String {
+    match id {
+        -1 => "Player".into(),
+        0 => "This Event".into(),
+        id => format!("EV{id:03}"),
+    }
+}
+
+fn vehicle(id: u32) -> &'static str {
+    match id {
+        0 => "Boat",
+        1 => "Ship",
+        _ => "Airship",
+    }
+}
+
+fn audio(file: &AudioFile) -> String {
+    if file.name.is_empty() {
+        return "None".into();
+    }
+    format!(
+        "{} ({}, {}, {})",
+        file.name, file.volume, file.pitch, file.pan
+    )
+}
+
+fn enemy(index: i64) -> String {
+    match index {
+        i if i < 0 => "Entire Troop".into(),
+        i => format!("#{}", i + 1),
+    }
+}
+
+fn join(values: &[i64]) -> String {
+    let values = values.iter().map(i64::to_string).collect::<Vec<_>>();
+    format!("({})", values.join(","))
+}
+
+fn wait(wait: bool) -> &'static str {
+    if wait { " (Wait)" } else { "" }
+}
+
+/// Name of a step of a movement route
+fn move_command(command: &MoveCommand) -> String {
+    let param = |i: usize| command.parameters.get(i).cloned().unwrap_or(Value::Null);
+    let text = |i: usize| param(i).as_str().unwrap_or_default().to_string();
+    let id = |i: usize| param(i).as_u64().unwrap_or_default();
+
+    match command.code {
+        0 => String::new(),
+        1 => "Move Down".into(),
+        2 => "Move Left".into(),
+        3 => "Move Right".into(),
+        4 => "Move Up".into(),
+        5 => "Move Lower Left".into(),
+        6 => "Move Lower Right".into(),
+        7 => "Move Upper Left".into(),
+        8 => "Move Upper Right".into(),
+        9 => "Move at Random".into(),
+        10 => "Move toward Player".into(),
+        11 => "Move away from Player".into(),
+        12 => "1 Step Forward".into(),
+        13 => "1 Step Backward".into(),
+        14 => format!("Jump : {}, {}", param(0), param(1)),
+        15 => format!("Wait : {} frames", param(0)),
+        16 => "Turn Down".into(),
+        17 => "Turn Left".into(),
+        18 => "Turn Right".into(),
+        19 => "Turn Up".into(),
+        20 => "Turn 90° Right".into(),
+        21 => "Turn 90° Left".into(),
+        22 => "Turn 180°".into(),
+        23 => "Turn 90° Right or Left".into(),
+        24 => "Turn at Random".into(),
+        25 => "Turn toward Player".into(),
+        26 => "Turn away from Player".into(),
+        27 => format!("Switch ON : #{:04}", id(0)),
+        28 => format!("Switch OFF : #{:04}", id(0)),
+        29 => format!("Speed : {}", param(0)),
+        30 => format!("Frequency : {}", param(0)),
+        31 => "Walking Animation ON".into(),
+        32 => "Walking Animation OFF".into(),
+        33 => "Stepping Animation ON".into(),
+        34 => "Stepping Animation OFF".into(),
+        35 => "Direction Fix ON".into(),
+        36 => "Direction Fix OFF".into(),
+        37 => "Through ON".into(),
+        38 => "Through OFF".into(),
+        39 => "Transparent ON".into(),
+        40 => "Transparent OFF".into(),
+        41 => format!("Image : {}({})", text(0), param(1)),
+        42 => format!("Opacity : {}", param(0)),
+        43 => format!("Blend : {}", param(0)),
+        44 => format!("SE : {}", param(0).get("name").unwrap_or(&Value::Null)),
+        45 => format!("Script : {}", text(0)),
+        code => format!("Move {code}"),
+    }
+}
+
+/// Turns ids into names where a database is available
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Printer<'a> {
+    pub database: Option<&'a Database>,
+}
+impl<'a> Printer<'a> {
+    pub fn new(database: Option<&'a Database>) -> Self {
+        Self { database }
+    }
+
+    /// `#0001 Name`, the way the editor lists switches and variables
+    fn numbered(id: u32, name: Option<&str>) -> String {
+        match name.filter(|x| !x.is_empty()) {
+            Some(name) => format!("#{id:04} {name}"),
+            None => format!("#{id:04}"),
+        }
+    }
+
+    /// Name from the database, or the id if there's no name for it
+    fn named<T>(
+        &self,
+        table: impl Fn(&Database) -> &Table<T>,
+        name: impl Fn(&T) -> &str,
+        id: u32,
+    ) -> String {
+        self.database
+            .and_then(|db| table(db).get(id as usize))
+            .map(name)
+            .filter(|x| !x.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("#{id:04}"))
+    }
+
+    fn switch(&self, id: u32) -> String {
+        let name = self
+            .database
+            .and_then(|db| db.system.switches.get(id as usize));
+        Self::numbered(id, name.map(String::as_str))
+    }
+
+    fn switch_range(&self, start: u32, end: u32) -> String {
+        if start == end {
+            self.switch(start)
+        } else {
+            format!("#{start:04}..#{end:04}")
+        }
+    }
+
+    fn variable(&self, id: u32) -> String {
+        let name = self
+            .database
+            .and_then(|db| db.system.variables.get(id as usize));
+        Self::numbered(id, name.map(String::as_str))
+    }
+
+    fn variable_range(&self, start: u32, end: u32) -> String {
+        if start == end {
+            self.variable(start)
+        } else {
+            format!("#{start:04}..#{end:04}")
+        }
+    }
+
+    fn actor(&self, id: u32) -> String {
+        self.named(|db| &db.actors, |x| &x.name, id)
+    }
+
+    fn item(&self, id: u32) -> String {
+        self.named(|db| &db.items, |x| &x.name, id)
+    }
+
+    fn weapon(&self, id: u32) -> String {
+        self.named(|db| &db.weapons, |x| &x.name, id)
+    }
+
+    fn armor(&self, id: u32) -> String {
+        self.named(|db| &db.armors, |x| &x.name, id)
+    }
+
+    fn skill(&self, id: u32) -> String {
+        self.named(|db| &db.skills, |x| &x.name, id)
+    }
+
+    fn state(&self, id: u32) -> String {
+        self.named(|db| &db.states, |x| &x.name, id)
+    }
+
+    fn class(&self, id: u32) -> String {
+        self.named(|db| &db.classes, |x| &x.name, id)
+    }
+
+    fn map(&self, id: u32) -> String {
+        self.named(|db| &db.map_infos, |x| &x.name, id)
+    }
+
+    fn operand(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Constant(value) => value.to_string(),
+            Operand::Variable(id) => format!("{{{}}}", self.variable(id)),
+        }
+    }
+
+    fn change(&self, increase: bool, amount: Operand) -> String {
+        let sign = if increase { "+" } else { "-" };
+        format!("{sign} {}", self.operand(amount))
+    }
+
+    fn actor_ref(&self, actor: ActorRef) -> String {
+        match actor {
+            ActorRef::Fixed(0) => "Entire Party".into(),
+            ActorRef::Fixed(id) => self.actor(id),
+            ActorRef::Variable(id) => format!("{{{}}}", self.variable(id)),
+        }
+    }
+
+    fn position(&self, position: Position) -> String {
+        match position {
+            Position::Direct { x, y } => format!("({x},{y})"),
+            Position::Variables { x, y } => {
+                format!("({{{}}},{{{}}})", self.variable(x), self.variable(y))
+            }
+            Position::Character(id) => format!("Exchange with {}", character(id)),
+        }
+    }
+
+    fn location(&self, location: Location) -> String {
+        match location {
+            Location::Direct { map_id, x, y } => format!("{} ({x},{y})", self.map(map_id)),
+            Location::Variables { map_id, x, y } => format!(
+                "{{{}}} ({{{}}},{{{}}})",
+                self.variable(map_id),
+                self.variable(x),
+                self.variable(y)
+            ),
+        }
+    }
+
+    fn picture(&self, settings: &PictureSettings) -> String {
+        let origin = if settings.origin == 0 {
+            "Upper Left"
+        } else {
+            "Center"
+        };
+        format!(
+            "{origin} {}, ({}%,{}%), {}",
+            self.position(settings.position),
+            settings.scale_x,
+            settings.scale_y,
+            settings.opacity
+        )
+    }
+
+    fn good(&self, good: &ShopGood) -> String {
+        let name = match good.kind {
+            0 => self.item(good.id),
+            1 => self.weapon(good.id),
+            _ => self.armor(good.id),
+        };
+        match good.price {
+            Some(price) => format!("{name} ({price})"),
+            None => name,
+        }
+    }
+
+    fn condition(&self, condition: &Condition) -> String {
+        match condition {
+            Condition::Switch { switch_id, on } => {
+                format!("{} is {}", self.switch(*switch_id), on_off(*on))
+            }
+            Condition::Variable {
+                variable_id,
+                comparison,
+                operand,
+            } => format!(
+                "{} {} {}",
+                self.variable(*variable_id),
+                comparison.symbol(),
+                self.operand(*operand)
+            ),
+            Condition::SelfSwitch { ch, on } => format!("Self Switch {ch} is {}", on_off(*on)),
+            Condition::Timer {
+                comparison,
+                seconds,
+            } => format!(
+                "Timer {} {} min {} sec",
+                comparison.symbol(),
+                seconds / 60,
+                seconds % 60
+            ),
+            Condition::Actor { actor_id, check } => {
+                let actor = self.actor(*actor_id);
+                match check {
+                    ActorCheck::InParty => format!("{actor} is in the party"),
+                    ActorCheck::Name(name) => format!("Name of {actor} is {name}"),
+                    ActorCheck::Class(id) => format!("Class of {actor} is {}", self.class(*id)),
+                    ActorCheck::Skill(id) => format!("{actor} has learned {}", self.skill(*id)),
+                    ActorCheck::Weapon(id) => format!("{actor} has {} equipped", self.weapon(*id)),
+                    ActorCheck::Armor(id) => format!("{actor} has {} equipped", self.armor(*id)),
+                    ActorCheck::State(id) => {
+                        format!("{actor} is affected by {}", self.state(*id))
+                    }
+                }
+            }
+            Condition::Enemy {
+                enemy_index,
+                state_id,
+            } => match state_id {
+                None => format!("{} is appeared", enemy(*enemy_index)),
+                Some(id) => format!("{} is affected by {}", enemy(*enemy_index), self.state(*id)),
+            },
+            Condition::Character {
+                character_id,
+                direction: dir,
+            } => format!("{} is facing {}", character(*character_id), direction(*dir)),
+            Condition::Gold { comparison, amount } => {
+                format!("Gold {} {amount}", comparison.symbol())
+            }
+            Condition::Item(id) => format!("Party has {}", self.item(*id)),
+            Condition::Weapon {
+                weapon_id,
+                include_equipment,
+            } => {
+                let equip = if *include_equipment {
+                    " (Include Equipment)"
+                } else {
+                    ""
+                };
+                format!("Party has {}{equip}", self.weapon(*weapon_id))
+            }
+            Condition::Armor {
+                armor_id,
+                include_equipment,
+            } => {
+                let equip = if *include_equipment {
+                    " (Include Equipment)"
+                } else {
+                    ""
+                };
+                format!("Party has {}{equip}", self.armor(*armor_id))
+            }
+            Condition::Button(name) => format!("Button [{name}] is pressed down"),
+            Condition::Script(script) => format!("Script : {script}"),
+            Condition::Vehicle(id) => format!("{} is driven", vehicle(*id)),
+        }
+    }
+
+    /// One command, without the indent or the leading marker
+    pub fn command(&self, command: &Command) -> String {
+        use Command::*;
+
+        match command {
+            End => String::new(),
+            ShowText {
+                face_name,
+                face_index,
+                background,
+                position,
+                speaker,
+            } => {
+                let face = if face_name.is_empty() {
+                    "None".to_string()
+                } else {
+                    format!("{face_name}({face_index})")
+                };
+                let background = ["Window", "Dim", "Transparent"]
+                    .get(*background as usize)
+                    .unwrap_or(&"Window");
+                let position = ["Top", "Middle", "Bottom"]
+                    .get(*position as usize)
+                    .unwrap_or(&"Bottom");
+                match speaker.as_deref().filter(|x| !x.is_empty()) {
+                    Some(speaker) => format!("Text : {face}, {background}, {position}, {speaker}"),
+                    None => format!("Text : {face}, {background}, {position}"),
+                }
+            }
+            Text(text) | ScrollingText(text) => format!("     : {text}"),
+            ShowChoices {
+                choices,
+                cancel_type,
+                default_type,
+                ..
+            } => {
+                let default = match default_type {
+                    -1 => "-".to_string(),
+                    i => format!("#{}", i + 1),
+                };
+                let cancel = match cancel_type {
+                    -2 => "Branch".to_string(),
+                    -1 => "Disallow".to_string(),
+                    i => format!("#{}", i + 1),
+                };
+                format!(
+                    "Show Choices : {} ({default}, {cancel})",
+                    choices.join(", ")
+                )
+            }
+            When { text, .. } => format!("When {text}"),
+            WhenCancel => "When Cancel".into(),
+            ChoicesEnd | EndIf | BattleEnd => "End".into(),
+            InputNumber {
+                variable_id,
+                digits,
+            } => format!(
+                "Input Number : {}, {digits} digits",
+                self.variable(*variable_id)
+            ),
+            SelectItem {
+                variable_id,
+                item_type,
+            } => {
+                let kind = [
+                    "",
+                    "Regular Item",
+                    "Key Item",
+                    "Hidden Item A",
+                    "Hidden Item B",
+                ]
+                .get(*item_type as usize)
+                .unwrap_or(&"");
+                format!("Select Item : {}, {kind}", self.variable(*variable_id))
+            }
+            ShowScrollingText {
+                speed,
+                no_fast_forward,
+            } => {
+                let fast = if *no_fast_forward {
+                    ", No Fast Forward"
+                } else {
+                    ""
+                };
+                format!("Text (S) : Speed {speed}{fast}")
+            }
+            Comment(text) => format!("Comment : {text}"),
+            CommentMore(text) | ScriptMore(text) | PluginCommandMzMore(text) => {
+                format!("       : {text}")
+            }
+            If(condition) => format!("If : {}", self.condition(condition)),
+            Else => "Else".into(),
+            Loop => "Loop".into(),
+            RepeatAbove => "Repeat Above".into(),
+            BreakLoop => "Break Loop".into(),
+            ExitEventProcessing => "Exit Event Processing".into(),
+            CommonEvent(id) => format!(
+                "Common Event : {}",
+                self.named(|db| &db.common_events, |x| &x.name, *id)
+            ),
+            Label(name) => format!("Label : {name}"),
+            JumpToLabel(name) => format!("Jump to Label : {name}"),
+            ControlSwitches { start, end, on } => format!(
+                "Control Switches : {} = {}",
+                self.switch_range(*start, *end),
+                on_off(*on)
+            ),
+            ControlVariables {
+                start,
+                end,
+                operation,
+                operand,
+            } => {
+                let operand = match operand {
+                    VariableOperand::Constant(value) => value.to_string(),
+                    VariableOperand::Variable(id) => self.variable(*id),
+                    VariableOperand::Random { min, max } => format!("Random {min}..{max}"),
+                    VariableOperand::GameData {
+                        kind,
+                        param1,
+                        param2,
+                    } => format!("Game Data {kind} ({param1}, {param2})"),
+                    VariableOperand::Script(script) => script.clone(),
+                };
+                format!(
+                    "Control Variables : {} {} {operand}",
+                    self.variable_range(*start, *end),
+                    operation.symbol()
+                )
+            }
+            ControlSelfSwitch { ch, on } => format!("Control Self Switch : {ch} = {}", on_off(*on)),
+            ControlTimer(Some(seconds)) => format!(
+                "Control Timer : Start, {} min {} sec",
+                seconds / 60,
+                seconds % 60
+            ),
+            ControlTimer(None) => "Control Timer : Stop".into(),
+            ChangeGold { increase, amount } => {
+                format!("Change Gold : {}", self.change(*increase, *amount))
+            }
+            ChangeItems {
+                item_id,
+                increase,
+                amount,
+            } => format!(
+                "Change Items : {} {}",
+                self.item(*item_id),
+                self.change(*increase, *amount)
+            ),
+            ChangeWeapons {
+                weapon_id,
+                increase,
+                amount,
+                ..
+            } => format!(
+                "Change Weapons : {} {}",
+                self.weapon(*weapon_id),
+                self.change(*increase, *amount)
+            ),
+            ChangeArmors {
+                armor_id,
+                increase,
+                amount,
+                ..
+            } => format!(
+                "Change Armors : {} {}",
+                self.armor(*armor_id),
+                self.change(*increase, *amount)
+            ),
+            ChangePartyMember {
+                actor_id,
+                add,
+                initialize,
+            } => {
+                let action = if *add { "Add" } else { "Remove" };
+                let init = if *initialize { " (Initialize)" } else { "" };
+                format!(
+                    "Change Party Member : {action} {}{init}",
+                    self.actor(*actor_id)
+                )
+            }
+            ChangeBattleBgm(file) => format!("Change Battle BGM : {}", audio(file)),
+            ChangeVictoryMe(file) => format!("Change Victory ME : {}", audio(file)),
+            ChangeDefeatMe(file) => format!("Change Defeat ME : {}", audio(file)),
+            ChangeSaveAccess(enabled) => format!("Change Save Access : {}", enabled_text(*enabled)),
+            ChangeMenuAccess(enabled) => format!("Change Menu Access : {}", enabled_text(*enabled)),
+            ChangeEncounter(enabled) => format!("Change Encounter : {}", enabled_text(*enabled)),
+            ChangeFormationAccess(enabled) => {
+                format!("Change Formation Access : {}", enabled_text(*enabled))
+            }
+            ChangeWindowColor(tone) => format!("Change Window Color : {}", join(tone)),
+            ChangeVehicleBgm { vehicle: id, bgm } => {
+                format!("Change Vehicle BGM : {}, {}", vehicle(*id), audio(bgm))
+            }
+            TransferPlayer {
+                location,
+                direction: dir,
+                fade,
+            } => {
+                let fade = ["Black", "White", "None"]
+                    .get(*fade as usize)
+                    .unwrap_or(&"Black");
+                format!(
+                    "Transfer Player : {} (Direction: {}, Fade: {fade})",
+                    self.location(*location),
+                    direction(*dir)
+                )
+            }
+            SetVehicleLocation {
+                vehicle: id,
+                location,
+            } => format!(
+                "Set Vehicle Location : {}, {}",
+                vehicle(*id),
+                self.location(*location)
+            ),
+            SetEventLocation {
+                character_id,
+                position,
+                direction: dir,
+            } => format!(
+                "Set Event Location : {}, {} (Direction: {})",
+                character(*character_id),
+                self.position(*position),
+                direction(*dir)
+            ),
+            ScrollMap {
+                direction: dir,
+                distance,
+                speed,
+            } => format!("Scroll Map : {}, {distance}, {speed}", direction(*dir)),
+            SetMovementRoute {
+                character_id,
+                route,
+            } => {
+                let mut options = vec![];
+                if route.repeat {
+                    options.push("Repeat");
+                }
+                if route.skippable {
+                    options.push("Skip");
+                }
+                if route.wait {
+                    options.push("Wait");
+                }
+                let options = if options.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", options.join(", "))
+                };
+                format!("Set Movement Route : {}{options}", character(*character_id))
+            }
+            MoveRouteStep(step) => format!("                    : ◇{}", move_command(step)),
+            GetOnOffVehicle => "Get on/off Vehicle".into(),
+            ChangeTransparency(on) => format!("Change Transparency : {}", on_off(*on)),
+            ShowAnimation {
+                character_id,
+                animation_id,
+                wait: w,
+            } => format!(
+                "Show Animation : {}, {}{}",
+                character(*character_id),
+                self.named(|db| &db.animations, |x| &x.name, *animation_id),
+                wait(*w)
+            ),
+            ShowBalloonIcon {
+                character_id,
+                balloon_id,
+                wait: w,
+            } => format!(
+                "Show Balloon Icon : {}, {balloon_id}{}",
+                character(*character_id),
+                wait(*w)
+            ),
+            EraseEvent => "Erase Event".into(),
+            ChangePlayerFollowers(on) => format!("Change Player Followers : {}", on_off(*on)),
+            GatherFollowers => "Gather Followers".into(),
+            FadeoutScreen => "Fadeout Screen".into(),
+            FadeinScreen => "Fadein Screen".into(),
+            TintScreen {
+                tone,
+                duration,
+                wait: w,
+            } => format!(
+                "Tint Screen : {}, {duration} frames{}",
+                join(tone),
+                wait(*w)
+            ),
+            FlashScreen {
+                color,
+                duration,
+                wait: w,
+            } => format!(
+                "Flash Screen : {}, {duration} frames{}",
+                join(color),
+                wait(*w)
+            ),
+            ShakeScreen {
+                power,
+                speed,
+                duration,
+                wait: w,
+            } => format!(
+                "Shake Screen : {power}, {speed}, {duration} frames{}",
+                wait(*w)
+            ),
+            Wait(frames) => format!("Wait : {frames} frames"),
+            ShowPicture {
+                picture_id,
+                name,
+                settings,
+            } => format!(
+                "Show Picture : #{picture_id}, {name}, {}",
+                self.picture(settings)
+            ),
+            MovePicture {
+                picture_id,
+                settings,
+                duration,
+                wait: w,
+            } => format!(
+                "Move Picture : #{picture_id}, {}, {duration} frames{}",
+                self.picture(settings),
+                wait(*w)
+            ),
+            RotatePicture { picture_id, speed } => {
+                format!("Rotate Picture : #{picture_id}, {speed}")
+            }
+            TintPicture {
+                picture_id,
+                tone,
+                duration,
+                wait: w,
+            } => format!(
+                "Tint Picture : #{picture_id}, {}, {duration} frames{}",
+                join(tone),
+                wait(*w)
+            ),
+            ErasePicture(id) => format!("Erase Picture : #{id}"),
+            SetWeatherEffect {
+                kind,
+                power,
+                duration,
+                wait: w,
+            } => format!(
+                "Set Weather Effect : {kind}, {power}, {duration} frames{}",
+                wait(*w)
+            ),
+            PlayBgm(file) => format!("Play BGM : {}", audio(file)),
+            FadeoutBgm(seconds) => format!("Fadeout BGM : {seconds} sec"),
+            SaveBgm => "Save BGM".into(),
+            ResumeBgm => "Resume BGM".into(),
+            PlayBgs(file) => format!("Play BGS : {}", audio(file)),
+            FadeoutBgs(seconds) => format!("Fadeout BGS : {seconds} sec"),
+            PlayMe(file) => format!("Play ME : {}", audio(file)),
+            PlaySe(file) => format!("Play SE : {}", audio(file)),
+            StopSe => "Stop SE".into(),
+            PlayMovie(name) => format!("Play Movie : {name}"),
+            ChangeMapNameDisplay(on) => format!("Change Map Name Display : {}", on_off(*on)),
+            ChangeTileset(id) => format!(
+                "Change Tileset : {}",
+                self.named(|db| &db.tilesets, |x| &x.name, *id)
+            ),
+            ChangeBattleBackground { back1, back2 } => {
+                format!("Change Battle Back. : {back1} & {back2}")
+            }
+            ChangeParallax { name, .. } => format!("Change Parallax : {name}"),
+            GetLocationInfo {
+                variable_id,
+                info_type,
+                position,
+            } => {
+                let info = [
+                    "Terrain Tag",
+                    "Event ID",
+                    "Tile ID (Layer 1)",
+                    "Tile ID (Layer 2)",
+                    "Tile ID (Layer 3)",
+                    "Tile ID (Layer 4)",
+                    "Region ID",
+                ]
+                .get(*info_type as usize)
+                .unwrap_or(&"?");
+                format!(
+                    "Get Location Info : {}, {info}, {}",
+                    self.variable(*variable_id),
+                    self.position(*position)
+                )
+            }
+            BattleProcessing {
+                troop,
+                can_escape,
+                can_lose,
+            } => {
+                let troop = match troop {
+                    TroopRef::Direct(id) => self.named(|db| &db.troops, |x| &x.name, *id),
+                    TroopRef::Variable(id) => format!("{{{}}}", self.variable(*id)),
+                    TroopRef::RandomEncounter => "Same as Random Encounter".into(),
+                };
+                let mut options = vec![];
+                if *can_escape {
+                    options.push("Can Escape");
+                }
+                if *can_lose {
+                    options.push("Can Lose");
+                }
+                if options.is_empty() {
+                    format!("Battle Processing : {troop}")
+                } else {
+                    format!("Battle Processing : {troop} ({})", options.join(", "))
+                }
+            }
+            IfWin => "If Win".into(),
+            IfEscape => "If Escape".into(),
+            IfLose => "If Lose".into(),
+            ShopProcessing {
+                good,
+                purchase_only,
+            } => {
+                let only = if *purchase_only {
+                    " (Purchase Only)"
+                } else {
+                    ""
+                };
+                format!("Shop Processing : {}{only}", self.good(good))
+            }
+            ShopGood(good) => format!("                : {}", self.good(good)),
+            NameInput {
+                actor_id,
+                max_characters,
+            } => format!(
+                "Name Input Processing : {}, {max_characters} characters",
+                self.actor(*actor_id)
+            ),
+            OpenMenuScreen => "Open Menu Screen".into(),
+            OpenSaveScreen => "Open Save Screen".into(),
+            GameOver => "Game Over".into(),
+            ReturnToTitleScreen => "Return to Title Screen".into(),
+            ChangeHp {
+                actor,
+                increase,
+                amount,
+                allow_knockout,
+            } => {
+                let knockout = if *allow_knockout {
+                    " (Allow Knockout)"
+                } else {
+                    ""
+                };
+                format!(
+                    "Change HP : {}, {}{knockout}",
+                    self.actor_ref(*actor),
+                    self.change(*increase, *amount)
+                )
+            }
+            ChangeMp {
+                actor,
+                increase,
+                amount,
+            } => format!(
+                "Change MP : {}, {}",
+                self.actor_ref(*actor),
+                self.change(*increase, *amount)
+            ),
+            ChangeTp {
+                actor,
+                increase,
+                amount,
+            } => format!(
+                "Change TP : {}, {}",
+                self.actor_ref(*actor),
+                self.change(*increase, *amount)
+            ),
+            ChangeState {
+                actor,
+                add,
+                state_id,
+            } => format!(
+                "Change State : {}, {} {}",
+                self.actor_ref(*actor),
+                if *add { "+" } else { "-" },
+                self.state(*state_id)
+            ),
+            RecoverAll(actor) => format!("Recover All : {}", self.actor_ref(*actor)),
+            ChangeExp {
+                actor,
+                increase,
+                amount,
+                ..
+            } => format!(
+                "Change EXP : {}, {}",
+                self.actor_ref(*actor),
+                self.change(*increase, *amount)
+            ),
+            ChangeLevel {
+                actor,
+                increase,
+                amount,
+                ..
+            } => format!(
+                "Change Level : {}, {}",
+                self.actor_ref(*actor),
+                self.change(*increase, *amount)
+            ),
+            ChangeParameter {
+                actor,
+                param_id,
+                increase,
+                amount,
+            } => {
+                let param = self
+                    .database
+                    .and_then(|db| db.system.terms.params.get(*param_id as usize))
+                    .cloned()
+                    .unwrap_or_else(|| format!("Parameter {param_id}"));
+                format!(
+                    "Change Parameter : {}, {param} {}",
+                    self.actor_ref(*actor),
+                    self.change(*increase, *amount)
+                )
+            }
+            ChangeSkill {
+                actor,
+                learn,
+                skill_id,
+            } => format!(
+                "Change Skill : {}, {} {}",
+                self.actor_ref(*actor),
+                if *learn { "+" } else { "-" },
+                self.skill(*skill_id)
+            ),
+            ChangeEquipment {
+                actor_id,
+                etype_id,
+                item_id,
+            } => {
+                let slot = self
+                    .database
+                    .and_then(|db| db.system.equip_types.get(*etype_id as usize))
+                    .cloned()
+                    .unwrap_or_else(|| format!("Slot {etype_id}"));
+                let item = match (item_id, etype_id) {
+                    (0, _) => "None".to_string(),
+                    (id, 1) => self.weapon(*id),
+                    (id, _) => self.armor(*id),
+                };
+                format!(
+                    "Change Equipment : {}, {slot} = {item}",
+                    self.actor(*actor_id)
+                )
+            }
+            ChangeName { actor_id, name } => {
+                format!("Change Name : {}, {name}", self.actor(*actor_id))
+            }
+            ChangeClass {
+                actor_id, class_id, ..
+            } => format!(
+                "Change Class : {}, {}",
+                self.actor(*actor_id),
+                self.class(*class_id)
+            ),
+            ChangeActorImages {
+                actor_id,
+                character_name,
+                character_index,
+                face_name,
+                face_index,
+                battler_name,
+            } => format!(
+                "Change Actor Images : {}, {character_name}({character_index}), {face_name}({face_index}), {battler_name}",
+                self.actor(*actor_id)
+            ),
+            ChangeVehicleImage {
+                vehicle: id,
+                character_name,
+                character_index,
+            } => format!(
+                "Change Vehicle Image : {}, {character_name}({character_index})",
+                vehicle(*id)
+            ),
+            ChangeNickname { actor_id, nickname } => {
+                format!("Change Nickname : {}, {nickname}", self.actor(*actor_id))
+            }
+            ChangeProfile { actor_id, profile } => format!(
+                "Change Profile : {}, {}",
+                self.actor(*actor_id),
+                profile.replace('\n', " ")
+            ),
+            ChangeEnemyHp {
+                enemy_index,
+                increase,
+                amount,
+                ..
+            } => format!(
+                "Change Enemy HP : {}, {}",
+                enemy(*enemy_index),
+                self.change(*increase, *amount)
+            ),
+            ChangeEnemyMp {
+                enemy_index,
+                increase,
+                amount,
+            } => format!(
+                "Change Enemy MP : {}, {}",
+                enemy(*enemy_index),
+                self.change(*increase, *amount)
+            ),
+            ChangeEnemyTp {
+                enemy_index,
+                increase,
+                amount,
+            } => format!(
+                "Change Enemy TP : {}, {}",
+                enemy(*enemy_index),
+                self.change(*increase, *amount)
+            ),
+            ChangeEnemyState {
+                enemy_index,
+                add,
+                state_id,
+            } => format!(
+                "Change Enemy State : {}, {} {}",
+                enemy(*enemy_index),
+                if *add { "+" } else { "-" },
+                self.state(*state_id)
+            ),
+            EnemyRecoverAll(index) => format!("Enemy Recover All : {}", enemy(*index)),
+            EnemyAppear(index) => format!("Enemy Appear : {}", enemy(*index)),
+            EnemyTransform {
+                enemy_index,
+                enemy_id,
+            } => format!(
+                "Enemy Transform : {}, {}",
+                enemy(*enemy_index),
+                self.named(|db| &db.enemies, |x| &x.name, *enemy_id)
+            ),
+            ShowBattleAnimation {
+                enemy_index,
+                animation_id,
+                all_enemies,
+            } => {
+                let target = if *all_enemies {
+                    "Entire Troop".to_string()
+                } else {
+                    enemy(*enemy_index)
+                };
+                format!(
+                    "Show Battle Animation : {target}, {}",
+                    self.named(|db| &db.animations, |x| &x.name, *animation_id)
+                )
+            }
+            ForceAction {
+                subject,
+                skill_id,
+                target_index,
+            } => {
+                let subject = match subject {
+                    Battler::Enemy(index) => enemy(*index),
+                    Battler::Actor(id) => self.actor(*id),
+                };
+                let target = match target_index {
+                    -2 => "Last Target".to_string(),
+                    -1 => "Random".to_string(),
+                    i => format!("Index {}", i + 1),
+                };
+                format!(
+                    "Force Action : {subject}, {}, {target}",
+                    self.skill(*skill_id)
+                )
+            }
+            AbortBattle => "Abort Battle".into(),
+            Script(text) => format!("Script : {text}"),
+            PluginCommand(text) => format!("Plugin Command : {text}"),
+            PluginCommandMz {
+                plugin,
+                command,
+                text,
+                ..
+            } => {
+                let text = if text.is_empty() { command } else { text };
+                format!("Plugin Command : {plugin}, {text}")
+            }
+            Unknown { code, parameters } => {
+                format!("Command {code} : {}", Value::from(parameters.clone()))
+            }
+        }
+    }
+
+    /// One line of a list, with the indent and marker the editor uses
+    pub fn line(&self, command: &EventCommand) -> String {
+        let indent = "  ".repeat(command.indent as usize);
+        match Command::decode(command) {
+            Ok(decoded) if decoded.is_continuation() => {
+                format!("{indent}: {}", self.command(&decoded))
+            }
+            Ok(decoded) => format!("{indent}◆{}", self.command(&decoded)),
+            Err(e) => format!(
+                "{indent}◆Command {} : {} ({e})",
+                command.code,
+                Value::from(command.parameters.clone())
+            ),
+        }
+    }
+
+    /// Every line of `list`, each ending in a newline
+    pub fn list(&self, list: &[EventCommand]) -> String {
+        list.iter().map(|x| self.line(x) + "\n").collect()
+    }
+}
@@ -0,0 +1,75 @@
+//! `System.json`, the only database file that isn't a list.
+
+use super::common::AudioFile;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// `$dataSystem.terms`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default)]
+pub struct Terms {
+    /// Level, HP, MP, TP, EXP and their abbreviations
+    pub basic: Vec<String>,
+    /// Menu and battle commands. Some entries are null in older projects.
+    pub commands: Vec<Option<String>>,
+    /// Parameter names, MHP to LUK followed by hit and evasion
+    pub params: Vec<String>,
+    pub messages: Map<String, Value>,
+}
+
+/// Boat, ship or airship
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Vehicle {
+    pub bgm: AudioFile,
+    pub character_name: String,
+    pub character_index: u32,
+    pub start_map_id: u32,
+    pub start_x: i32,
+    pub start_y: i32,
+}
+
+/// `System.json`. Lists of names (`switches`, `elements`, ...) are indexed by
+/// id and start with an empty string.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct System {
+    pub game_title: String,
+    pub currency_unit: String,
+    /// Editor locale, e.g. `en_US`. Missing before MV 1.1.
+    pub locale: String,
+    /// Changes every time the project is saved, which invalidates saves
+    /// when `DataManager` compares it
+    pub version_id: i64,
+    pub switches: Vec<String>,
+    pub variables: Vec<String>,
+    pub elements: Vec<String>,
+    pub skill_types: Vec<String>,
+    pub weapon_types: Vec<String>,
+    pub armor_types: Vec<String>,
+    pub equip_types: Vec<String>,
+    pub terms: Terms,
+    pub party_members: Vec<u32>,
+    pub start_map_id: u32,
+    pub start_x: i32,
+    pub start_y: i32,
+    pub boat: Vehicle,
+    pub ship: Vehicle,
+    pub airship: Vehicle,
+    pub title_bgm: AudioFile,
+    pub battle_bgm: AudioFile,
+    pub has_encrypted_images: bool,
+    pub has_encrypted_audio: bool,
+    /// Hex key for deployed games with encrypted assets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption_key: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl System {
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        super::read_file(path)
+    }
+}
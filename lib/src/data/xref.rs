@@ -0,0 +1,535 @@
+//! Where switches, variables, self switches and items are used in events.
+//!
+//! Covers map events, common events and troop pages: page conditions,
+//! autonomous movement routes and every command of their lists. Scripts and
+//! message text are searched for the usual `$gameSwitches.value(n)`,
+//! `$gameVariables.setValue(n, ...)` and `\V[n]` forms, but anything computed
+//! at runtime can't be found this way.
+
+use super::{
+    Database,
+    command::{
+        ActorRef, Command, Condition, Location, Operand, Position, TroopRef, VariableOperand,
+        VariableOperation,
+    },
+    event::{EventCommand, MoveRoute},
+    map::{Map, PageConditions},
+    print::Printer,
+};
+use serde::Serialize;
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "id")]
+pub enum Target {
+    Switch(u32),
+    Variable(u32),
+    /// "A" to "D" of whichever event the reference is in. In common events
+    /// and troops that's the event that called them.
+    SelfSwitch(String),
+    Item(u32),
+}
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Switch(id) => write!(f, "switch {id}"),
+            Self::Variable(id) => write!(f, "variable {id}"),
+            Self::SelfSwitch(ch) => write!(f, "self switch {ch}"),
+            Self::Item(id) => write!(f, "item {id}"),
+        }
+    }
+}
+
+/// Whether the reference can change the value. Reads include any other
+/// mention, like an item sold in a shop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Source {
+    #[serde(rename_all = "camelCase")]
+    MapEvent {
+        map_id: u32,
+        map_name: String,
+        event_id: u32,
+        event_name: String,
+    },
+    CommonEvent {
+        id: u32,
+        name: String,
+    },
+    Troop {
+        id: u32,
+        name: String,
+    },
+}
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MapEvent {
+                map_id,
+                map_name,
+                event_id,
+                event_name,
+            } => write!(f, "Map{map_id:03} {map_name}, EV{event_id:03} {event_name}"),
+            Self::CommonEvent { id, name } => write!(f, "Common Event {id:04} {name}"),
+            Self::Troop { id, name } => write!(f, "Troop {id:04} {name}"),
+        }
+    }
+}
+
+/// Part of a page the reference is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "line")]
+pub enum Place {
+    Conditions,
+    /// Autonomous movement of a map event page
+    MoveRoute,
+    /// 1-based, the way the editor counts
+    Line(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reference {
+    pub target: Target,
+    pub access: Access,
+    pub source: Source,
+    /// 1-based, `None` for common events which don't have pages
+    pub page: Option<usize>,
+    pub place: Place,
+    /// The command as the editor shows it
+    pub text: String,
+}
+impl Display for Reference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)?;
+        if let Some(page) = self.page {
+            write!(f, ", page {page}")?;
+        }
+        match self.place {
+            Place::Conditions => write!(f, ", conditions")?,
+            Place::MoveRoute => write!(f, ", autonomous movement")?,
+            Place::Line(line) => write!(f, ", line {line}")?,
+        }
+        let access = match self.access {
+            Access::Read => "read",
+            Access::Write => "write",
+        };
+        write!(f, " ({access}): {}", self.text)
+    }
+}
+
+/// A command that couldn't be decoded, so anything it uses is missing from
+/// the index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadableCommand {
+    pub source: Source,
+    pub page: Option<usize>,
+    /// 1-based, the way the editor counts
+    pub line: usize,
+    pub code: u32,
+    pub error: String,
+}
+impl Display for UnreadableCommand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)?;
+        if let Some(page) = self.page {
+            write!(f, ", page {page}")?;
+        }
+        let Self {
+            line, code, error, ..
+        } = self;
+        write!(f, ", line {line} (code {code}): {error}")
+    }
+}
+
+/// Ids following each of `patterns` in `text`, e.g. `5` in `$gameSwitches.value(5)`
+fn script_ids(text: &str, patterns: &[&str]) -> Vec<u32> {
+    let mut ids = vec![];
+    for pattern in patterns {
+        for (start, _) in text.match_indices(pattern) {
+            let rest = text[start + pattern.len()..].trim_start();
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(rest, |end| &rest[..end]);
+            if let Ok(id) = digits.parse() {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// References in script code
+fn script_targets(script: &str, found: &mut Vec<(Target, Access)>) {
+    for id in script_ids(script, &["$gameSwitches.value("]) {
+        found.push((Target::Switch(id), Access::Read));
+    }
+    for id in script_ids(script, &["$gameSwitches.setValue("]) {
+        found.push((Target::Switch(id), Access::Write));
+    }
+    for id in script_ids(script, &["$gameVariables.value("]) {
+        found.push((Target::Variable(id), Access::Read));
+    }
+    for id in script_ids(script, &["$gameVariables.setValue("]) {
+        found.push((Target::Variable(id), Access::Write));
+    }
+}
+
+fn read_variable(id: u32, found: &mut Vec<(Target, Access)>) {
+    found.push((Target::Variable(id), Access::Read));
+}
+
+fn operand_targets(operand: Operand, found: &mut Vec<(Target, Access)>) {
+    if let Operand::Variable(id) = operand {
+        read_variable(id, found);
+    }
+}
+
+fn actor_targets(actor: ActorRef, found: &mut Vec<(Target, Access)>) {
+    if let ActorRef::Variable(id) = actor {
+        read_variable(id, found);
+    }
+}
+
+fn position_targets(position: Position, found: &mut Vec<(Target, Access)>) {
+    if let Position::Variables { x, y } = position {
+        read_variable(x, found);
+        read_variable(y, found);
+    }
+}
+
+fn location_targets(location: Location, found: &mut Vec<(Target, Access)>) {
+    if let Location::Variables { map_id, x, y } = location {
+        read_variable(map_id, found);
+        read_variable(x, found);
+        read_variable(y, found);
+    }
+}
+
+fn condition_targets(condition: &Condition, found: &mut Vec<(Target, Access)>) {
+    match condition {
+        Condition::Switch { switch_id, .. } => {
+            found.push((Target::Switch(*switch_id), Access::Read))
+        }
+        Condition::Variable {
+            variable_id,
+            operand,
+            ..
+        } => {
+            read_variable(*variable_id, found);
+            operand_targets(*operand, found);
+        }
+        Condition::SelfSwitch { ch, .. } => {
+            found.push((Target::SelfSwitch(ch.clone()), Access::Read))
+        }
+        Condition::Item(id) => found.push((Target::Item(*id), Access::Read)),
+        Condition::Script(script) => script_targets(script, found),
+        _ => {}
+    }
+}
+
+/// Everything `command` reads or writes
+fn command_targets(command: &Command) -> Vec<(Target, Access)> {
+    use Command::*;
+
+    let mut found = vec![];
+    match command {
+        Text(text) | ScrollingText(text) | When { text, .. } => {
+            for id in script_ids(text, &["\\V[", "\\v["]) {
+                read_variable(id, &mut found);
+            }
+        }
+        InputNumber { variable_id, .. } | SelectItem { variable_id, .. } => {
+            found.push((Target::Variable(*variable_id), Access::Write))
+        }
+        If(condition) => condition_targets(condition, &mut found),
+        ControlSwitches { start, end, .. } => {
+            for id in *start..=*end {
+                found.push((Target::Switch(id), Access::Write));
+            }
+        }
+        ControlVariables {
+            start,
+            end,
+            operation,
+            operand,
+        } => {
+            for id in *start..=*end {
+                if *operation != VariableOperation::Set {
+                    read_variable(id, &mut found);
+                }
+                found.push((Target::Variable(id), Access::Write));
+            }
+            match operand {
+                VariableOperand::Variable(id) => read_variable(*id, &mut found),
+                // The number of the item `param1` the party has
+                VariableOperand::GameData {
+                    kind: 0, param1, ..
+                } => {
+                    if let Ok(id) = u32::try_from(*param1) {
+                        found.push((Target::Item(id), Access::Read));
+                    }
+                }
+                VariableOperand::Script(script) => script_targets(script, &mut found),
+                _ => {}
+            }
+        }
+        ControlSelfSwitch { ch, .. } => found.push((Target::SelfSwitch(ch.clone()), Access::Write)),
+        ChangeGold { amount, .. } | ChangeWeapons { amount, .. } | ChangeArmors { amount, .. } => {
+            operand_targets(*amount, &mut found)
+        }
+        ChangeItems {
+            item_id, amount, ..
+        } => {
+            found.push((Target::Item(*item_id), Access::Write));
+            operand_targets(*amount, &mut found);
+        }
+        TransferPlayer { location, .. } | SetVehicleLocation { location, .. } => {
+            location_targets(*location, &mut found)
+        }
+        SetEventLocation { position, .. } => position_targets(*position, &mut found),
+        SetMovementRoute { route, .. } => route_targets(route, &mut found),
+        ShowPicture { settings, .. } | MovePicture { settings, .. } => {
+            position_targets(settings.position, &mut found)
+        }
+        GetLocationInfo {
+            variable_id,
+            position,
+            ..
+        } => {
+            found.push((Target::Variable(*variable_id), Access::Write));
+            position_targets(*position, &mut found);
+        }
+        BattleProcessing {
+            troop: TroopRef::Variable(id),
+            ..
+        } => read_variable(*id, &mut found),
+        ShopProcessing { good, .. } | ShopGood(good) if good.kind == 0 => {
+            found.push((Target::Item(good.id), Access::Read))
+        }
+        ChangeHp { actor, amount, .. }
+        | ChangeMp { actor, amount, .. }
+        | ChangeTp { actor, amount, .. }
+        | ChangeExp { actor, amount, .. }
+        | ChangeLevel { actor, amount, .. }
+        | ChangeParameter { actor, amount, .. } => {
+            actor_targets(*actor, &mut found);
+            operand_targets(*amount, &mut found);
+        }
+        ChangeState { actor, .. } | RecoverAll(actor) | ChangeSkill { actor, .. } => {
+            actor_targets(*actor, &mut found)
+        }
+        ChangeEnemyHp { amount, .. }
+        | ChangeEnemyMp { amount, .. }
+        | ChangeEnemyTp { amount, .. } => operand_targets(*amount, &mut found),
+        Script(script) | ScriptMore(script) => script_targets(script, &mut found),
+        _ => {}
+    }
+    found
+}
+
+/// Switches turned on or off by a movement route
+fn route_targets(route: &MoveRoute, found: &mut Vec<(Target, Access)>) {
+    for step in &route.list {
+        match step.code {
+            // Switch ON, Switch OFF
+            27 | 28 => {
+                if let Some(id) = step.parameters.first().and_then(|x| x.as_u64()) {
+                    found.push((Target::Switch(id as u32), Access::Write));
+                }
+            }
+            // Script
+            45 => {
+                if let Some(script) = step.parameters.first().and_then(|x| x.as_str()) {
+                    script_targets(script, found);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn page_condition_targets(conditions: &PageConditions) -> Vec<(Target, Access)> {
+    let mut found = vec![];
+    if conditions.switch1_valid {
+        found.push((Target::Switch(conditions.switch1_id), Access::Read));
+    }
+    if conditions.switch2_valid {
+        found.push((Target::Switch(conditions.switch2_id), Access::Read));
+    }
+    if conditions.variable_valid {
+        read_variable(conditions.variable_id, &mut found);
+    }
+    if conditions.self_switch_valid {
+        let ch = conditions.self_switch_ch.clone();
+        found.push((Target::SelfSwitch(ch), Access::Read));
+    }
+    if conditions.item_valid {
+        found.push((Target::Item(conditions.item_id), Access::Read));
+    }
+    found
+}
+
+/// Every reference found in the game's events
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Index {
+    pub references: Vec<Reference>,
+    /// Maps listed in `MapInfos.json` that couldn't be read
+    pub unreadable_maps: Vec<(u32, String)>,
+    /// Commands skipped because they couldn't be decoded
+    pub unreadable_commands: Vec<UnreadableCommand>,
+}
+impl Index {
+    /// Indexes the common events and troops of `database` and every map in
+    /// `data_dir` it lists
+    pub fn build(data_dir: &Path, database: &Database) -> Self {
+        let mut index = Self::default();
+        let printer = Printer::new(Some(database));
+
+        for (map_id, info) in database.map_infos.iter() {
+            let map_id = map_id as u32;
+            let map = match Map::load(data_dir, map_id) {
+                Ok(map) => map,
+                Err(e) => {
+                    index.unreadable_maps.push((map_id, e.to_string()));
+                    continue;
+                }
+            };
+
+            for (event_id, event) in map.events.iter() {
+                let source = Source::MapEvent {
+                    map_id,
+                    map_name: info.name.clone(),
+                    event_id: event_id as u32,
+                    event_name: event.name.clone(),
+                };
+                for (i, page) in event.pages.iter().enumerate() {
+                    let page_number = Some(i + 1);
+                    for (target, access) in page_condition_targets(&page.conditions) {
+                        index.references.push(Reference {
+                            target,
+                            access,
+                            source: source.clone(),
+                            page: page_number,
+                            place: Place::Conditions,
+                            text: "Page conditions".into(),
+                        });
+                    }
+
+                    // Only runs if the page moves by a custom route
+                    let mut found = vec![];
+                    if page.move_type == 3 {
+                        route_targets(&page.move_route, &mut found);
+                    }
+                    for (target, access) in found {
+                        index.references.push(Reference {
+                            target,
+                            access,
+                            source: source.clone(),
+                            page: page_number,
+                            place: Place::MoveRoute,
+                            text: "Autonomous movement".into(),
+                        });
+                    }
+
+                    index.add_list(&printer, &source, page_number, &page.list);
+                }
+            }
+        }
+
+        for (id, event) in database.common_events.iter() {
+            let source = Source::CommonEvent {
+                id: id as u32,
+                name: event.name.clone(),
+            };
+            // The trigger switch only matters for autorun and parallel
+            if event.trigger != 0 {
+                index.references.push(Reference {
+                    target: Target::Switch(event.switch_id),
+                    access: Access::Read,
+                    source: source.clone(),
+                    page: None,
+                    place: Place::Conditions,
+                    text: "Trigger switch".into(),
+                });
+            }
+            index.add_list(&printer, &source, None, &event.list);
+        }
+
+        for (id, troop) in database.troops.iter() {
+            let source = Source::Troop {
+                id: id as u32,
+                name: troop.name.clone(),
+            };
+            for (i, page) in troop.pages.iter().enumerate() {
+                if page.conditions.switch_valid {
+                    index.references.push(Reference {
+                        target: Target::Switch(page.conditions.switch_id),
+                        access: Access::Read,
+                        source: source.clone(),
+                        page: Some(i + 1),
+                        place: Place::Conditions,
+                        text: "Page conditions".into(),
+                    });
+                }
+                index.add_list(&printer, &source, Some(i + 1), &page.list);
+            }
+        }
+
+        index
+    }
+
+    fn add_list(
+        &mut self,
+        printer: &Printer,
+        source: &Source,
+        page: Option<usize>,
+        list: &[EventCommand],
+    ) {
+        for (i, command) in list.iter().enumerate() {
+            let decoded = match Command::decode(command) {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    self.unreadable_commands.push(UnreadableCommand {
+                        source: source.clone(),
+                        page,
+                        line: i + 1,
+                        code: command.code,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let mut found = command_targets(&decoded);
+            found.dedup();
+            for (target, access) in found {
+                self.references.push(Reference {
+                    target,
+                    access,
+                    source: source.clone(),
+                    page,
+                    place: Place::Line(i + 1),
+                    text: printer.line(command).trim_start().to_string(),
+                });
+            }
+        }
+    }
+
+    /// References to `target`, in the order maps, common events and troops
+    /// were indexed
+    pub fn find<'a>(&'a self, target: &'a Target) -> impl Iterator<Item = &'a Reference> {
+        self.references.iter().filter(move |x| &x.target == target)
+    }
+}
@@ -0,0 +1,254 @@
+//! Turns a deployed game with encrypted assets back into a project the
+//! editor can open.
+//!
+//! Encrypted files start with a 16 byte `RPGMV` header, followed by the
+//! original file with its first 16 bytes xored with the key from
+//! `System.json`. MV names them `.rpgmvp`/`.rpgmvo`/`.rpgmvm`, MZ appends `_`
+//! to the original extension.
+
+use crate::{
+    data::system::System,
+    encryption_key::EncryptionKey,
+    error::Error,
+    image,
+    layout::{Engine, GameLayout},
+    util::write_atomic,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+const SIGNATURE: [u8; 16] = [
+    0x52, 0x50, 0x47, 0x4d, 0x56, 0x00, 0x00, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Encrypted extension to the original one
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rpgmvp", "png"),
+    ("rpgmvo", "ogg"),
+    ("rpgmvm", "m4a"),
+    ("png_", "png"),
+    ("ogg_", "ogg"),
+    ("m4a_", "m4a"),
+];
+
+/// The file the editor opens a project by, and its contents
+const fn project_file(engine: Engine) -> (&'static str, &'static str) {
+    match engine {
+        Engine::Mv => ("Game.rpgproject", "RPGMV 1.6.2"),
+        Engine::Mz => ("game.rmmzproject", "RMMZ 1.8.0"),
+    }
+}
+
+/// Outcome of [`decrypt_game`]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub project_dir: PathBuf,
+    pub engine: Engine,
+    pub decrypted: usize,
+    pub copied: usize,
+    /// The project file, if there wasn't one already
+    pub project_file: Option<PathBuf>,
+    /// Files that couldn't be decrypted or copied, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+    /// `System.json` flags left set because some of those files are still
+    /// encrypted
+    pub kept_flags: Vec<&'static str>,
+}
+
+/// The `System.json` flag that says files with the original `extension`
+/// are encrypted
+fn encryption_flag(extension: &str) -> &'static str {
+    match extension {
+        "png" => "hasEncryptedImages",
+        _ => "hasEncryptedAudio",
+    }
+}
+
+/// Original extension of an encrypted file
+fn decrypted_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?;
+    EXTENSIONS
+        .iter()
+        .find(|(encrypted, _)| extension.eq_ignore_ascii_case(encrypted))
+        .map(|(_, original)| *original)
+}
+
+/// Decrypts the contents of one encrypted file
+pub fn decrypt_bytes(key: &EncryptionKey, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    if bytes.len() < 32 {
+        return Err(Error::Decrypt("File is too short".into()));
+    }
+    if bytes[..16] != SIGNATURE {
+        return Err(Error::Decrypt("Missing RPGMV header".into()));
+    }
+    image::decrypt(Some(key.clone()), bytes)
+}
+
+/// Whether `bytes` start the way a file of `extension` should, which they
+/// won't when the key is wrong
+fn has_magic(bytes: &[u8], extension: &str) -> bool {
+    match extension {
+        "png" => bytes.starts_with(b"\x89PNG"),
+        "ogg" => bytes.starts_with(b"OggS"),
+        "m4a" => bytes.get(4..8) == Some(b"ftyp"),
+        _ => true,
+    }
+}
+
+fn decrypt_file(
+    key: &EncryptionKey,
+    path: &Path,
+    out: &Path,
+    extension: &str,
+) -> Result<(), Error> {
+    let bytes = decrypt_bytes(key, &std::fs::read(path)?)?;
+    if !has_magic(&bytes, extension) {
+        let msg = format!("Doesn't decrypt to a .{extension} file, is the key right?");
+        return Err(Error::Decrypt(msg));
+    }
+
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_atomic(out, &bytes)
+}
+
+fn copy_file(path: &Path, out: &Path) -> Result<(), Error> {
+    if let Some(parent) = out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(path, out)?;
+    Ok(())
+}
+
+fn files(dir: &Path, skip: Option<&Path>, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|x| x.map(|x| x.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        if Some(path.as_path()) == skip {
+            continue;
+        }
+        if path.is_dir() {
+            self::files(&path, skip, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Clears `hasEncryptedImages` and `hasEncryptedAudio`, except for `keep`
+fn clear_encryption_flags(path: &Path, keep: &[&str]) -> Result<(), Error> {
+    let text = std::fs::read_to_string(path)?;
+    let mut system: Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))?;
+
+    let Some(object) = system.as_object_mut() else {
+        return Err(Error::Decrypt(format!(
+            "{} isn't an object",
+            path.display()
+        )));
+    };
+    for flag in ["hasEncryptedImages", "hasEncryptedAudio"] {
+        if object.contains_key(flag) && !keep.contains(&flag) {
+            object.insert(flag.into(), Value::Bool(false));
+        }
+    }
+    write_atomic(path, serde_json::to_string(&system)?.as_bytes())
+}
+
+/// Decrypts the assets of the game `game_dir` is in and writes a project file
+/// for the editor.
+///
+/// With `out_dir`, the project is copied there with its assets decrypted
+/// and the game is left untouched. Otherwise the encrypted files are
+/// replaced in place. `key` overrides the key in `System.json`.
+pub fn decrypt_game(
+    game_dir: &Path,
+    out_dir: Option<&Path>,
+    key: Option<EncryptionKey>,
+) -> Result<Report, Error> {
+    let layout = GameLayout::find(game_dir)?;
+    if let Some(package) = &layout.package {
+        let msg = format!(
+            "The game is bundled in {}, extract it first",
+            package.path.display()
+        );
+        return Err(Error::Decrypt(msg));
+    }
+    let source = layout.project_dir;
+    let system_path = source.join("data").join("System.json");
+
+    let key = match key {
+        Some(key) => key,
+        None => {
+            let system = System::read_file(&system_path)?;
+            let hex = system.encryption_key.filter(|x| !x.is_empty());
+            let hex = hex.ok_or_else(|| {
+                Error::Decrypt("System.json has no encryptionKey, pass the key instead".into())
+            })?;
+            EncryptionKey::from_hex_str(&hex)?
+        }
+    };
+
+    let target = out_dir.map_or_else(|| source.clone(), Path::to_path_buf);
+    let mut report = Report {
+        project_dir: target.clone(),
+        engine: layout.engine,
+        decrypted: 0,
+        copied: 0,
+        project_file: None,
+        failed: vec![],
+        kept_flags: vec![],
+    };
+
+    let mut paths = vec![];
+    // Layout paths are canonical, so the output has to be too to skip it
+    let skip = out_dir.and_then(|x| x.canonicalize().ok());
+    files(&source, skip.as_deref(), &mut paths)?;
+    for path in paths {
+        let relative = path.strip_prefix(&source).unwrap_or(&path);
+        let out = target.join(relative);
+
+        let result = match decrypted_extension(&path) {
+            Some(extension) => {
+                let out = out.with_extension(extension);
+                decrypt_file(&key, &path, &out, extension).and_then(|_| {
+                    if out_dir.is_none() {
+                        std::fs::remove_file(&path)?;
+                    }
+                    report.decrypted += 1;
+                    Ok(())
+                })
+            }
+            None if out_dir.is_some() => copy_file(&path, &out).map(|_| report.copied += 1),
+            None => continue,
+        };
+        if let Err(e) = result {
+            // The game can't load a file left encrypted once the flag is off
+            if let Some(extension) = decrypted_extension(&path) {
+                let flag = encryption_flag(extension);
+                if !report.kept_flags.contains(&flag) {
+                    report.kept_flags.push(flag);
+                }
+            }
+            report.failed.push((path, e.to_string()));
+        }
+    }
+
+    let target_system = target.join("data").join("System.json");
+    clear_encryption_flags(&target_system, &report.kept_flags)?;
+
+    let (name, contents) = project_file(layout.engine);
+    let project_file = target.join(name);
+    if !project_file.exists() {
+        std::fs::write(&project_file, contents)?;
+        report.project_file = Some(project_file);
+    }
+
+    Ok(report)
+}
@@ -1,6 +1,6 @@
 use crate::error::Error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct EncryptionKey([u8; 16]);
 impl EncryptionKey {
     pub fn new(inner: [u8; 16]) -> Self {
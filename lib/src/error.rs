@@ -12,6 +12,15 @@ pub enum Error {
     Json(String),
     Key(String),
     Io(std::io::ErrorKind),
+    Save(String),
+    Data(String),
+    Storage(String),
+    Patch(String),
+    Translation(String),
+    Decrypt(String),
+    Plugins(String),
+    Layout(String),
+    Package(String),
 }
 impl From<JsonError> for Error {
     fn from(value: JsonError) -> Self {
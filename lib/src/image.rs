@@ -1,4 +1,4 @@
-use image::{GenericImage, GenericImageView, Pixel, SubImage};
+use image::{GenericImage, SubImage};
 
 use crate::{encryption_key::EncryptionKey, error::Error};
 use std::io::{BufReader, Read};
@@ -62,9 +62,6 @@ impl SpritesheetKind {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct ImageData(Vec<u8>);
-
 #[ouroboros::self_referencing]
 struct SpritesheetImage<Image>
 where
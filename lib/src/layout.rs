@@ -0,0 +1,259 @@
+//! Where a game keeps its files.
+//!
+//! MV desktop builds put the game in `www` next to the executable, while MZ
+//! and web builds (and projects straight from the editor) keep `data`, `img`
+//! and `js` at the top level. [`GameLayout::find`] works from any path
+//! inside the game, so a save directory, the `data` directory or the
+//! executable all lead to the same layout. Saves kept elsewhere need the
+//! game's directory given with [`GameLayout::open`].
+//!
+//! Games bundled into `package.nw` or an Enigma Virtual Box executable have
+//! no loose files, so their directories only exist inside the
+//! [`Package`]. [`GameLayout::read`] looks in both, loose files first.
+
+use crate::{error::Error, package::Package};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Engine {
+    Mv,
+    Mz,
+}
+impl Engine {
+    /// Extension of encrypted images
+    pub const fn encrypted_image_extension(&self) -> &'static str {
+        match self {
+            Self::Mv => "rpgmvp",
+            Self::Mz => "png_",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Platform {
+    /// NW.js, saving to files in `save`
+    Desktop,
+    /// Browser, saving to localStorage
+    Web,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GameLayout {
+    /// Top directory of the game, with the executable for desktop builds
+    pub root: PathBuf,
+    /// The directory with `index.html`, `data` and `js`: `www` for MV
+    /// desktop builds, otherwise the root
+    pub project_dir: PathBuf,
+    pub engine: Engine,
+    pub platform: Platform,
+    /// Where the game's files are when they aren't loose
+    pub package: Option<Package>,
+}
+impl GameLayout {
+    fn is_project_dir(dir: &Path) -> bool {
+        dir.join("data").join("System.json").is_file()
+    }
+
+    fn is_desktop(root: &Path) -> bool {
+        if root.join("package.json").is_file() || root.join("package.nw").exists() {
+            return true;
+        }
+        std::fs::read_dir(root).is_ok_and(|mut entries| {
+            entries.any(|entry| {
+                entry.is_ok_and(|x| {
+                    x.path()
+                        .extension()
+                        .is_some_and(|x| x.eq_ignore_ascii_case("exe"))
+                })
+            })
+        })
+    }
+
+    /// A package in `dir` with the game in it, and the game's directory
+    fn find_package(dir: &Path) -> Option<(Package, PathBuf)> {
+        let mut candidates = vec![dir.join("package.nw")];
+        let mut executables = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|x| x.extension().is_some_and(|x| x.eq_ignore_ascii_case("exe")))
+            .collect::<Vec<_>>();
+        executables.sort();
+        candidates.extend(executables);
+
+        candidates
+            .into_iter()
+            .filter(|x| x.is_file())
+            .filter_map(|x| Package::open(&x).ok())
+            .find_map(|package| {
+                if package.contains("www/data/System.json") {
+                    Some((package, dir.join("www")))
+                } else if package.contains("data/System.json") {
+                    Some((package, dir.to_path_buf()))
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn detect_engine(&self) -> Engine {
+        let js = self.js_dir();
+        if self.exists(&js.join("rmmz_core.js")) {
+            return Engine::Mz;
+        }
+        if self.exists(&js.join("rpg_core.js")) {
+            return Engine::Mv;
+        }
+
+        // Without the engine's scripts, go by System.json: only MZ has `advanced`
+        let system = self.read(&self.data_dir().join("System.json"));
+        match system {
+            Ok(system) if system.windows(10).any(|x| x == b"\"advanced\"") => Engine::Mz,
+            _ => Engine::Mv,
+        }
+    }
+
+    /// The game directory `dir` is for: `dir` itself, its `www`, or a
+    /// package in it
+    fn locate(dir: &Path) -> Option<(PathBuf, Option<Package>)> {
+        let www = dir.join("www");
+        if Self::is_project_dir(dir) {
+            Some((dir.to_path_buf(), None))
+        } else if Self::is_project_dir(&www) {
+            Some((www, None))
+        } else {
+            let (package, project_dir) = Self::find_package(dir)?;
+            Some((project_dir, Some(package)))
+        }
+    }
+
+    fn from_project_dir(project_dir: PathBuf, package: Option<Package>) -> Self {
+        let is_www = project_dir
+            .file_name()
+            .is_some_and(|x| x.eq_ignore_ascii_case("www"));
+        let root = match project_dir.parent() {
+            Some(parent) if is_www => parent.to_path_buf(),
+            _ => project_dir.clone(),
+        };
+
+        let mut layout = Self {
+            platform: if package.is_some() || Self::is_desktop(&root) {
+                Platform::Desktop
+            } else {
+                Platform::Web
+            },
+            engine: Engine::Mv,
+            root,
+            project_dir,
+            package,
+        };
+        layout.engine = layout.detect_engine();
+        layout
+    }
+
+    /// Finds the game `path` is in, by going up from it until a directory
+    /// with the game's files, or a package with them, turns up.
+    ///
+    /// Saves kept outside the game, in a directory a plugin picked or in
+    /// NW.js's user data directory, aren't in it; use [`GameLayout::open`]
+    /// with the game's directory for those.
+    pub fn find(path: &Path) -> Result<Self, Error> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        let (project_dir, package) = path
+            .ancestors()
+            .find_map(Self::locate)
+            .ok_or_else(|| Error::Layout(format!("{} isn't in a game", path.display())))?;
+        Ok(Self::from_project_dir(project_dir, package))
+    }
+
+    /// The game in `game_dir`, which is its top directory, `www` or the
+    /// directory with its package
+    pub fn open(game_dir: &Path) -> Result<Self, Error> {
+        let game_dir = game_dir
+            .canonicalize()
+            .unwrap_or_else(|_| game_dir.to_path_buf());
+
+        let (project_dir, package) = Self::locate(&game_dir)
+            .ok_or_else(|| Error::Layout(format!("{} isn't a game", game_dir.display())))?;
+        Ok(Self::from_project_dir(project_dir, package))
+    }
+
+    /// [`GameLayout::open`] with `game_dir` when there is one, otherwise
+    /// [`GameLayout::find`] from `save_dir`
+    pub fn for_saves(save_dir: &Path, game_dir: Option<&Path>) -> Result<Self, Error> {
+        match game_dir {
+            Some(game_dir) => Self::open(game_dir),
+            None => Self::find(save_dir),
+        }
+    }
+
+    /// Path of `path` inside the package
+    fn package_path(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts = relative
+            .components()
+            .map(|x| x.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join("/"))
+    }
+
+    /// Whether the file at `path` exists, loose or in the package
+    pub fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+            || self.package.as_ref().is_some_and(|package| {
+                self.package_path(path)
+                    .is_some_and(|x| package.contains(&x))
+            })
+    }
+
+    /// Contents of the file at `path`, loose or in the package
+    pub fn read(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        if path.is_file() {
+            return Ok(std::fs::read(path)?);
+        }
+        match (&self.package, self.package_path(path)) {
+            (Some(package), Some(x)) if package.contains(&x) => package.read(&x),
+            _ => Err(Error::Layout(format!("Missing file: {}", path.display()))),
+        }
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        self.project_dir.join("data")
+    }
+
+    pub fn img_dir(&self) -> PathBuf {
+        self.project_dir.join("img")
+    }
+
+    pub fn audio_dir(&self) -> PathBuf {
+        self.project_dir.join("audio")
+    }
+
+    pub fn js_dir(&self) -> PathBuf {
+        self.project_dir.join("js")
+    }
+
+    /// Where desktop builds save. Web builds save to localStorage instead,
+    /// see [`crate::local_storage`].
+    pub fn save_dir(&self) -> PathBuf {
+        self.project_dir.join("save")
+    }
+
+    /// The image `name` in `img/<folder>`, encrypted or not. Falls back to
+    /// the encrypted path when neither exists.
+    pub fn image_path(&self, folder: &str, name: &str) -> PathBuf {
+        let dir = self.img_dir().join(folder);
+        let encrypted = dir.join(format!(
+            "{name}.{}",
+            self.engine.encrypted_image_extension()
+        ));
+        let plain = dir.join(format!("{name}.png"));
+
+        if !self.exists(&encrypted) && self.exists(&plain) {
+            plain
+        } else {
+            encrypted
+        }
+    }
+}
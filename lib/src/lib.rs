@@ -1,4 +1,12 @@
+pub mod data;
+pub mod decrypt;
 pub mod encryption_key;
 pub mod error;
 pub mod image;
+pub mod layout;
+pub mod local_storage;
+pub mod package;
+pub mod plugins;
 pub mod save;
+pub mod translation;
+pub mod util;
@@ -0,0 +1,634 @@
+//! Just enough of LevelDB's on-disk format to read a Chromium `Local Storage/leveldb`
+//! directory: write-ahead logs (`.log`) and sorted tables (`.ldb` / `.sst`).
+//!
+//! Only the tables the `MANIFEST` lists and the logs it hasn't compacted yet
+//! are read. Files left behind by a compaction can still hold values whose
+//! deletion the compaction dropped, which would bring them back. Within
+//! those, the newest record for each key wins, by sequence number.
+//!
+//! Writes are appended to the current log as a single batch, which LevelDB
+//! replays the next time the database is opened.
+
+use crate::error::Error;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{File, OpenOptions, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const LOG_BLOCK_SIZE: usize = 32 * 1024;
+const LOG_HEADER_SIZE: usize = 7;
+const TABLE_FOOTER_SIZE: usize = 48;
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const BLOCK_TRAILER_SIZE: usize = 5;
+
+const TYPE_DELETION: u8 = 0;
+const TYPE_VALUE: u8 = 1;
+
+const LOG_FULL: u8 = 1;
+const LOG_FIRST: u8 = 2;
+const LOG_MIDDLE: u8 = 3;
+const LOG_LAST: u8 = 4;
+
+fn corrupt(msg: impl std::fmt::Display) -> Error {
+    Error::Storage(format!("Corrupt LevelDB data: {msg}"))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < len {
+            return Err(corrupt("unexpected end of data"));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        let bytes = self.bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(corrupt("varint too long"))
+    }
+
+    /// Varint length followed by that many bytes
+    pub fn slice(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.varint()? as usize;
+        self.bytes(len)
+    }
+}
+
+/// One put or delete, tagged with its sequence number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Record {
+    pub sequence: u64,
+    pub value: Option<Vec<u8>>,
+}
+
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_slice(out: &mut Vec<u8>, data: &[u8]) {
+    put_varint(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+/// Latest state of every key in a database
+#[derive(Debug, Default)]
+pub(crate) struct Database {
+    pub records: BTreeMap<Vec<u8>, Record>,
+}
+impl Database {
+    fn insert(&mut self, key: &[u8], record: Record) {
+        match self.records.get(key) {
+            Some(existing) if existing.sequence > record.sequence => {}
+            _ => {
+                self.records.insert(key.to_vec(), record);
+            }
+        }
+    }
+
+    fn max_sequence(&self) -> u64 {
+        self.records.values().map(|x| x.sequence).max().unwrap_or(0)
+    }
+
+    /// Keys with a live value
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.records
+            .iter()
+            .filter_map(|(key, record)| Some((key.as_slice(), record.value.as_deref()?)))
+    }
+}
+
+/// Joins the fragments of a log file back into whole records
+fn read_log_records(data: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+    let mut records = vec![];
+    let mut pending = Vec::new();
+
+    for block in data.chunks(LOG_BLOCK_SIZE) {
+        let mut reader = Reader::new(block);
+
+        while reader.data.len() >= LOG_HEADER_SIZE {
+            let _checksum = reader.u32()?;
+            let length = u16::from_le_bytes(reader.bytes(2)?.try_into().unwrap()) as usize;
+            let kind = reader.u8()?;
+
+            // Zeroed space at the end of a preallocated file
+            if kind == 0 && length == 0 {
+                break;
+            }
+
+            // A torn write at the end of the log, ignored the same way LevelDB does
+            let Ok(payload) = reader.bytes(length) else {
+                break;
+            };
+
+            match kind {
+                LOG_FULL => records.push(payload.to_vec()),
+                LOG_FIRST => {
+                    pending.clear();
+                    pending.extend_from_slice(payload);
+                }
+                LOG_MIDDLE => pending.extend_from_slice(payload),
+                LOG_LAST => {
+                    pending.extend_from_slice(payload);
+                    records.push(std::mem::take(&mut pending));
+                }
+                _ => return Err(corrupt(format!("unknown log record type {kind}"))),
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+fn read_write_batch(data: &[u8], db: &mut Database) -> Result<(), Error> {
+    let mut reader = Reader::new(data);
+    let sequence = reader.u64()?;
+    let count = reader.u32()?;
+
+    for i in 0..count as u64 {
+        let kind = reader.u8()?;
+        let key = reader.slice()?;
+        let value = match kind {
+            TYPE_VALUE => Some(reader.slice()?.to_vec()),
+            TYPE_DELETION => None,
+            _ => return Err(corrupt(format!("unknown batch entry type {kind}"))),
+        };
+
+        db.insert(
+            key,
+            Record {
+                sequence: sequence + i,
+                value,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+fn read_log(data: &[u8], db: &mut Database) -> Result<(), Error> {
+    for record in read_log_records(data)? {
+        read_write_batch(&record, db)?;
+    }
+    Ok(())
+}
+
+struct BlockHandle {
+    offset: usize,
+    size: usize,
+}
+impl BlockHandle {
+    fn read(reader: &mut Reader) -> Result<Self, Error> {
+        Ok(Self {
+            offset: reader.varint()? as usize,
+            size: reader.varint()? as usize,
+        })
+    }
+}
+
+fn read_block(table: &[u8], handle: &BlockHandle) -> Result<Vec<u8>, Error> {
+    let end = handle
+        .offset
+        .checked_add(handle.size)
+        .filter(|&end| table.len().checked_sub(end) >= Some(BLOCK_TRAILER_SIZE))
+        .ok_or_else(|| corrupt("block out of range"))?;
+
+    let contents = &table[handle.offset..end];
+    match table[end] {
+        0 => Ok(contents.to_vec()),
+        1 => snap::raw::Decoder::new()
+            .decompress_vec(contents)
+            .map_err(corrupt),
+        kind => Err(corrupt(format!("unknown block compression {kind}"))),
+    }
+}
+
+type BlockEntry<'a> = (Vec<u8>, &'a [u8]);
+
+/// Key/value pairs of a table block, with prefix compression undone
+fn block_entries(block: &[u8]) -> Result<Vec<BlockEntry<'_>>, Error> {
+    if block.len() < 4 {
+        return Err(corrupt("block too small"));
+    }
+    let restart_count = u32::from_le_bytes(block[block.len() - 4..].try_into().unwrap()) as usize;
+    let entries_end = block
+        .len()
+        .checked_sub(4 + restart_count * 4)
+        .ok_or_else(|| corrupt("invalid restart count"))?;
+
+    let mut reader = Reader::new(&block[..entries_end]);
+    let mut entries = vec![];
+    let mut key = Vec::new();
+
+    while !reader.is_empty() {
+        let shared = reader.varint()? as usize;
+        let non_shared = reader.varint()? as usize;
+        let value_len = reader.varint()? as usize;
+
+        key.truncate(shared);
+        key.extend_from_slice(reader.bytes(non_shared)?);
+        let value = reader.bytes(value_len)?;
+
+        entries.push((key.clone(), value));
+    }
+
+    Ok(entries)
+}
+
+fn read_table(table: &[u8], db: &mut Database) -> Result<(), Error> {
+    if table.len() < TABLE_FOOTER_SIZE {
+        return Err(corrupt("table too small"));
+    }
+
+    let footer = &table[table.len() - TABLE_FOOTER_SIZE..];
+    let magic = u64::from_le_bytes(footer[40..].try_into().unwrap());
+    if magic != TABLE_MAGIC {
+        return Err(corrupt("bad table magic"));
+    }
+
+    let mut reader = Reader::new(footer);
+    let _metaindex = BlockHandle::read(&mut reader)?;
+    let index = BlockHandle::read(&mut reader)?;
+
+    let index = read_block(table, &index)?;
+    for (_, handle) in block_entries(&index)? {
+        let handle = BlockHandle::read(&mut Reader::new(handle))?;
+        let block = read_block(table, &handle)?;
+
+        for (internal_key, value) in block_entries(&block)? {
+            // User key followed by (sequence << 8 | type)
+            let Some(split) = internal_key.len().checked_sub(8) else {
+                return Err(corrupt("internal key too short"));
+            };
+            let (key, tag) = internal_key.split_at(split);
+            let tag = u64::from_le_bytes(tag.try_into().unwrap());
+
+            let value = match (tag & 0xff) as u8 {
+                TYPE_VALUE => Some(value.to_vec()),
+                TYPE_DELETION => None,
+                kind => return Err(corrupt(format!("unknown key type {kind}"))),
+            };
+
+            db.insert(
+                key,
+                Record {
+                    sequence: tag >> 8,
+                    value,
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn files_with_extension<'a>(
+    files: &'a [PathBuf],
+    extensions: &'a [&str],
+) -> impl Iterator<Item = &'a PathBuf> {
+    files.iter().filter(|path| {
+        path.extension()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| extensions.contains(&x))
+    })
+}
+
+/// Number LevelDB named a file with, like 5 for `000005.ldb`
+fn file_number(path: &Path) -> Option<u64> {
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+/// Reads the live tables and logs in `dir`
+pub(crate) fn read_database(dir: &Path) -> Result<Database, Error> {
+    if !dir.join("CURRENT").exists() {
+        let msg = format!("Not a LevelDB directory: {}", dir.display());
+        return Err(Error::Storage(msg));
+    }
+
+    let manifest = read_manifest(dir)?;
+    let files = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    let mut db = Database::default();
+
+    for path in files_with_extension(&files, &["ldb", "sst"]) {
+        if file_number(path).is_some_and(|x| manifest.tables.contains(&x)) {
+            read_table(&std::fs::read(path)?, &mut db)?;
+        }
+    }
+    // Older logs were compacted into the tables already
+    for path in files_with_extension(&files, &["log"]) {
+        let is_live = file_number(path).is_some_and(|x| {
+            x >= manifest.log_number
+                || (manifest.prev_log_number != 0 && x == manifest.prev_log_number)
+        });
+        if is_live {
+            read_log(&std::fs::read(path)?, &mut db)?;
+        }
+    }
+
+    Ok(db)
+}
+
+/// The parts of the `MANIFEST` needed to read the database and append to
+/// the log
+#[derive(Debug, Default)]
+struct Manifest {
+    log_number: u64,
+    prev_log_number: u64,
+    last_sequence: u64,
+    /// Numbers of the tables in the current version
+    tables: BTreeSet<u64>,
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, Error> {
+    let current = std::fs::read_to_string(dir.join("CURRENT"))?;
+    let data = std::fs::read(dir.join(current.trim()))?;
+
+    let mut manifest = Manifest::default();
+
+    // Each record is a VersionEdit, later ones override earlier ones
+    for record in read_log_records(&data)? {
+        let mut reader = Reader::new(&record);
+        while !reader.is_empty() {
+            match reader.varint()? {
+                // Comparator
+                1 => {
+                    reader.slice()?;
+                }
+                2 => manifest.log_number = reader.varint()?,
+                // Next file number
+                3 => {
+                    reader.varint()?;
+                }
+                9 => manifest.prev_log_number = reader.varint()?,
+                4 => manifest.last_sequence = reader.varint()?,
+                // Compaction pointer
+                5 => {
+                    reader.varint()?;
+                    reader.slice()?;
+                }
+                // Deleted file: level and number
+                6 => {
+                    reader.varint()?;
+                    manifest.tables.remove(&reader.varint()?);
+                }
+                // New file: level, number, size and key range
+                7 => {
+                    reader.varint()?;
+                    manifest.tables.insert(reader.varint()?);
+                    reader.varint()?;
+                    reader.slice()?;
+                    reader.slice()?;
+                }
+                tag => return Err(corrupt(format!("unknown manifest tag {tag}"))),
+            }
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Checksum stored in log record headers
+fn masked_crc(kind: u8, payload: &[u8]) -> u32 {
+    let crc = crc32c::crc32c_append(crc32c::crc32c(&[kind]), payload);
+    crc.rotate_right(15).wrapping_add(0xa282ead8)
+}
+
+/// Splits `record` into log fragments, starting `offset` bytes into the file
+fn log_fragments(record: &[u8], offset: u64) -> Vec<u8> {
+    let mut out = vec![];
+    let mut block_offset = (offset % LOG_BLOCK_SIZE as u64) as usize;
+    let mut rest = record;
+    let mut is_first = true;
+
+    loop {
+        let left_in_block = LOG_BLOCK_SIZE - block_offset;
+        if left_in_block < LOG_HEADER_SIZE {
+            // Too small for a header, padded with zeros
+            out.resize(out.len() + left_in_block, 0);
+            block_offset = 0;
+            continue;
+        }
+
+        let length = rest.len().min(left_in_block - LOG_HEADER_SIZE);
+        let (payload, remaining) = rest.split_at(length);
+        let is_last = remaining.is_empty();
+        let kind = match (is_first, is_last) {
+            (true, true) => LOG_FULL,
+            (true, false) => LOG_FIRST,
+            (false, false) => LOG_MIDDLE,
+            (false, true) => LOG_LAST,
+        };
+
+        out.extend_from_slice(&masked_crc(kind, payload).to_le_bytes());
+        out.extend_from_slice(&(length as u16).to_le_bytes());
+        out.push(kind);
+        out.extend_from_slice(payload);
+
+        block_offset += LOG_HEADER_SIZE + length;
+        rest = remaining;
+        is_first = false;
+
+        if is_last {
+            return out;
+        }
+    }
+}
+
+/// Takes the lock LevelDB holds while a database is open
+fn lock(dir: &Path) -> Result<File, Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("LOCK"))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(Error::Storage(
+            "Database is in use, close the game or browser first".into(),
+        )),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Writes `puts` as one batch, newer than everything already in the database
+pub(crate) fn write_batch(dir: &Path, puts: &[(Vec<u8>, Vec<u8>)]) -> Result<(), Error> {
+    let _lock = lock(dir)?;
+
+    let manifest = read_manifest(dir)?;
+    let db = read_database(dir)?;
+    let sequence = manifest.last_sequence.max(db.max_sequence()) + 1;
+
+    let mut batch = vec![];
+    batch.extend_from_slice(&sequence.to_le_bytes());
+    batch.extend_from_slice(&(puts.len() as u32).to_le_bytes());
+    for (key, value) in puts {
+        batch.push(TYPE_VALUE);
+        put_slice(&mut batch, key);
+        put_slice(&mut batch, value);
+    }
+
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(format!("{:06}.log", manifest.log_number)))?;
+    let offset = log.metadata()?.len();
+
+    log.write_all(&log_fragments(&batch, offset))?;
+    log.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Copy of `testdata/leveldb`, a database written by LevelDB itself with
+    /// the keys Chromium uses, that a test can write to
+    pub(crate) fn fixture(name: &str) -> PathBuf {
+        let source =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/local_storage/testdata/leveldb");
+        let dir = std::env::temp_dir().join(format!("lib-leveldb-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for entry in std::fs::read_dir(source).unwrap() {
+            let entry = entry.unwrap();
+            std::fs::copy(entry.path(), dir.join(entry.file_name())).unwrap();
+        }
+        dir
+    }
+
+    fn live_keys(db: &Database) -> Vec<&[u8]> {
+        db.entries().map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn fixture_is_read() {
+        let dir = fixture("read");
+        let manifest = read_manifest(&dir).unwrap();
+        assert_eq!(manifest.tables, BTreeSet::from([167, 171]));
+        assert_eq!(manifest.log_number, 172);
+
+        let db = read_database(&dir).unwrap();
+        let keys = live_keys(&db);
+        for key in [
+            &b"_file://\0\x01RPG File1"[..],
+            b"_chrome-extension://abcdef\0\x01RPG Global",
+            b"_chrome-extension://abcdef\0\x01RPG File2",
+            b"_chrome-extension://abcdef\0\x01RPG File3",
+        ] {
+            assert!(keys.contains(&key), "missing {}", key.escape_ascii());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn records_spanning_blocks_are_joined() {
+        // Ends 100 bytes before the first block does
+        let filler = vec![b'a'; LOG_BLOCK_SIZE - 100 - LOG_HEADER_SIZE];
+        let spanning = (0..2 * LOG_BLOCK_SIZE).map(|i| i as u8).collect::<Vec<_>>();
+
+        let mut log = log_fragments(&filler, 0);
+        assert_eq!(log.len(), LOG_BLOCK_SIZE - 100);
+        log.extend(log_fragments(&spanning, log.len() as u64));
+        // First, middle and last
+        assert_eq!(
+            log.len(),
+            spanning.len() + filler.len() + 4 * LOG_HEADER_SIZE
+        );
+
+        assert_eq!(read_log_records(&log).unwrap(), [filler, spanning]);
+    }
+
+    #[test]
+    fn block_tails_too_small_for_a_header_are_skipped() {
+        let filler = vec![b'a'; LOG_BLOCK_SIZE - 3 - LOG_HEADER_SIZE];
+        let next = b"next".to_vec();
+
+        let mut log = log_fragments(&filler, 0);
+        log.extend(log_fragments(&next, log.len() as u64));
+        assert_eq!(log.len(), LOG_BLOCK_SIZE + LOG_HEADER_SIZE + next.len());
+        assert_eq!(log[LOG_BLOCK_SIZE - 3..LOG_BLOCK_SIZE], [0, 0, 0]);
+
+        assert_eq!(read_log_records(&log).unwrap(), [filler, next]);
+    }
+
+    #[test]
+    fn written_batches_are_read_back() {
+        let dir = fixture("write");
+        let before = read_database(&dir).unwrap();
+        let key = b"_file://\0\x01RPG File1".to_vec();
+        assert_ne!(before.records[&key].value.as_deref(), Some(&b"\x01new"[..]));
+
+        let large = vec![b'x'; 3 * LOG_BLOCK_SIZE];
+        write_batch(&dir, &[(key.clone(), b"\x01new".to_vec())]).unwrap();
+        write_batch(
+            &dir,
+            &[
+                (b"large".to_vec(), large.clone()),
+                (b"small".to_vec(), b"1".to_vec()),
+            ],
+        )
+        .unwrap();
+
+        let after = read_database(&dir).unwrap();
+        assert_eq!(after.records[&key].value.as_deref(), Some(&b"\x01new"[..]));
+        assert_eq!(after.records[&b"large"[..]].value.as_ref(), Some(&large));
+        assert_eq!(
+            after.records[&b"small"[..]].value.as_deref(),
+            Some(&b"1"[..])
+        );
+
+        // Newer than everything LevelDB wrote, so it wins when LevelDB replays the log
+        let sequence = after.records[&key].sequence;
+        assert!(sequence > before.max_sequence());
+        assert!(sequence > read_manifest(&dir).unwrap().last_sequence);
+        assert_eq!(after.records[&b"small"[..]].sequence, sequence + 2);
+
+        assert_eq!(live_keys(&after).len(), live_keys(&before).len() + 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,409 @@
+//! Saves kept in a Chromium / NW.js `Local Storage/leveldb` directory, where
+//! web builds of MV games write them (`StorageManager.saveToWebStorage`).
+//!
+//! Each value is the same lzstring base64 text as a `.rpgsave` file.
+//!
+//! The game or browser using the directory must be closed when importing,
+//! otherwise it overwrites the imported saves with the ones it has loaded.
+
+mod leveldb;
+
+use crate::{
+    error::Error,
+    save::{Format, Json, backup, compress},
+    util::write_atomic,
+};
+use std::{
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+/// Key from `StorageManager.webStorageKey`
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum SaveKey {
+    Config,
+    Global,
+    File(usize),
+}
+impl SaveKey {
+    pub fn storage_key(self) -> String {
+        match self {
+            Self::Config => "RPG Config".to_string(),
+            Self::Global => "RPG Global".to_string(),
+            Self::File(id) => format!("RPG File{id}"),
+        }
+    }
+
+    pub fn from_storage_key(key: &str) -> Option<Self> {
+        match key {
+            "RPG Config" => Some(Self::Config),
+            "RPG Global" => Some(Self::Global),
+            _ => key.strip_prefix("RPG File")?.parse().ok().map(Self::File),
+        }
+    }
+
+    /// From a save file name such as `file1.rpgsave` or `global.rmmzsave`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Format::from_path(path).ok()?;
+        match path.file_stem()?.to_str()? {
+            "config" => Some(Self::Config),
+            "global" => Some(Self::Global),
+            stem => stem.strip_prefix("file")?.parse().ok().map(Self::File),
+        }
+    }
+
+    /// Matching `.rpgsave` file in `save_dir`
+    pub fn file_path(self, save_dir: &Path) -> PathBuf {
+        match self {
+            Self::Config => Format::Mv.config_path(save_dir),
+            Self::Global => Format::Mv.global_path(save_dir),
+            Self::File(id) => Format::Mv.file_path(save_dir, id),
+        }
+    }
+}
+impl Display for SaveKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.storage_key())
+    }
+}
+
+/// Chromium strings start with a byte saying how the rest is encoded
+fn decode_string(data: &[u8]) -> Option<String> {
+    match data.split_first()? {
+        // Latin-1
+        (1, rest) => Some(rest.iter().map(|&b| char::from(b)).collect()),
+        // UTF-16LE
+        (0, rest) => {
+            let units = rest
+                .chunks_exact(2)
+                .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16(&units).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Latin-1 when every character fits, UTF-16LE otherwise, like Chromium does
+fn encode_string(s: &str) -> Vec<u8> {
+    match s.chars().map(u8::try_from).collect::<Result<Vec<_>, _>>() {
+        Ok(latin1) => [&[1], latin1.as_slice()].concat(),
+        Err(_) => std::iter::once(0)
+            .chain(s.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect(),
+    }
+}
+
+fn key_prefix(origin: &str) -> Vec<u8> {
+    [&b"_"[..], origin.as_bytes(), b"\0"].concat()
+}
+
+/// `META:<origin>`, a protobuf holding the last modification time and the size of the origin's data
+fn encode_meta(storage: &LocalStorage, origin: &str, puts: &[(String, String)]) -> Vec<u8> {
+    let existing = storage
+        .entries
+        .iter()
+        .filter(|x| x.origin == origin && !puts.iter().any(|(key, _)| *key == x.key))
+        .map(|x| (x.key.as_str(), x.value.as_str()));
+    let new = puts
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()));
+
+    let size = existing
+        .chain(new)
+        .map(|(key, value)| encode_string(key).len() + encode_string(value).len())
+        .sum::<usize>();
+
+    // Microseconds since 1601, as stored by `base::Time`
+    const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600_000_000;
+    let last_modified = chrono::Utc::now().timestamp_micros() + WINDOWS_EPOCH_OFFSET;
+
+    let mut meta = vec![0x08];
+    leveldb::put_varint(&mut meta, last_modified as u64);
+    meta.push(0x10);
+    leveldb::put_varint(&mut meta, size as u64);
+    meta
+}
+
+/// `_<origin>\0<key>`
+fn decode_key(key: &[u8]) -> Option<(String, String)> {
+    let key = key.strip_prefix(b"_")?;
+    let separator = key.iter().position(|&b| b == 0)?;
+    let origin = std::str::from_utf8(&key[..separator]).ok()?;
+    let key = decode_string(&key[separator + 1..])?;
+    Some((origin.to_string(), key))
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Entry {
+    /// Page origin, e.g. `file://` or `chrome-extension://<id>` for NW.js
+    pub origin: String,
+    pub key: String,
+    pub value: String,
+}
+
+/// Save file found in local storage
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StoredSave<'a> {
+    pub origin: &'a str,
+    pub key: SaveKey,
+    /// lzstring base64, the contents of the matching `.rpgsave`
+    pub data: &'a str,
+}
+impl StoredSave<'_> {
+    pub fn json(&self) -> Result<Json, Error> {
+        Json::decompress(self.data)
+    }
+}
+
+/// Every local storage entry of a browser profile
+#[derive(Debug, Default, Clone)]
+pub struct LocalStorage {
+    entries: Vec<Entry>,
+}
+impl LocalStorage {
+    /// Accepts either the `Local Storage` directory or the `leveldb` one inside it
+    pub fn read_dir(path: &Path) -> Result<Self, Error> {
+        let db = leveldb::read_database(&leveldb_dir(path))?;
+
+        let entries = db
+            .entries()
+            .filter_map(|(key, value)| {
+                let (origin, key) = decode_key(key)?;
+                let value = decode_string(value)?;
+                Some(Entry { origin, key, value })
+            })
+            .collect();
+
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get(&self, origin: &str, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|x| x.origin == origin && x.key == key)
+            .map(|x| x.value.as_str())
+    }
+
+    /// Origins that have at least one save
+    pub fn save_origins(&self) -> Vec<&str> {
+        let mut origins = self
+            .saves(None)
+            .into_iter()
+            .map(|x| x.origin)
+            .collect::<Vec<_>>();
+        origins.dedup();
+        origins
+    }
+
+    /// Saves of `origin`, or of every origin. Sorted by origin, then key.
+    pub fn saves(&self, origin: Option<&str>) -> Vec<StoredSave<'_>> {
+        let mut saves = self
+            .entries
+            .iter()
+            .filter(|x| origin.is_none_or(|origin| x.origin == origin))
+            .filter_map(|x| {
+                Some(StoredSave {
+                    origin: &x.origin,
+                    key: SaveKey::from_storage_key(&x.key)?,
+                    data: &x.value,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        saves.sort_by_key(|x| (x.origin, x.key));
+        saves
+    }
+
+    /// The only origin with saves, or an error listing them if there's more than one
+    pub fn single_save_origin(&self) -> Result<&str, Error> {
+        match self.save_origins().as_slice() {
+            [] => Err(Error::Storage("No saves in local storage".into())),
+            [origin] => Ok(origin),
+            origins => Err(Error::Storage(format!(
+                "Saves found for several origins, pick one of: {}",
+                origins.join(", ")
+            ))),
+        }
+    }
+}
+
+fn leveldb_dir(path: &Path) -> PathBuf {
+    let nested = path.join("leveldb");
+    if nested.is_dir() {
+        nested
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Writes the saves of `origin` into `save_dir` as `.rpgsave` files,
+/// snapshotting any existing files first. Returns the written paths.
+pub fn export(
+    storage: &LocalStorage,
+    origin: &str,
+    save_dir: &Path,
+) -> Result<Vec<PathBuf>, Error> {
+    let saves = storage.saves(Some(origin));
+    if saves.is_empty() {
+        return Err(Error::Storage(format!("No saves for origin {origin}")));
+    }
+
+    std::fs::create_dir_all(save_dir)?;
+
+    let paths = saves
+        .iter()
+        .map(|x| x.key.file_path(save_dir))
+        .collect::<Vec<_>>();
+    backup::snapshot(save_dir, &paths)?;
+
+    for (save, path) in saves.iter().zip(&paths) {
+        write_atomic(path, save.data.as_bytes())?;
+    }
+
+    Ok(paths)
+}
+
+/// Writes `saves` into the local storage of `origin` under their `RPG ...` keys,
+/// replacing what's there
+pub fn import(storage_dir: &Path, origin: &str, saves: &[(SaveKey, &Json)]) -> Result<(), Error> {
+    let dir = leveldb_dir(storage_dir);
+    let storage = LocalStorage::read_dir(&dir)?;
+
+    let puts = saves
+        .iter()
+        .map(|(key, json)| Ok((key.storage_key(), compress(&json.to_string()?))))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let meta = encode_meta(&storage, origin, &puts);
+    let records = puts
+        .iter()
+        .map(|(key, value)| {
+            let key = [key_prefix(origin), encode_string(key)].concat();
+            (key, encode_string(value))
+        })
+        .chain(std::iter::once((
+            format!("META:{origin}").into_bytes(),
+            meta,
+        )))
+        .collect::<Vec<_>>();
+
+    leveldb::write_batch(&dir, &records)
+}
+
+/// Reads the `.rpgsave` files at `paths` and imports them, see [`import`]
+pub fn import_files(storage_dir: &Path, origin: &str, paths: &[PathBuf]) -> Result<(), Error> {
+    let saves = paths
+        .iter()
+        .map(|path| {
+            let key = SaveKey::from_path(path).ok_or_else(|| {
+                Error::Storage(format!("Not a save file name: {}", path.display()))
+            })?;
+            // Only MV web builds keep their saves in local storage
+            if Format::from_path(path)? != Format::Mv {
+                return Err(Error::Storage(format!(
+                    "{} is an MZ save, convert it to MV first",
+                    path.display()
+                )));
+            }
+            Ok((key, Json::read_file(path)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let saves = saves
+        .iter()
+        .map(|(key, json)| (*key, json))
+        .collect::<Vec<_>>();
+    import(storage_dir, origin, &saves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use leveldb::tests::fixture;
+
+    #[test]
+    fn strings_are_latin1_when_they_fit() {
+        assert_eq!(encode_string("RPG File1"), b"\x01RPG File1");
+        assert_eq!(encode_string("café"), b"\x01caf\xe9");
+        assert_eq!(decode_string(b"\x01caf\xe9").unwrap(), "café");
+    }
+
+    #[test]
+    fn other_strings_are_utf16() {
+        // Outside the BMP, so a surrogate pair
+        let text = "セーブ 𝄞";
+        let encoded = encode_string(text);
+        assert_eq!(encoded[0], 0);
+        assert_eq!(&encoded[1..5], [0xbb, 0x30, 0xfc, 0x30]);
+        assert_eq!(encoded.len(), 1 + 2 * text.encode_utf16().count());
+        assert_eq!(decode_string(&encoded).unwrap(), text);
+
+        // Unpaired surrogate, and an unknown encoding byte
+        assert_eq!(decode_string(b"\x00\x00\xd8"), None);
+        assert_eq!(decode_string(b"\x02abc"), None);
+    }
+
+    #[test]
+    fn fixture_saves_are_listed() {
+        let dir = fixture("list");
+        let storage = LocalStorage::read_dir(&dir).unwrap();
+        assert_eq!(
+            storage.save_origins(),
+            ["chrome-extension://abcdef", "file://"]
+        );
+
+        let keys = storage
+            .saves(Some("chrome-extension://abcdef"))
+            .iter()
+            .map(|x| x.key)
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [SaveKey::Global, SaveKey::File(2), SaveKey::File(3)]);
+        for save in storage.saves(None) {
+            save.json().unwrap();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imported_saves_are_read_back() {
+        let dir = fixture("import");
+        let before = LocalStorage::read_dir(&dir).unwrap();
+        let json = Json::from_string(r#"{"party":{"_gold":123},"é":"セーブ"}"#).unwrap();
+
+        import(
+            &dir,
+            "file://",
+            &[(SaveKey::File(1), &json), (SaveKey::File(4), &json)],
+        )
+        .unwrap();
+
+        let after = LocalStorage::read_dir(&dir).unwrap();
+        for save in after.saves(Some("file://")) {
+            assert_eq!(
+                save.json().unwrap().to_string().unwrap(),
+                json.to_string().unwrap()
+            );
+        }
+        assert_eq!(after.saves(Some("file://")).len(), 2);
+        // Other origins are left alone
+        assert_eq!(
+            after.saves(Some("chrome-extension://abcdef")),
+            before.saves(Some("chrome-extension://abcdef"))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mz_saves_are_not_imported() {
+        let dir = fixture("import-mz");
+        let paths = [PathBuf::from("file1.rmmzsave")];
+        let result = import_files(&dir, "file://", &paths);
+        assert!(matches!(result, Err(Error::Storage(msg)) if msg.contains("MZ save")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+//! Enigma Virtual Box's file system, embedded in the packed executable.
+//!
+//! The format isn't documented. This follows the layout the open source
+//! unpackers use: an `EVB\0` header, then a tree of nodes, each starting
+//! with its size, child count, UTF-16 name and type, and after the whole
+//! tree the contents of every file in the same order. Since the header
+//! length has changed between versions, the tree is looked for at each
+//! offset after the signature and only accepted if all of it is valid.
+//!
+//! Files that Enigma Virtual Box compressed are listed but can't be read.
+
+use super::{Compression, Entry, Reader, corrupt};
+use crate::error::Error;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+
+const SIGNATURE: &[u8] = b"EVB\0";
+/// Furthest the tree is looked for after the signature
+const MAX_HEADER_SIZE: usize = 0x100;
+/// Most of the file read for the tree, which is small next to the contents
+const MAX_TREE_SIZE: u64 = 64 * 1024 * 1024;
+
+const NODE_FILE: u8 = 2;
+const NODE_FOLDER: u8 = 3;
+
+/// The folder the executable is in
+const DEFAULT_FOLDER: &str = "%DEFAULT FOLDER%";
+
+/// Reads a file front to back once, keeping what's been read from the
+/// earliest offset still needed
+struct Scanner<'a> {
+    file: &'a mut File,
+    buf: Vec<u8>,
+    /// Offset in the file of `buf[0]`
+    start: u64,
+}
+impl Scanner<'_> {
+    /// Reads until `buf` holds `len` bytes or the file ends. Returns whether
+    /// anything was read.
+    fn fill(&mut self, len: usize) -> Result<bool, Error> {
+        let missing = len.saturating_sub(self.buf.len()) as u64;
+        let read = self
+            .file
+            .by_ref()
+            .take(missing)
+            .read_to_end(&mut self.buf)?;
+        Ok(read > 0)
+    }
+
+    /// Drops the bytes before `buf[at]`
+    fn discard(&mut self, at: usize) {
+        self.buf.drain(..at);
+        self.start += at as u64;
+    }
+}
+
+struct PackedFile {
+    path: String,
+    stored_size: u64,
+    size: u64,
+}
+
+struct Tree {
+    files: Vec<PackedFile>,
+    /// Bytes the tree takes up
+    size: usize,
+}
+
+fn read_name(reader: &mut Reader) -> Result<String, Error> {
+    let mut units = vec![];
+    loop {
+        match reader.u16()? {
+            0 => break,
+            x => units.push(x),
+        }
+    }
+    let name = String::from_utf16(&units).map_err(|_| corrupt("bad file name"))?;
+    if name
+        .chars()
+        .any(|x| x.is_control() || x == '/' || x == '\\')
+    {
+        return Err(corrupt("bad file name"));
+    }
+    Ok(name)
+}
+
+/// Reads a node and its children, adding the files under `path`
+fn read_node(reader: &mut Reader, path: &str, files: &mut Vec<PackedFile>) -> Result<(), Error> {
+    let size = reader.u32()? as usize;
+    let mut node = Reader::new(reader.bytes(size)?);
+
+    node.skip(8)?;
+    let count = node.u32()?;
+    let name = read_name(&mut node)?;
+    let path = match (path, name.as_str()) {
+        (_, DEFAULT_FOLDER) | (_, "") => path.to_string(),
+        ("", name) => name.to_string(),
+        (path, name) => format!("{path}/{name}"),
+    };
+
+    match node.u8()? {
+        NODE_FILE => {
+            node.skip(2)?;
+            let size = node.u32()? as u64;
+            node.skip(4)?;
+            // Creation, access and write times, then attributes
+            node.skip(24 + 15)?;
+            let stored_size = node.u32()? as u64;
+            if count != 0 || path.is_empty() {
+                return Err(corrupt("bad file node"));
+            }
+            files.push(PackedFile {
+                path,
+                stored_size,
+                size,
+            });
+        }
+        NODE_FOLDER => {
+            for _ in 0..count {
+                read_node(reader, &path, files)?;
+            }
+        }
+        _ => return Err(corrupt("unknown node type")),
+    }
+    Ok(())
+}
+
+fn read_tree(data: &[u8]) -> Result<Tree, Error> {
+    let mut reader = Reader::new(data);
+    let mut files = vec![];
+    read_node(&mut reader, "", &mut files)?;
+    Ok(Tree {
+        files,
+        size: data.len() - reader.data.len(),
+    })
+}
+
+/// The tree in `data`, which starts with the signature found at `signature`,
+/// and the offset of the file contents after it
+fn find_tree(data: &[u8], signature: u64, len: u64) -> Option<(Tree, u64)> {
+    (SIGNATURE.len()..MAX_HEADER_SIZE.min(data.len())).find_map(|start| {
+        let tree = read_tree(&data[start..]).ok()?;
+        let contents = tree.files.iter().map(|x| x.stored_size).sum::<u64>();
+        let offset = signature + (start + tree.size) as u64;
+        (offset + contents <= len && !tree.files.is_empty()).then_some((tree, offset))
+    })
+}
+
+/// Files in the Enigma Virtual Box file system in `file`, or `None` if it
+/// doesn't have one
+pub(super) fn read_index(file: &mut File) -> Result<Option<BTreeMap<String, Entry>>, Error> {
+    const CHUNK: usize = 4 * 1024 * 1024;
+
+    let len = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut scanner = Scanner {
+        file,
+        buf: vec![],
+        start: 0,
+    };
+
+    // The signature can turn up by chance in the executable's own code, so
+    // the search carries on after each one without a tree
+    let mut from = 0;
+    let mut seen_signature = false;
+    let (tree, mut offset) = loop {
+        let Some(i) = scanner.buf[from..]
+            .windows(SIGNATURE.len())
+            .position(|x| x == SIGNATURE)
+        else {
+            // Keep the end in case the signature is split between reads
+            let kept = scanner.buf.len().saturating_sub(SIGNATURE.len() - 1);
+            scanner.discard(kept.max(from));
+            from = 0;
+            if !scanner.fill(scanner.buf.len() + CHUNK)? {
+                if seen_signature {
+                    let msg = "Found an Enigma Virtual Box signature, but not a file system this can read";
+                    return Err(Error::Package(msg.into()));
+                }
+                return Ok(None);
+            }
+            continue;
+        };
+        seen_signature = true;
+
+        let mut at = from + i;
+        // Dropping what's before moves everything after, so only once a
+        // tree's worth has built up rather than at every signature
+        if at >= MAX_TREE_SIZE as usize {
+            scanner.discard(at);
+            at = 0;
+        }
+        from = at + 1;
+
+        let signature = scanner.start + at as u64;
+        scanner.fill(at + (len - signature).min(MAX_TREE_SIZE) as usize)?;
+        if let Some(found) = find_tree(&scanner.buf[at..], signature, len) {
+            break found;
+        }
+    };
+
+    let mut entries = BTreeMap::new();
+    for file in tree.files {
+        let compression = if file.stored_size == file.size {
+            Compression::Stored
+        } else {
+            Compression::Unsupported("Enigma Virtual Box compression".into())
+        };
+        entries.insert(
+            file.path,
+            Entry {
+                offset,
+                stored_size: file.stored_size,
+                size: file.size,
+                compression,
+            },
+        );
+        offset += file.stored_size;
+    }
+
+    Ok(Some(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{Kind, Package};
+    use std::path::{Path, PathBuf};
+
+    fn node(count: u32, name: &str, kind: u8, sizes: Option<(u32, u32)>) -> Vec<u8> {
+        let mut node = vec![0; 8];
+        node.extend(count.to_le_bytes());
+        node.extend(name.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        node.push(kind);
+        if let Some((size, stored_size)) = sizes {
+            node.extend([0; 2]);
+            node.extend(size.to_le_bytes());
+            node.extend([0; 4 + 24 + 15]);
+            node.extend(stored_size.to_le_bytes());
+        }
+        [(node.len() as u32).to_le_bytes().to_vec(), node].concat()
+    }
+
+    fn file(name: &str, contents: &[u8]) -> Vec<u8> {
+        let size = contents.len() as u32;
+        node(0, name, NODE_FILE, Some((size, size)))
+    }
+
+    /// An executable of `prefix` followed by a file system holding
+    /// `package.json`, `www/index.html` and a compressed `www/big.bin`
+    fn executable(dir: &Path, prefix: &[u8]) -> PathBuf {
+        let tree = [
+            node(2, DEFAULT_FOLDER, NODE_FOLDER, None),
+            file("package.json", b"{}"),
+            node(2, "www", NODE_FOLDER, None),
+            file("index.html", b"<html>"),
+            node(0, "big.bin", NODE_FILE, Some((100, 3))),
+        ]
+        .concat();
+
+        let mut data = prefix.to_vec();
+        data.extend(SIGNATURE);
+        data.extend([0; 0x44]);
+        data.extend(tree);
+        data.extend(b"{}<html>xyz");
+
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("Game.exe");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lib-enigma-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn files_are_read_from_the_tree() {
+        let dir = temp_dir("read");
+        let package = Package::open(&executable(&dir, b"MZ EVB\0 not a tree")).unwrap();
+        assert_eq!(package.kind, Kind::EnigmaVirtualBox);
+        assert_eq!(
+            package.files().collect::<Vec<_>>(),
+            ["package.json", "www/big.bin", "www/index.html"]
+        );
+        assert_eq!(package.read("package.json").unwrap(), b"{}");
+        assert_eq!(package.read("www/index.html").unwrap(), b"<html>");
+        assert!(package.read("www/big.bin").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn signatures_split_between_reads_are_found() {
+        let dir = temp_dir("stray");
+        // One split between two reads, and one right before the real one
+        let mut prefix = b"MZ".to_vec();
+        prefix.resize(4 * 1024 * 1024 - 2, 0x90);
+        prefix.extend(b"EVB\0");
+        prefix.resize(5 * 1024 * 1024, 0x90);
+        prefix.extend(b"EVB\0\x01");
+
+        let package = Package::open(&executable(&dir, &prefix)).unwrap();
+        assert_eq!(package.read("www/index.html").unwrap(), b"<html>");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn signatures_without_a_tree_are_errors() {
+        let dir = temp_dir("broken");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Game.exe");
+
+        std::fs::write(&path, b"MZ EVB\0 not a tree").unwrap();
+        let e = Package::open(&path).unwrap_err();
+        assert!(
+            matches!(&e, Error::Package(msg) if msg.contains("Enigma")),
+            "{e:?}"
+        );
+
+        std::fs::write(&path, b"MZ plain executable").unwrap();
+        let e = Package::open(&path).unwrap_err();
+        assert!(
+            matches!(&e, Error::Package(msg) if msg.contains("no bundled files")),
+            "{e:?}"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+//! Games shipped with their files bundled into one file instead of a loose
+//! `www` directory: NW.js's `package.nw` zip (which can also be appended to
+//! the executable) and Enigma Virtual Box executables.
+//!
+//! Only the index is read when opening, file contents are read on demand.
+//! Paths use `/` and are relative to the game's root directory, like
+//! `www/data/System.json`.
+
+mod enigma;
+mod zip;
+
+use crate::error::Error;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Kind {
+    /// `package.nw`, or a zip appended to `nw.exe`
+    Zip,
+    EnigmaVirtualBox,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Compression {
+    Stored,
+    Deflate,
+    Encrypted,
+    /// A method this doesn't read
+    Unsupported(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Entry {
+    offset: u64,
+    stored_size: u64,
+    size: u64,
+    compression: Compression,
+}
+
+fn corrupt(msg: impl std::fmt::Display) -> Error {
+    Error::Package(format!("Corrupt package: {msg}"))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < len {
+            return Err(corrupt("unexpected end of data"));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+fn read_at(file: &mut File, offset: u64, len: u64) -> Result<Vec<u8>, Error> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = vec![];
+    file.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(corrupt("file data past the end of the package"));
+    }
+    Ok(bytes)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Package {
+    pub path: PathBuf,
+    pub kind: Kind,
+    entries: BTreeMap<String, Entry>,
+}
+impl Package {
+    /// Opens a `package.nw` or an executable with either kind of package
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let mut file = File::open(path)?;
+
+        // A broken zip is most likely a stray signature in an executable,
+        // which may still have an Enigma Virtual Box file system
+        let (kind, entries) = if let Ok(Some(entries)) = zip::read_index(&mut file) {
+            (Kind::Zip, entries)
+        } else if let Some(entries) = enigma::read_index(&mut file)? {
+            (Kind::EnigmaVirtualBox, entries)
+        } else {
+            let msg = format!("{} has no bundled files", path.display());
+            return Err(Error::Package(msg));
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind,
+            entries,
+        })
+    }
+
+    /// Paths of the bundled files, sorted
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Contents of the bundled file at `path`
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let entry = self
+            .entries
+            .get(path)
+            .ok_or_else(|| Error::Package(format!("{path} isn't in {}", self.path.display())))?;
+
+        let mut file = File::open(&self.path)?;
+        let stored = read_at(&mut file, entry.offset, entry.stored_size)?;
+        let bytes = match &entry.compression {
+            Compression::Stored => stored,
+            Compression::Deflate => {
+                let mut bytes = Vec::with_capacity(entry.size as usize);
+                flate2::read::DeflateDecoder::new(stored.as_slice()).read_to_end(&mut bytes)?;
+                bytes
+            }
+            Compression::Encrypted => {
+                return Err(Error::Package(format!("{path} is encrypted")));
+            }
+            Compression::Unsupported(method) => {
+                let msg = format!("{path} is compressed with {method}, which isn't supported");
+                return Err(Error::Package(msg));
+            }
+        };
+
+        if bytes.len() as u64 != entry.size {
+            return Err(corrupt(format!("{path} has the wrong size")));
+        }
+        Ok(bytes)
+    }
+
+    /// Writes every bundled file under `out_dir`. Files that can't be read
+    /// are returned with the reason instead of stopping the rest.
+    pub fn extract(&self, out_dir: &Path) -> Result<ExtractReport, Error> {
+        let mut report = ExtractReport::default();
+
+        for path in self.files() {
+            let out = path
+                .split('/')
+                .filter(|x| !x.is_empty() && *x != "." && *x != "..")
+                .fold(out_dir.to_path_buf(), |out, x| out.join(x));
+
+            let result = self.read(path).and_then(|bytes| {
+                if let Some(parent) = out.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&out, bytes)?;
+                Ok(())
+            });
+            match result {
+                Ok(()) => report.written.push(out),
+                Err(e) => report.failed.push((path.to_string(), e.to_string())),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Outcome of [`Package::extract`]
+#[derive(Debug, Default)]
+pub struct ExtractReport {
+    pub written: Vec<PathBuf>,
+    pub failed: Vec<(String, String)>,
+}
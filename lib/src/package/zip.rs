@@ -0,0 +1,264 @@
+//! Zip archives, found from the end of the file so zips appended to an
+//! executable work too. Zip64 isn't supported, NW.js packages stay well
+//! under its limits.
+
+use super::{Compression, Entry, Reader, corrupt, read_at};
+use crate::error::Error;
+use std::{collections::BTreeMap, fs::File, io::Seek};
+
+const END_SIGNATURE: u32 = 0x06054b50;
+const CENTRAL_SIGNATURE: u32 = 0x02014b50;
+const LOCAL_SIGNATURE: u32 = 0x04034b50;
+
+const END_SIZE: u64 = 22;
+const LOCAL_HEADER_SIZE: u64 = 30;
+const MAX_COMMENT_SIZE: u64 = 0xffff;
+
+const FLAG_ENCRYPTED: u16 = 1;
+const FLAG_UTF8: u16 = 1 << 11;
+
+/// Files in the zip at the end of `file`, or `None` if there isn't one
+pub(super) fn read_index(file: &mut File) -> Result<Option<BTreeMap<String, Entry>>, Error> {
+    let len = file.seek(std::io::SeekFrom::End(0))?;
+    if len < END_SIZE {
+        return Ok(None);
+    }
+
+    let tail_start = len.saturating_sub(END_SIZE + MAX_COMMENT_SIZE);
+    let tail = read_at(file, tail_start, len - tail_start)?;
+    let Some(end) = (0..=tail.len() - END_SIZE as usize)
+        .rev()
+        .find(|&i| tail[i..i + 4] == END_SIGNATURE.to_le_bytes())
+    else {
+        return Ok(None);
+    };
+
+    let mut reader = Reader::new(&tail[end + 4..]);
+    reader.skip(6)?;
+    let count = reader.u16()?;
+    let directory_size = reader.u32()? as u64;
+    let directory_offset = reader.u32()? as u64;
+    if count == 0xffff || directory_offset == 0xffff_ffff {
+        return Err(Error::Package("Zip64 packages aren't supported".into()));
+    }
+
+    // Offsets are from the start of the zip, which isn't the start of the
+    // file when it's appended to an executable
+    let end_offset = tail_start + end as u64;
+    let Some(shift) = end_offset.checked_sub(directory_offset + directory_size) else {
+        return Ok(None);
+    };
+    let directory = read_at(file, directory_offset + shift, directory_size)?;
+    if !directory.starts_with(&CENTRAL_SIGNATURE.to_le_bytes()) {
+        return Ok(None);
+    }
+
+    let mut entries = BTreeMap::new();
+    let mut reader = Reader::new(&directory);
+    for _ in 0..count {
+        if reader.u32()? != CENTRAL_SIGNATURE {
+            return Err(corrupt("bad zip central directory"));
+        }
+        reader.skip(4)?;
+        let flags = reader.u16()?;
+        let method = reader.u16()?;
+        reader.skip(8)?;
+        let stored_size = reader.u32()? as u64;
+        let size = reader.u32()? as u64;
+        let name_len = reader.u16()? as usize;
+        let extra_len = reader.u16()? as usize;
+        let comment_len = reader.u16()? as usize;
+        reader.skip(8)?;
+        let local_offset = reader.u32()? as u64 + shift;
+        let name = reader.bytes(name_len)?;
+        reader.skip(extra_len + comment_len)?;
+
+        // Names are CP437 without the UTF-8 flag, which is the same for ASCII
+        let name = if flags & FLAG_UTF8 != 0 {
+            String::from_utf8(name.to_vec()).map_err(|_| corrupt("bad zip file name"))?
+        } else {
+            String::from_utf8_lossy(name).into_owned()
+        };
+        let name = name.replace('\\', "/");
+        if name.ends_with('/') {
+            continue;
+        }
+
+        let local = read_at(file, local_offset, LOCAL_HEADER_SIZE)?;
+        let mut local = Reader::new(&local);
+        if local.u32()? != LOCAL_SIGNATURE {
+            return Err(corrupt(format!("bad zip header for {name}")));
+        }
+        local.skip(22)?;
+        let local_name_len = local.u16()? as u64;
+        let local_extra_len = local.u16()? as u64;
+
+        let compression = match method {
+            _ if flags & FLAG_ENCRYPTED != 0 => Compression::Encrypted,
+            0 => Compression::Stored,
+            8 => Compression::Deflate,
+            method => Compression::Unsupported(format!("zip method {method}")),
+        };
+        entries.insert(
+            name,
+            Entry {
+                offset: local_offset + LOCAL_HEADER_SIZE + local_name_len + local_extra_len,
+                stored_size,
+                size,
+                compression,
+            },
+        );
+    }
+
+    Ok(Some(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{Kind, Package};
+    use std::{io::Write, path::PathBuf};
+
+    struct ZipFile<'a> {
+        name: &'a str,
+        flags: u16,
+        contents: &'a [u8],
+        deflate: bool,
+    }
+
+    fn stored<'a>(name: &'a str, contents: &'a [u8]) -> ZipFile<'a> {
+        ZipFile {
+            name,
+            flags: 0,
+            contents,
+            deflate: false,
+        }
+    }
+
+    /// A zip of `files` after `prefix`, with offsets from the start of the zip
+    fn zip(prefix: &[u8], files: &[ZipFile], comment: &[u8]) -> Vec<u8> {
+        let mut local = vec![];
+        let mut central = vec![];
+
+        for file in files {
+            let data = if file.deflate {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(file.contents).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                file.contents.to_vec()
+            };
+            let method: u16 = if file.deflate { 8 } else { 0 };
+            let offset = local.len() as u32;
+
+            // Version, flags, method, time, date and CRC-32, which isn't checked
+            let common = [
+                &20u16.to_le_bytes()[..],
+                &file.flags.to_le_bytes(),
+                &method.to_le_bytes(),
+                &[0; 8],
+                &(data.len() as u32).to_le_bytes(),
+                &(file.contents.len() as u32).to_le_bytes(),
+                &(file.name.len() as u16).to_le_bytes(),
+            ]
+            .concat();
+
+            local.extend(LOCAL_SIGNATURE.to_le_bytes());
+            local.extend(&common);
+            local.extend(3u16.to_le_bytes());
+            local.extend(file.name.as_bytes());
+            local.extend(b"xyz");
+            local.extend(&data);
+
+            central.extend(CENTRAL_SIGNATURE.to_le_bytes());
+            central.extend(20u16.to_le_bytes());
+            central.extend(&common);
+            // Extra, comment, disk, internal and external attributes
+            central.extend([0; 12]);
+            central.extend(offset.to_le_bytes());
+            central.extend(file.name.as_bytes());
+        }
+
+        let mut end = END_SIGNATURE.to_le_bytes().to_vec();
+        end.extend([0; 4]);
+        end.extend((files.len() as u16).to_le_bytes());
+        end.extend((files.len() as u16).to_le_bytes());
+        end.extend((central.len() as u32).to_le_bytes());
+        end.extend((local.len() as u32).to_le_bytes());
+        end.extend((comment.len() as u16).to_le_bytes());
+        end.extend(comment);
+
+        [prefix, &local, &central, &end].concat()
+    }
+
+    fn write(name: &str, data: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lib-zip-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("package.nw");
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn appended_zips_are_read() {
+        let system = br#"{"gameTitle":"Demo","gameTitle2":"Demo","gameTitle3":"Demo"}"#;
+        let files = [
+            stored("package.json", b"{}"),
+            stored("www/", b""),
+            ZipFile {
+                name: "www/data/System.json",
+                flags: FLAG_UTF8,
+                contents: system,
+                deflate: true,
+            },
+            stored("www\\img\\a.png", b"png"),
+            ZipFile {
+                name: "www/secret.js",
+                flags: FLAG_ENCRYPTED,
+                contents: b"js",
+                deflate: false,
+            },
+        ];
+
+        for (name, prefix) in [("plain", &b""[..]), ("appended", b"MZ nw.exe PK\x05\x06")] {
+            let path = write(name, &zip(prefix, &files, b"Packed for NW.js"));
+            let package = Package::open(&path).unwrap();
+            assert_eq!(package.kind, Kind::Zip);
+            assert_eq!(
+                package.files().collect::<Vec<_>>(),
+                [
+                    "package.json",
+                    "www/data/System.json",
+                    "www/img/a.png",
+                    "www/secret.js"
+                ]
+            );
+            assert_eq!(package.read("package.json").unwrap(), b"{}");
+            assert_eq!(package.read("www/data/System.json").unwrap(), system);
+            assert_eq!(package.read("www/img/a.png").unwrap(), b"png");
+            assert!(package.read("www/secret.js").is_err());
+            std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn broken_directories_are_errors() {
+        let data = zip(b"", &[stored("package.json", b"{}")], b"");
+
+        let path = write("truncated", &data[..data.len() - 10]);
+        assert!(
+            read_index(&mut File::open(&path).unwrap())
+                .unwrap()
+                .is_none()
+        );
+
+        // Local header of the only file overwritten
+        let mut bad = data.clone();
+        bad[..4].copy_from_slice(b"MZ\0\0");
+        std::fs::write(&path, &bad).unwrap();
+        assert!(read_index(&mut File::open(&path).unwrap()).is_err());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
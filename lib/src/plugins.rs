@@ -0,0 +1,122 @@
+//! `js/plugins.js`, the plugin list the editor writes as a JS assignment:
+//!
+//! ```js
+//! // Generated by RPG Maker.
+//! // Do not edit this file directly.
+//! var $plugins =
+//! [
+//! {"name":"Community_Basic","status":true,"description":"...","parameters":{...}},
+//! ...
+//! ];
+//! ```
+//!
+//! The text around the array is kept as it was, and the array is written
+//! back one plugin per line like the editor does.
+
+use crate::{error::Error, util::write_atomic};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Plugin {
+    pub name: String,
+    /// Whether the plugin is on
+    pub status: bool,
+    pub description: String,
+    /// Values as the editor saved them. Structs and arrays are JSON inside
+    /// the string.
+    pub parameters: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl Plugin {
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.get(name)?.as_str()
+    }
+
+    /// Sets a parameter, adding it if the plugin doesn't have it yet
+    pub fn set_parameter(&mut self, name: &str, value: impl Into<String>) {
+        self.parameters
+            .insert(name.to_string(), Value::String(value.into()));
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plugins {
+    /// Everything before the array
+    prefix: String,
+    pub plugins: Vec<Plugin>,
+    /// Everything after the array
+    suffix: String,
+}
+impl Plugins {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let error = |msg: &str| Error::Plugins(msg.into());
+
+        let name = text
+            .find("$plugins")
+            .ok_or_else(|| error("No $plugins variable"))?;
+        let start = text[name..]
+            .find('=')
+            .and_then(|x| text[name + x..].find('[').map(|y| name + x + y))
+            .ok_or_else(|| error("$plugins isn't assigned an array"))?;
+
+        let mut stream =
+            serde_json::Deserializer::from_str(&text[start..]).into_iter::<Vec<Plugin>>();
+        let plugins = stream
+            .next()
+            .ok_or_else(|| error("$plugins isn't assigned an array"))??;
+        let end = start + stream.byte_offset();
+
+        Ok(Self {
+            prefix: text[..start].to_string(),
+            plugins,
+            suffix: text[end..].to_string(),
+        })
+    }
+
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    pub fn to_js(&self) -> Result<String, Error> {
+        let mut js = self.prefix.clone();
+        js.push_str("[\n");
+        for (i, plugin) in self.plugins.iter().enumerate() {
+            if i > 0 {
+                js.push_str(",\n");
+            }
+            js.push_str(&serde_json::to_string(plugin)?);
+        }
+        if !self.plugins.is_empty() {
+            js.push('\n');
+        }
+        js.push(']');
+        js.push_str(&self.suffix);
+        Ok(js)
+    }
+
+    pub fn write_file(&self, path: &Path) -> Result<(), Error> {
+        write_atomic(path, self.to_js()?.as_bytes())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Plugin> {
+        self.plugins.iter().find(|x| x.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Result<&mut Plugin, Error> {
+        self.plugins
+            .iter_mut()
+            .find(|x| x.name == name)
+            .ok_or_else(|| Error::Plugins(format!("No plugin named {name}")))
+    }
+
+    /// Turns a plugin on or off
+    pub fn set_status(&mut self, name: &str, status: bool) -> Result<(), Error> {
+        self.get_mut(name)?.status = status;
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+//! Snapshots of save files taken before anything in [`crate::save`] writes
+//! over them.
+//!
+//! Each snapshot is a directory under `<save dir>/backups` named after the
+//! time it was taken, holding copies of the files plus a `manifest.json`
+//! listing which files were copied and which didn't exist yet.
+
+use super::Format;
+use crate::{error::Error, util::write_atomic};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+pub const BACKUP_DIR_NAME: &str = "backups";
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Retention {
+    /// Number of most recent snapshots to keep
+    pub keep_last: usize,
+    /// Snapshots older than this are removed, even if within `keep_last`
+    pub max_age: Option<Duration>,
+}
+
+/// Applied after every automatic snapshot
+pub const DEFAULT_RETENTION: Retention = Retention {
+    keep_last: 50,
+    max_age: None,
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+    created: super::global::DateTime,
+    files: Vec<String>,
+    missing: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    /// Directory name, sortable by creation time
+    pub id: String,
+    pub path: PathBuf,
+    pub created: chrono::DateTime<chrono::Utc>,
+    /// Save files stored in the snapshot
+    pub files: Vec<String>,
+    /// Save files that didn't exist when the snapshot was taken
+    pub missing: Vec<String>,
+}
+
+pub fn backup_dir(save_dir: &Path) -> PathBuf {
+    save_dir.join(BACKUP_DIR_NAME)
+}
+
+/// Whether `dir` is where a game keeps its saves, going by the global index
+/// or backups taken before
+pub fn is_save_dir(dir: &Path) -> bool {
+    [Format::Mv, Format::Mz]
+        .iter()
+        .any(|x| x.global_path(dir).is_file())
+        || backup_dir(dir).is_dir()
+}
+
+fn file_name(path: &Path) -> Result<String, Error> {
+    path.file_name()
+        .and_then(|x| x.to_str())
+        .map(|x| x.to_string())
+        .ok_or_else(|| Error::Save(format!("Invalid save file path: {}", path.display())))
+}
+
+/// Whether a name read from a manifest is a save file directly in the save
+/// directory, so restoring it can't write or delete anything else
+fn is_save_file_name(name: &str) -> bool {
+    let path = Path::new(name);
+    matches!(
+        path.components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(x)] if *x == path.as_os_str()
+    ) && Format::from_path(path).is_ok()
+}
+
+/// Copies `files` (which must be in `save_dir`) into a new snapshot.
+/// Returns `None` if there was nothing to record.
+pub fn snapshot(save_dir: &Path, files: &[PathBuf]) -> Result<Option<Snapshot>, Error> {
+    let mut names = files
+        .iter()
+        .map(|path| file_name(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    names.dedup();
+
+    if names.is_empty() {
+        return Ok(None);
+    }
+
+    let created = chrono::Utc::now();
+    let base_id = created.format("%Y%m%d-%H%M%S-%3f").to_string();
+
+    let backups = backup_dir(save_dir);
+    std::fs::create_dir_all(&backups)?;
+
+    // Several writes can happen within the same millisecond
+    let (id, path) = (0..1000)
+        .map(|i| match i {
+            0 => base_id.clone(),
+            i => format!("{base_id}-{i}"),
+        })
+        .map(|id| {
+            let path = backups.join(&id);
+            (id, path)
+        })
+        .find(|(_, path)| !path.exists())
+        .ok_or_else(|| Error::Save(format!("Too many backups named {base_id}")))?;
+    std::fs::create_dir(&path)?;
+
+    let (files, missing): (Vec<_>, Vec<_>) = names
+        .into_iter()
+        .partition(|name| save_dir.join(name).exists());
+
+    for name in &files {
+        std::fs::copy(save_dir.join(name), path.join(name))?;
+    }
+
+    let manifest = Manifest {
+        created: super::global::DateTime(created),
+        files,
+        missing,
+    };
+    std::fs::write(
+        path.join(MANIFEST_FILE_NAME),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    prune(save_dir, &DEFAULT_RETENTION)?;
+
+    Ok(Some(Snapshot {
+        id,
+        path,
+        created,
+        files: manifest.files,
+        missing: manifest.missing,
+    }))
+}
+
+/// Snapshots in `save_dir`, oldest first. Snapshots whose manifest names
+/// anything but save files are left out.
+pub fn list(save_dir: &Path) -> Result<Vec<Snapshot>, Error> {
+    let backups = backup_dir(save_dir);
+    if !backups.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut snapshots = std::fs::read_dir(&backups)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path.file_name()?.to_str()?.to_string();
+            let manifest = std::fs::read_to_string(path.join(MANIFEST_FILE_NAME)).ok()?;
+            let manifest: Manifest = serde_json::from_str(&manifest).ok()?;
+            let is_valid = manifest
+                .files
+                .iter()
+                .chain(&manifest.missing)
+                .all(|x| is_save_file_name(x));
+            if !is_valid {
+                return None;
+            }
+
+            Some(Snapshot {
+                id,
+                path,
+                created: manifest.created.0,
+                files: manifest.files,
+                missing: manifest.missing,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    snapshots.sort_by(|a, b| (a.created, &a.id).cmp(&(b.created, &b.id)));
+    Ok(snapshots)
+}
+
+/// Removes snapshots that fall outside `retention`
+pub fn prune(save_dir: &Path, retention: &Retention) -> Result<(), Error> {
+    let snapshots = list(save_dir)?;
+    let now = chrono::Utc::now();
+
+    let excess = snapshots.len().saturating_sub(retention.keep_last);
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        let is_expired = retention.max_age.is_some_and(|max_age| {
+            (now - snapshot.created)
+                .to_std()
+                .is_ok_and(|age| age > max_age)
+        });
+
+        if i < excess || is_expired {
+            std::fs::remove_dir_all(&snapshot.path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_files(snapshot: &Snapshot) -> Result<Vec<(&String, Vec<u8>)>, Error> {
+    snapshot
+        .files
+        .iter()
+        .map(|name| Ok((name, std::fs::read(snapshot.path.join(name))?)))
+        .collect()
+}
+
+fn write_back(
+    save_dir: &Path,
+    files: Vec<(&String, Vec<u8>)>,
+    missing: &[String],
+) -> Result<(), Error> {
+    // Carry on past failures so as much as possible is put back
+    let written = files
+        .into_iter()
+        .map(|(name, data)| write_atomic(&save_dir.join(name), &data));
+    let removed = missing.iter().map(|name| {
+        let path = save_dir.join(name);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    });
+
+    written
+        .chain(removed)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Puts the files in snapshot `id` back into `save_dir`, and removes files that
+/// didn't exist at the time. The current files are snapshotted first.
+pub fn restore(save_dir: &Path, id: &str) -> Result<(), Error> {
+    let target = list(save_dir)?
+        .into_iter()
+        .find(|x| x.id == id)
+        .ok_or_else(|| Error::Save(format!("Backup {id} doesn't exist")))?;
+
+    // Read everything up front, taking a new snapshot may prune this one
+    let files = read_files(&target)?;
+
+    let affected = target
+        .files
+        .iter()
+        .chain(&target.missing)
+        .map(|name| save_dir.join(name))
+        .collect::<Vec<_>>();
+    snapshot(save_dir, &affected)?;
+
+    write_back(save_dir, files, &target.missing)
+}
+
+/// Undoes an operation that failed partway, by putting the files of the
+/// `snapshot` it took back without taking another one
+pub(crate) fn roll_back(save_dir: &Path, snapshot: &Snapshot) -> Result<(), Error> {
+    write_back(save_dir, read_files(snapshot)?, &snapshot.missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lib-backup-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("global.rpgsave"), "global").unwrap();
+        dir
+    }
+
+    fn read(dir: &Path, name: &str) -> Option<String> {
+        std::fs::read_to_string(dir.join(name)).ok()
+    }
+
+    #[test]
+    fn restore_and_roll_back_put_files_back() {
+        let dir = save_dir("restore");
+        std::fs::write(dir.join("file1.rpgsave"), "one").unwrap();
+        let names = ["global.rpgsave", "file1.rpgsave", "file2.rpgsave"];
+        let paths = names.map(|x| dir.join(x));
+
+        let taken = snapshot(&dir, &paths).unwrap().unwrap();
+        assert_eq!(taken.files, ["file1.rpgsave", "global.rpgsave"]);
+        assert_eq!(taken.missing, ["file2.rpgsave"]);
+        let listed = list(&dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            (&listed[0].files, &listed[0].missing),
+            (&taken.files, &taken.missing)
+        );
+
+        let change = || {
+            std::fs::write(dir.join("file1.rpgsave"), "changed").unwrap();
+            std::fs::write(dir.join("file2.rpgsave"), "new").unwrap();
+            std::fs::remove_file(dir.join("global.rpgsave")).unwrap();
+        };
+        let is_restored = || {
+            read(&dir, "global.rpgsave").as_deref() == Some("global")
+                && read(&dir, "file1.rpgsave").as_deref() == Some("one")
+                && read(&dir, "file2.rpgsave").is_none()
+        };
+
+        change();
+        roll_back(&dir, &taken).unwrap();
+        assert!(is_restored());
+        // Rolling back doesn't take a snapshot of its own
+        assert_eq!(list(&dir).unwrap().len(), 1);
+
+        change();
+        restore(&dir, &taken.id).unwrap();
+        assert!(is_restored());
+
+        // The changed files were snapshotted before being replaced
+        let snapshots = list(&dir).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        let before_restore = &snapshots[1];
+        assert_eq!(before_restore.files, ["file1.rpgsave", "file2.rpgsave"]);
+        assert_eq!(before_restore.missing, ["global.rpgsave"]);
+        assert_eq!(
+            read(&before_restore.path, "file2.rpgsave").as_deref(),
+            Some("new")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_snapshots() {
+        let dir = save_dir("prune");
+        let paths = [dir.join("global.rpgsave")];
+        let ids = (0..5)
+            .map(|_| snapshot(&dir, &paths).unwrap().unwrap().id)
+            .collect::<Vec<_>>();
+
+        let retention = Retention {
+            keep_last: 3,
+            max_age: None,
+        };
+        prune(&dir, &retention).unwrap();
+        let kept = list(&dir).unwrap();
+        assert_eq!(
+            kept.iter().map(|x| &x.id).collect::<Vec<_>>(),
+            ids[2..].iter().collect::<Vec<_>>()
+        );
+
+        // Backdate the oldest one left
+        let manifest_path = kept[0].path.join(MANIFEST_FILE_NAME);
+        let mut manifest: Manifest =
+            serde_json::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        manifest.created.0 -= chrono::Duration::days(2);
+        std::fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+
+        let retention = Retention {
+            keep_last: 3,
+            max_age: Some(Duration::from_secs(24 * 60 * 60)),
+        };
+        prune(&dir, &retention).unwrap();
+        let kept = list(&dir).unwrap();
+        assert_eq!(
+            kept.iter().map(|x| &x.id).collect::<Vec<_>>(),
+            ids[3..].iter().collect::<Vec<_>>()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn manifests_naming_other_files_are_ignored() {
+        let dir = save_dir("names");
+        for (i, name) in [
+            "../file1.rpgsave",
+            "/tmp/file1.rpgsave",
+            "sub/file1.rpgsave",
+            "plugins.js",
+            "..",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let path = backup_dir(&dir).join(format!("bad-{i}"));
+            std::fs::create_dir_all(&path).unwrap();
+            let manifest = Manifest {
+                created: crate::save::global::DateTime(chrono::Utc::now()),
+                files: vec![],
+                missing: vec![name.to_string()],
+            };
+            std::fs::write(
+                path.join(MANIFEST_FILE_NAME),
+                serde_json::to_string(&manifest).unwrap(),
+            )
+            .unwrap();
+
+            assert!(list(&dir).unwrap().is_empty(), "{name}");
+            assert!(restore(&dir, &format!("bad-{i}")).is_err());
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        assert!(is_save_file_name("file1.rpgsave"));
+        assert!(is_save_file_name("global.rmmzsave"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+use super::Json;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+const fn default_volume() -> u32 {
+    100
+}
+
+/// `config.rpgsave` (MV) / `config.rmmzsave` (MZ), as written by `ConfigManager.makeData`.
+///
+/// Fields are in the order the engine writes them, and keys added by plugins
+/// are kept in `extra` after them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    #[serde(default)]
+    pub always_dash: bool,
+    #[serde(default)]
+    pub command_remember: bool,
+    /// MZ only
+    #[serde(rename = "touchUI", default, skip_serializing_if = "Option::is_none")]
+    pub touch_ui: Option<bool>,
+    #[serde(default = "default_volume")]
+    pub bgm_volume: u32,
+    #[serde(default = "default_volume")]
+    pub bgs_volume: u32,
+    #[serde(default = "default_volume")]
+    pub me_volume: u32,
+    #[serde(default = "default_volume")]
+    pub se_volume: u32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl Config {
+    pub fn from_json(json: &Json) -> Result<Self, Error> {
+        Self::deserialize(json.value()).map_err(|e| Error::Save(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<Json, Error> {
+        serde_json::to_value(self)
+            .map(Json::new)
+            .map_err(|e| e.into())
+    }
+
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        Self::from_json(&Json::read_file(path)?)
+    }
+
+    pub fn write_file(&self, path: &Path) -> Result<(), Error> {
+        self.to_json()?.write_file(path)
+    }
+}
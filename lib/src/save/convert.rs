@@ -0,0 +1,367 @@
+//! Converting saves between MV and MZ, for games ported from one to the other.
+//!
+//! Both engines use the same class names for everything in a save, but MV 1.6+
+//! writes extra JsonEx metadata (`@c` ids, `@a` array wrappers and `@r`
+//! references) that MZ doesn't understand, and a few objects keep different
+//! fields. MZ saves are converted to the plain JsonEx layout MV used before
+//! 1.6, which every MV version can load.
+//!
+//! `_versionId` is reset in converted saves so the engine reloads the current
+//! map from the new game's data on load (`Scene_Load.reloadMapIfUpdated`).
+
+use super::{Format, Json, backup, config::Config, global::Global, global::MV_GLOBAL_ID};
+use crate::error::Error;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Classes written by the engine itself. Anything else was added by a plugin,
+/// and the target game needs the same plugin for the save to load.
+const CORE_CLASSES: &[&str] = &[
+    "Game_Action",
+    "Game_ActionResult",
+    "Game_Actor",
+    "Game_Actors",
+    "Game_CommonEvent",
+    "Game_Enemy",
+    "Game_Event",
+    "Game_Follower",
+    "Game_Followers",
+    "Game_Interpreter",
+    "Game_Item",
+    "Game_Map",
+    "Game_Party",
+    "Game_Picture",
+    "Game_Player",
+    "Game_Screen",
+    "Game_SelfSwitches",
+    "Game_Switches",
+    "Game_System",
+    "Game_Timer",
+    "Game_Troop",
+    "Game_Variables",
+    "Game_Vehicle",
+];
+
+/// `Game_Interpreter._characterId` for the player
+const PLAYER_CHARACTER_ID: i64 = -1;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub written: Vec<PathBuf>,
+    /// Save files that weren't converted, and why
+    pub skipped: Vec<(PathBuf, String)>,
+    /// Plugin classes found in the saves
+    pub plugin_classes: BTreeSet<String>,
+}
+
+/// Replaces `{"@r": id}` with a copy of the object with that `@c` id
+fn resolve_references(value: &Value) -> Result<Value, Error> {
+    fn collect<'a>(value: &'a Value, registry: &mut HashMap<u64, &'a Value>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(id) = object.get("@c").and_then(Value::as_u64) {
+                    registry.insert(id, value);
+                }
+                object.values().for_each(|x| collect(x, registry));
+            }
+            Value::Array(array) => array.iter().for_each(|x| collect(x, registry)),
+            _ => {}
+        }
+    }
+
+    fn expand(
+        value: &Value,
+        registry: &HashMap<u64, &Value>,
+        visiting: &mut Vec<u64>,
+    ) -> Result<Value, Error> {
+        match value {
+            Value::Object(object) => {
+                if let Some(id) = object.get("@r").and_then(Value::as_u64) {
+                    if visiting.contains(&id) {
+                        return Err(Error::Save(format!(
+                            "Save has a circular reference to object {id}"
+                        )));
+                    }
+                    let target = registry
+                        .get(&id)
+                        .ok_or_else(|| Error::Save(format!("Reference to missing object {id}")))?;
+
+                    visiting.push(id);
+                    let expanded = expand(target, registry, visiting);
+                    visiting.pop();
+                    return expanded;
+                }
+
+                let id = object.get("@c").and_then(Value::as_u64);
+                visiting.extend(id);
+                let object = object
+                    .iter()
+                    .map(|(key, x)| Ok((key.clone(), expand(x, registry, visiting)?)))
+                    .collect::<Result<Map<_, _>, Error>>();
+                if id.is_some() {
+                    visiting.pop();
+                }
+                Ok(Value::Object(object?))
+            }
+            Value::Array(array) => array
+                .iter()
+                .map(|x| expand(x, registry, visiting))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            _ => Ok(value.clone()),
+        }
+    }
+
+    let mut registry = HashMap::new();
+    collect(value, &mut registry);
+    expand(value, &registry, &mut vec![])
+}
+
+/// Drops `@c` ids and unwraps `{"@a": [...]}` arrays
+pub(super) fn strip_json_ex(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.shift_remove("@c");
+            if object.len() == 1
+                && let Some(Value::Array(array)) = object.shift_remove("@a")
+            {
+                *value = Value::Array(array);
+                strip_json_ex(value);
+                return;
+            }
+            object.values_mut().for_each(strip_json_ex);
+        }
+        Value::Array(array) => array.iter_mut().for_each(strip_json_ex),
+        _ => {}
+    }
+}
+
+fn class_name(value: &Value) -> Option<&str> {
+    value.get("@").and_then(Value::as_str)
+}
+
+fn collect_classes(value: &Value, classes: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(name) = class_name(value)
+                && !CORE_CLASSES.contains(&name)
+            {
+                classes.insert(name.to_string());
+            }
+            object.values().for_each(|x| collect_classes(x, classes));
+        }
+        Value::Array(array) => array.iter().for_each(|x| collect_classes(x, classes)),
+        _ => {}
+    }
+}
+
+/// Calls `f` on every object of class `name`
+fn for_each_class(value: &mut Value, name: &str, f: &mut impl FnMut(&mut Map<String, Value>)) {
+    match value {
+        Value::Object(object) => {
+            object.values_mut().for_each(|x| for_each_class(x, name, f));
+            if object.get("@").and_then(Value::as_str) == Some(name) {
+                f(object);
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(|x| for_each_class(x, name, f)),
+        _ => {}
+    }
+}
+
+/// MV keeps the character a move route waits on (`_character`), MZ its id
+fn interpreter_to_mz(interpreter: &mut Map<String, Value>) {
+    let Some(character) = interpreter.shift_remove("_character") else {
+        return;
+    };
+
+    let id = match class_name(&character) {
+        Some("Game_Player") => PLAYER_CHARACTER_ID,
+        _ => character
+            .get("_eventId")
+            .and_then(Value::as_i64)
+            .unwrap_or(0),
+    };
+    interpreter.insert("_characterId".into(), id.into());
+}
+
+fn interpreter_to_mv(interpreter: &mut Map<String, Value>) {
+    if interpreter.shift_remove("_characterId").is_none() {
+        return;
+    }
+
+    // MV waits on the character object itself, which can't be rebuilt here.
+    // Waiting on a null character would crash, so the wait is dropped.
+    interpreter.insert("_character".into(), Value::Null);
+    if interpreter.get("_waitMode").and_then(Value::as_str) == Some("route") {
+        interpreter.insert("_waitMode".into(), "".into());
+    }
+}
+
+/// Converts a decoded save to the layout of `to`. `file_id` is the slot it's
+/// going into, which MZ keeps in the save itself.
+pub fn convert_save(save: &Json, file_id: usize, to: Format) -> Result<Json, Error> {
+    let mut value = resolve_references(save.value())?;
+    strip_json_ex(&mut value);
+
+    match to {
+        Format::Mz => for_each_class(&mut value, "Game_Interpreter", &mut interpreter_to_mz),
+        Format::Mv => for_each_class(&mut value, "Game_Interpreter", &mut interpreter_to_mv),
+    }
+
+    if let Some(system) = value.get_mut("system").and_then(Value::as_object_mut) {
+        system.insert("_versionId".into(), 0.into());
+        match to {
+            Format::Mz => {
+                system.insert("_savefileId".into(), file_id.into());
+            }
+            Format::Mv => {
+                system.shift_remove("_savefileId");
+            }
+        }
+    }
+
+    Ok(Json::new(value))
+}
+
+/// Plugin classes used in a save
+pub fn plugin_classes(save: &Json) -> BTreeSet<String> {
+    let mut classes = BTreeSet::new();
+    collect_classes(save.value(), &mut classes);
+    classes
+}
+
+/// Only MV entries carry a global id, and only MZ has an autosave (index 0)
+pub fn convert_global(global: &Global, to: Format) -> Global {
+    let entries = global
+        .0
+        .iter()
+        .enumerate()
+        .map(|(file_id, entry)| {
+            let mut entry = entry.clone()?;
+            match to {
+                Format::Mv if file_id == 0 => return None,
+                Format::Mv => entry.global_id = Some(MV_GLOBAL_ID.to_string()),
+                Format::Mz => entry.global_id = None,
+            }
+            Some(entry)
+        })
+        .collect();
+
+    Global(entries)
+}
+
+/// Only MZ has the touch UI option, on by default
+pub fn convert_config(config: &Config, to: Format) -> Config {
+    let touch_ui = match to {
+        Format::Mv => None,
+        Format::Mz => Some(config.touch_ui.unwrap_or(true)),
+    };
+
+    Config {
+        touch_ui,
+        ..config.clone()
+    }
+}
+
+fn other(format: Format) -> Format {
+    match format {
+        Format::Mv => Format::Mz,
+        Format::Mz => Format::Mv,
+    }
+}
+
+/// Converts every save in `from_dir` into `to_dir`, in the format `to`.
+/// The converted index entries are merged into the global file already in
+/// `to_dir`, so the saves there stay listed. Existing files are snapshotted
+/// first.
+pub fn convert_dir(from_dir: &Path, to_dir: &Path, to: Format) -> Result<Report, Error> {
+    let from = other(to);
+    let mut report = Report::default();
+
+    let mut saves = vec![];
+    for (file_id, path) in from.save_files(from_dir)? {
+        if file_id == 0 && to == Format::Mv {
+            report
+                .skipped
+                .push((path, "MV has no autosave slot".to_string()));
+            continue;
+        }
+
+        let save = match Json::read_file(&path) {
+            Ok(save) => save,
+            Err(e) => {
+                report.skipped.push((path, e.to_string()));
+                continue;
+            }
+        };
+        match convert_save(&save, file_id, to) {
+            Ok(converted) => {
+                report.plugin_classes.extend(plugin_classes(&save));
+                saves.push((file_id, converted));
+            }
+            Err(e) => report.skipped.push((path, e.to_string())),
+        }
+    }
+
+    let from_global = from.global_path(from_dir);
+    let from_global = if from_global.exists() {
+        Some(Global::read_file(&from_global)?)
+    } else {
+        None
+    };
+
+    let from_config = from.config_path(from_dir);
+    let config = if from_config.exists() {
+        Some(convert_config(&Config::read_file(&from_config)?, to))
+    } else {
+        None
+    };
+
+    let to_global = to.global_path(to_dir);
+    let mut global = if to_global.exists() {
+        Global::read_file(&to_global)?
+    } else {
+        Global(vec![None])
+    };
+    if let Some(from_global) = from_global {
+        let converted = convert_global(&from_global, to);
+        for (file_id, _) in &saves {
+            global.set(*file_id, converted.get(*file_id).cloned());
+        }
+    }
+
+    std::fs::create_dir_all(to_dir)?;
+
+    let mut files = saves
+        .iter()
+        .map(|(file_id, _)| to.file_path(to_dir, *file_id))
+        .collect::<Vec<_>>();
+    files.push(to_global.clone());
+    if config.is_some() {
+        files.push(to.config_path(to_dir));
+    }
+    backup::snapshot(to_dir, &files)?;
+
+    for (file_id, save) in &saves {
+        let path = to.file_path(to_dir, *file_id);
+        save.write_file_without_backup(&path)?;
+        report.written.push(path);
+    }
+
+    global.to_json()?.write_file_without_backup(&to_global)?;
+    report.written.push(to_global);
+
+    if let Some(config) = config {
+        let path = to.config_path(to_dir);
+        config.to_json()?.write_file_without_backup(&path)?;
+        report.written.push(path);
+    }
+
+    Ok(report)
+}
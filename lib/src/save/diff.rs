@@ -0,0 +1,669 @@
+use super::{
+    Json,
+    convert::strip_json_ex,
+    extension::{Registry, plugin_keys},
+    model::{Actor, GameSave},
+    patch::json_equal,
+};
+use crate::{
+    data::{
+        DataSource, Table,
+        database::{Armor, Item, Weapon},
+        map::MapInfo,
+        system::System,
+    },
+    error::Error,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+};
+
+/// Names from the game's database, used to label ids in a diff
+#[derive(Debug, Default, Clone)]
+pub struct Names {
+    pub switches: Vec<String>,
+    pub variables: Vec<String>,
+    pub items: Vec<String>,
+    pub weapons: Vec<String>,
+    pub armors: Vec<String>,
+    pub maps: Vec<String>,
+}
+impl Names {
+    pub fn load(source: &DataSource) -> Result<Self, Error> {
+        // Indexed by id, with an empty name where there's no entry
+        fn names<T>(table: Table<T>, name: impl Fn(T) -> String) -> Vec<String> {
+            table
+                .0
+                .into_iter()
+                .map(|x| x.map(&name).unwrap_or_default())
+                .collect()
+        }
+
+        let system: System = source.read("System.json")?;
+        let items: Table<Item> = source.read("Items.json")?;
+        let weapons: Table<Weapon> = source.read("Weapons.json")?;
+        let armors: Table<Armor> = source.read("Armors.json")?;
+        let maps: Table<MapInfo> = source.read("MapInfos.json")?;
+
+        Ok(Self {
+            switches: system.switches,
+            variables: system.variables,
+            items: names(items, |x| x.name),
+            weapons: names(weapons, |x| x.name),
+            armors: names(armors, |x| x.name),
+            maps: names(maps, |x| x.name),
+        })
+    }
+
+    fn lookup(names: &[String], id: u32) -> Option<String> {
+        names.get(id as usize).filter(|x| !x.is_empty()).cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+impl<T> Change<T>
+where
+    T: PartialEq,
+{
+    fn new(old: T, new: T) -> Option<Self> {
+        (old != new).then_some(Self { old, new })
+    }
+}
+impl Change<Value> {
+    /// Like [`Change::new`], but `1` and `1.0` are the same
+    fn of_values(old: Value, new: Value) -> Option<Self> {
+        (!json_equal(&old, &new)).then_some(Self { old, new })
+    }
+}
+
+fn values_equal(old: Option<&Value>, new: Option<&Value>) -> bool {
+    match (old, new) {
+        (Some(old), Some(new)) => json_equal(old, new),
+        (old, new) => old == new,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NamedChange<T> {
+    pub id: u32,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub change: Change<T>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemKind {
+    Item,
+    Weapon,
+    Armor,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemChange {
+    pub kind: ItemKind,
+    #[serde(flatten)]
+    pub change: NamedChange<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SelfSwitchChange {
+    pub key: String,
+    #[serde(flatten)]
+    pub change: Change<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActorChange {
+    pub id: u32,
+    pub name: String,
+    pub fields: BTreeMap<&'static str, Change<Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub map_id: u32,
+    pub map_name: Option<String>,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Summaries of a plugin section before and after, see [`super::extension`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SectionChange {
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathChange {
+    /// JSON Pointer into the save
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveDiff {
+    pub switches: Vec<NamedChange<bool>>,
+    pub variables: Vec<NamedChange<Value>>,
+    pub self_switches: Vec<SelfSwitchChange>,
+    pub gold: Option<Change<f64>>,
+    pub items: Vec<ItemChange>,
+    pub actors: Vec<ActorChange>,
+    pub position: Option<Change<Position>>,
+    /// Sections claimed by an extension, as it summarises them
+    pub plugins: Vec<SectionChange>,
+    /// Raw changes to everything else, including unclaimed plugin sections
+    pub other: Vec<PathChange>,
+}
+impl SaveDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Paths the structured part of the diff already reports, with `*` matching any segment.
+/// `@a` wrappers are stripped before matching.
+const COVERED_PATHS: &[&[&str]] = &[
+    &["switches", "_data"],
+    &["variables", "_data"],
+    &["selfSwitches", "_data"],
+    &["party", "_gold"],
+    &["party", "_items"],
+    &["party", "_weapons"],
+    &["party", "_armors"],
+    &["actors", "_data", "*", "_name"],
+    &["actors", "_data", "*", "_nickname"],
+    &["actors", "_data", "*", "_classId"],
+    &["actors", "_data", "*", "_level"],
+    &["actors", "_data", "*", "_exp"],
+    &["actors", "_data", "*", "_hp"],
+    &["actors", "_data", "*", "_mp"],
+    &["actors", "_data", "*", "_tp"],
+    &["actors", "_data", "*", "_skills"],
+    &["actors", "_data", "*", "_equips"],
+    &["actors", "_data", "*", "_states"],
+    &["actors", "_data", "*", "_paramPlus"],
+    &["map", "_mapId"],
+    &["player", "_x"],
+    &["player", "_y"],
+    &["player", "_realX"],
+    &["player", "_realY"],
+];
+
+fn is_covered(path: &[String]) -> bool {
+    let path = path.iter().filter(|x| *x != "@a").collect::<Vec<_>>();
+
+    COVERED_PATHS.iter().any(|covered| {
+        path.len() >= covered.len() && covered.iter().zip(&path).all(|(c, p)| *c == "*" || c == p)
+    })
+}
+
+fn pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|x| format!("/{}", x.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+fn diff_values(
+    path: &mut Vec<String>,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    out: &mut Vec<PathChange>,
+) {
+    if is_covered(path) {
+        return;
+    }
+
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let keys = old
+                .keys()
+                .chain(new.keys().filter(|k| !old.contains_key(*k)));
+            for key in keys.cloned().collect::<Vec<_>>() {
+                // JsonEx object ids are renumbered on every save
+                if key == "@c" {
+                    continue;
+                }
+                let (old, new) = (old.get(&key), new.get(&key));
+                path.push(key);
+                diff_values(path, old, new, out);
+                path.pop();
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for i in 0..old.len().max(new.len()) {
+                path.push(i.to_string());
+                diff_values(path, old.get(i), new.get(i), out);
+                path.pop();
+            }
+        }
+        (old, new) if !values_equal(old, new) => out.push(PathChange {
+            path: pointer(path),
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Whole numbers are shown without a fraction, the way the game writes them
+fn number(x: f64) -> Value {
+    if x.fract() == 0.0 && x.abs() < 2f64.powi(53) {
+        Value::from(x as i64)
+    } else {
+        Value::from(x)
+    }
+}
+
+fn diff_actor(id: u32, old: Option<&Actor>, new: Option<&Actor>) -> Option<ActorChange> {
+    let fields = |actor: Option<&Actor>| -> BTreeMap<&'static str, Value> {
+        let Some(a) = actor else {
+            return BTreeMap::new();
+        };
+        let equips = a
+            .equips
+            .iter()
+            .map(|e| Value::from(format!("{}:{}", e.data_class, e.item_id)))
+            .collect::<Vec<_>>();
+
+        BTreeMap::from([
+            ("name", Value::from(a.name.clone())),
+            ("nickname", Value::from(a.nickname.clone())),
+            ("classId", Value::from(a.class_id)),
+            ("level", number(a.level)),
+            (
+                "exp",
+                number(a.exp.get(&a.class_id).copied().unwrap_or(0.0)),
+            ),
+            ("hp", number(a.hp)),
+            ("mp", number(a.mp)),
+            ("tp", number(a.tp)),
+            ("skills", Value::from(a.skills.0.clone())),
+            ("equips", Value::from(equips)),
+            ("states", Value::from(a.states.0.clone())),
+            (
+                "paramPlus",
+                a.param_plus.iter().copied().map(number).collect(),
+            ),
+        ])
+    };
+
+    let old_fields = fields(old);
+    let new_fields = fields(new);
+
+    let keys = old_fields
+        .keys()
+        .chain(new_fields.keys())
+        .collect::<BTreeSet<_>>();
+    let changes = keys
+        .into_iter()
+        .filter_map(|key| {
+            let old = old_fields.get(key).cloned().unwrap_or(Value::Null);
+            let new = new_fields.get(key).cloned().unwrap_or(Value::Null);
+            Change::of_values(old, new).map(|c| (*key, c))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    if changes.is_empty() {
+        return None;
+    }
+
+    let name = new.or(old).map(|a| a.name.clone()).unwrap_or_default();
+    Some(ActorChange {
+        id,
+        name,
+        fields: changes,
+    })
+}
+
+fn diff_counts(
+    kind: ItemKind,
+    names: &[String],
+    old: &BTreeMap<u32, f64>,
+    new: &BTreeMap<u32, f64>,
+    out: &mut Vec<ItemChange>,
+) {
+    let ids = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    for id in ids {
+        let change = Change::new(
+            old.get(id).copied().unwrap_or(0.0),
+            new.get(id).copied().unwrap_or(0.0),
+        );
+        if let Some(change) = change {
+            out.push(ItemChange {
+                kind,
+                change: NamedChange {
+                    id: *id,
+                    name: Names::lookup(names, *id),
+                    change,
+                },
+            });
+        }
+    }
+}
+
+fn diff_sections(old: &Json, new: &Json, extensions: &Registry) -> Vec<SectionChange> {
+    // Unclaimed sections are left to the raw changes
+    let keys = plugin_keys(old)
+        .chain(plugin_keys(new).filter(|k| old.value().get(k).is_none()))
+        .filter(|k| extensions.is_registered(k))
+        .collect::<Vec<_>>();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let stripped = |save: &Json| {
+                save.value().get(key).cloned().map(|mut x| {
+                    strip_json_ex(&mut x);
+                    x
+                })
+            };
+            if values_equal(stripped(old).as_ref(), stripped(new).as_ref()) {
+                return None;
+            }
+
+            let summary = |save: &Json| {
+                let value = save.value().get(key)?;
+                Some(extensions.section(key, value).summary)
+            };
+            Some(SectionChange {
+                key: key.to_string(),
+                old: summary(old),
+                new: summary(new),
+            })
+        })
+        .collect()
+}
+
+/// Compares two saves. Plugin sections claimed by `extensions` are
+/// summarised with them, the rest show up as raw changes.
+pub fn diff(
+    old: &Json,
+    new: &Json,
+    names: &Names,
+    extensions: &Registry,
+) -> Result<SaveDiff, Error> {
+    let old_save = GameSave::from_json(old)?;
+    let new_save = GameSave::from_json(new)?;
+
+    let switch_count = old_save
+        .switches
+        .data
+        .len()
+        .max(new_save.switches.data.len());
+    let switches = (1..switch_count)
+        .filter_map(|id| {
+            let change = Change::new(old_save.switches.get(id), new_save.switches.get(id))?;
+            let id = id as u32;
+            Some(NamedChange {
+                id,
+                name: Names::lookup(&names.switches, id),
+                change,
+            })
+        })
+        .collect();
+
+    let variable_count = old_save
+        .variables
+        .data
+        .len()
+        .max(new_save.variables.data.len());
+    let variables = (1..variable_count)
+        .filter_map(|id| {
+            let change = Change::of_values(old_save.variables.get(id), new_save.variables.get(id))?;
+            let id = id as u32;
+            Some(NamedChange {
+                id,
+                name: Names::lookup(&names.variables, id),
+                change,
+            })
+        })
+        .collect();
+
+    let self_switch_keys = old_save
+        .self_switches
+        .0
+        .keys()
+        .chain(new_save.self_switches.0.keys())
+        .collect::<BTreeSet<_>>();
+    let self_switches = self_switch_keys
+        .into_iter()
+        .filter_map(|key| {
+            let get = |save: &GameSave| save.self_switches.0.get(key).copied().unwrap_or(false);
+            let change = Change::new(get(&old_save), get(&new_save))?;
+            Some(SelfSwitchChange {
+                key: key.to_string(),
+                change,
+            })
+        })
+        .collect();
+
+    let mut items = vec![];
+    let (old_party, new_party) = (&old_save.party, &new_save.party);
+    diff_counts(
+        ItemKind::Item,
+        &names.items,
+        &old_party.items,
+        &new_party.items,
+        &mut items,
+    );
+    diff_counts(
+        ItemKind::Weapon,
+        &names.weapons,
+        &old_party.weapons,
+        &new_party.weapons,
+        &mut items,
+    );
+    diff_counts(
+        ItemKind::Armor,
+        &names.armors,
+        &old_party.armors,
+        &new_party.armors,
+        &mut items,
+    );
+
+    let actor_count = old_save.actors.data.len().max(new_save.actors.data.len());
+    let actors = (1..actor_count)
+        .filter_map(|id| diff_actor(id as u32, old_save.actors.get(id), new_save.actors.get(id)))
+        .collect();
+
+    let position = |save: &GameSave| {
+        let (x, y) = save.position();
+        let map_id = save.map_id();
+        Position {
+            map_id,
+            map_name: Names::lookup(&names.maps, map_id),
+            x,
+            y,
+        }
+    };
+
+    let mut other = vec![];
+    diff_values(
+        &mut vec![],
+        Some(old.value()),
+        Some(new.value()),
+        &mut other,
+    );
+    other.retain(|change| {
+        let key = change.path.split('/').nth(1).unwrap_or_default();
+        let key = key.replace("~1", "/").replace("~0", "~");
+        !extensions.is_registered(&key)
+    });
+
+    Ok(SaveDiff {
+        switches,
+        variables,
+        self_switches,
+        gold: Change::new(old_party.gold, new_party.gold),
+        items,
+        actors,
+        position: Change::new(position(&old_save), position(&new_save)),
+        plugins: diff_sections(old, new, extensions),
+        other,
+    })
+}
+
+fn write_name(f: &mut Formatter<'_>, name: &Option<String>) -> std::fmt::Result {
+    match name {
+        Some(name) => write!(f, " \"{name}\""),
+        None => Ok(()),
+    }
+}
+
+fn write_optional(f: &mut Formatter<'_>, value: &Option<Value>) -> std::fmt::Result {
+    match value {
+        Some(value) => write!(f, "{value}"),
+        None => write!(f, "(none)"),
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "map {}", self.map_id)?;
+        write_name(f, &self.map_name)?;
+        write!(f, " ({}, {})", self.x, self.y)
+    }
+}
+
+impl Display for SaveDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for NamedChange { id, name, change } in &self.switches {
+            write!(f, "Switch {id:04}")?;
+            write_name(f, name)?;
+            writeln!(f, ": {} -> {}", change.old, change.new)?;
+        }
+
+        for NamedChange { id, name, change } in &self.variables {
+            write!(f, "Variable {id:04}")?;
+            write_name(f, name)?;
+            writeln!(f, ": {} -> {}", change.old, change.new)?;
+        }
+
+        for SelfSwitchChange { key, change } in &self.self_switches {
+            writeln!(f, "Self switch {key}: {} -> {}", change.old, change.new)?;
+        }
+
+        if let Some(gold) = &self.gold {
+            writeln!(f, "Gold: {} -> {}", gold.old, gold.new)?;
+        }
+
+        for ItemChange { kind, change } in &self.items {
+            write!(f, "{kind:?} {}", change.id)?;
+            write_name(f, &change.name)?;
+            writeln!(f, ": {} -> {}", change.change.old, change.change.new)?;
+        }
+
+        for actor in &self.actors {
+            writeln!(f, "Actor {} \"{}\":", actor.id, actor.name)?;
+            for (field, change) in &actor.fields {
+                writeln!(f, "    {field}: {} -> {}", change.old, change.new)?;
+            }
+        }
+
+        if let Some(position) = &self.position {
+            writeln!(f, "Position: {} -> {}", position.old, position.new)?;
+        }
+
+        for SectionChange { key, old, new } in &self.plugins {
+            writeln!(
+                f,
+                "Plugin data {key}: {} -> {}",
+                old.as_deref().unwrap_or("(none)"),
+                new.as_deref().unwrap_or("(none)")
+            )?;
+        }
+
+        for PathChange { path, old, new } in &self.other {
+            write!(f, "{path}: ")?;
+            write_optional(f, old)?;
+            write!(f, " -> ")?;
+            write_optional(f, new)?;
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save::model::tests::save;
+
+    const ACTOR: &str =
+        r#"{"_actorId":1,"_name":"Harold","_classId":1,"_level":5,"_hp":450,"_mp":90}"#;
+
+    fn party(gold: &str, items: &str) -> String {
+        format!(
+            r#"{{"_gold":{gold},"_actors":[1],"_items":{items},"_weapons":{{}},"_armors":{{}}}}"#
+        )
+    }
+
+    fn diff_saves(old: &Json, new: &Json) -> SaveDiff {
+        diff(old, new, &Names::default(), &Registry::default()).unwrap()
+    }
+
+    #[test]
+    fn fractional_gold_and_items_are_diffed() {
+        let old = save(&party("10.0", r#"{"1":2}"#), ACTOR);
+        let new = save(
+            &party("15.5", r#"{"1":2.5}"#),
+            r#"{"_actorId":1,"_name":"Harold","_classId":1,"_level":5,"_hp":450.5,"_mp":90}"#,
+        );
+        let diff = diff_saves(&old, &new);
+
+        assert_eq!(
+            diff.gold,
+            Some(Change {
+                old: 10.0,
+                new: 15.5
+            })
+        );
+        assert_eq!(diff.items.len(), 1);
+        assert_eq!(diff.items[0].change.change, Change { old: 2.0, new: 2.5 });
+        assert_eq!(
+            diff.actors[0].fields["hp"],
+            Change {
+                old: Value::from(450),
+                new: Value::from(450.5)
+            }
+        );
+        assert!(diff.to_string().contains("Gold: 10 -> 15.5"));
+    }
+
+    #[test]
+    fn numbers_written_differently_are_not_changes() {
+        let set = |save: &mut Json, pointer: &str, text: &str| {
+            *save.value_mut().pointer_mut(pointer).unwrap() = serde_json::from_str(text).unwrap();
+        };
+        let mut old = save(&party("10", "{}"), ACTOR);
+        let mut new = save(&party("10.0", "{}"), ACTOR);
+        set(&mut old, "/variables/_data/@a", "[null,1,3]");
+        set(&mut new, "/variables/_data/@a", "[null,1.0,2]");
+        set(&mut old, "/system", r#"{"_volume":100,"@c":1}"#);
+        set(&mut new, "/system", r#"{"_volume":1e2,"@c":1}"#);
+
+        let diff = diff_saves(&old, &new);
+        assert_eq!(diff.gold, None);
+        assert_eq!(diff.variables.len(), 1);
+        assert_eq!(diff.variables[0].id, 2);
+        assert_eq!(diff.other, vec![]);
+
+        set(&mut new, "/system/_volume", "90.5");
+        let diff = diff_saves(&old, &new);
+        assert_eq!(diff.other.len(), 1);
+        assert_eq!(diff.other[0].path, "/system/_volume");
+    }
+}
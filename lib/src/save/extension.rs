@@ -0,0 +1,227 @@
+//! Typed access to the top-level keys plugins add to saves (by extending
+//! `DataManager.makeSaveContents`).
+//!
+//! A plugin's data is described by implementing [`Extension`], and tools that
+//! show saves take a [`Registry`] of them. [`Registry::default`] comes with
+//! the extensions here, like [`QuestLog`]. Keys nobody registered still get
+//! a generic summary.
+
+use super::{Json, convert::strip_json_ex};
+use crate::error::Error;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+
+/// Keys written by `DataManager.makeSaveContents`
+pub const CORE_KEYS: &[&str] = &[
+    "system",
+    "screen",
+    "timer",
+    "switches",
+    "variables",
+    "selfSwitches",
+    "actors",
+    "party",
+    "map",
+    "player",
+];
+
+pub trait Extension {
+    /// Top-level key the plugin stores its data under
+    const KEY: &'static str;
+
+    type Data: DeserializeOwned + Serialize;
+
+    /// One line description of `data`, e.g. "3 of 12 quests complete"
+    fn summary(data: &Self::Data) -> String;
+}
+
+/// Reads the section of `E`, with JsonEx metadata removed. `None` if the save
+/// doesn't have one.
+pub fn get<E: Extension>(save: &Json) -> Result<Option<E::Data>, Error> {
+    save.value()
+        .get(E::KEY)
+        .map(|value| decode::<E>(value))
+        .transpose()
+}
+
+/// Replaces the section of `E`, keeping the class name of the old one
+pub fn set<E: Extension>(save: &mut Json, data: &E::Data) -> Result<(), Error> {
+    let mut value = serde_json::to_value(data)?;
+
+    let save = save
+        .value_mut()
+        .as_object_mut()
+        .ok_or_else(|| Error::Save("Save isn't an object".into()))?;
+
+    if let Some(class) = save.get(E::KEY).and_then(|x| x.get("@"))
+        && let Some(object) = value.as_object_mut()
+        && !object.contains_key("@")
+    {
+        object.insert("@".into(), class.clone());
+    }
+
+    save.insert(E::KEY.into(), value);
+    Ok(())
+}
+
+fn decode<E: Extension>(value: &Value) -> Result<E::Data, Error> {
+    let mut value = value.clone();
+    strip_json_ex(&mut value);
+    serde_json::from_value(value).map_err(|e| Error::Save(format!("{}: {e}", E::KEY)))
+}
+
+trait Handler: Send + Sync {
+    fn key(&self) -> &'static str;
+    fn summary(&self, value: &Value) -> Result<String, Error>;
+}
+
+struct TypedHandler<E>(PhantomData<fn() -> E>);
+impl<E: Extension> Handler for TypedHandler<E> {
+    fn key(&self) -> &'static str {
+        E::KEY
+    }
+
+    fn summary(&self, value: &Value) -> Result<String, Error> {
+        decode::<E>(value).map(|data| E::summary(&data))
+    }
+}
+
+fn count(n: usize, singular: &str, plural: &str) -> String {
+    match n {
+        1 => format!("1 {singular}"),
+        n => format!("{n} {plural}"),
+    }
+}
+
+/// Summary of a value no extension claimed
+fn generic_summary(value: &Value) -> String {
+    let mut value = value.clone();
+    strip_json_ex(&mut value);
+
+    match &value {
+        Value::Object(object) => {
+            let fields = count(
+                object.keys().filter(|x| *x != "@").count(),
+                "field",
+                "fields",
+            );
+            match object.get("@").and_then(Value::as_str) {
+                Some(class) => format!("{class} with {fields}"),
+                None => fields,
+            }
+        }
+        Value::Array(array) => count(array.len(), "entry", "entries"),
+        value => value.to_string(),
+    }
+}
+
+/// A top-level key of a save that isn't part of the engine's own data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Section {
+    pub key: String,
+    pub summary: String,
+    /// Whether a registered extension described it
+    pub is_typed: bool,
+}
+
+pub struct Registry {
+    handlers: Vec<Box<dyn Handler>>,
+}
+impl Default for Registry {
+    /// The extensions this crate ships with
+    fn default() -> Self {
+        let mut registry = Self { handlers: vec![] };
+        registry.register::<QuestLog>();
+        registry
+    }
+}
+impl Registry {
+    /// Adds `E`, replacing anything registered for the same key
+    pub fn register<E: Extension + 'static>(&mut self) -> &mut Self {
+        self.handlers.retain(|x| x.key() != E::KEY);
+        self.handlers.push(Box::new(TypedHandler::<E>(PhantomData)));
+        self
+    }
+
+    pub fn is_registered(&self, key: &str) -> bool {
+        self.handler(key).is_some()
+    }
+
+    fn handler(&self, key: &str) -> Option<&dyn Handler> {
+        self.handlers
+            .iter()
+            .find(|x| x.key() == key)
+            .map(|x| x.as_ref())
+    }
+
+    /// Describes the value of `key`, falling back to a generic summary if no
+    /// extension claims it or it doesn't decode
+    pub fn section(&self, key: &str, value: &Value) -> Section {
+        let (summary, is_typed) = match self.handler(key).map(|x| x.summary(value)) {
+            Some(Ok(summary)) => (summary, true),
+            Some(Err(e)) => (format!("Unreadable: {e}"), false),
+            None => (generic_summary(value), false),
+        };
+
+        Section {
+            key: key.to_string(),
+            summary,
+            is_typed,
+        }
+    }
+
+    /// Every plugin section of `save`, in the order they're stored
+    pub fn sections(&self, save: &Json) -> Vec<Section> {
+        plugin_keys(save)
+            .filter_map(|key| Some(self.section(key, save.value().get(key)?)))
+            .collect()
+    }
+}
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys = self.handlers.iter().map(|x| x.key()).collect::<Vec<_>>();
+        f.debug_struct("Registry").field("keys", &keys).finish()
+    }
+}
+
+/// Top-level keys that aren't part of the engine's own data
+pub fn plugin_keys(save: &Json) -> impl Iterator<Item = &str> {
+    save.value()
+        .as_object()
+        .into_iter()
+        .flat_map(|x| x.keys())
+        .map(String::as_str)
+        .filter(|key| !key.starts_with('@') && !CORE_KEYS.contains(key))
+}
+
+/// One quest of a [`QuestLog`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quest {
+    /// `"completed"`, `"failed"`, or anything else while it's in progress
+    #[serde(default)]
+    pub status: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl Quest {
+    pub fn is_complete(&self) -> bool {
+        matches!(self.status.as_str(), "completed" | "complete")
+    }
+}
+
+/// The quest list quest log plugins save under `quests`, indexed by quest id
+/// with a null for unused ids
+pub struct QuestLog;
+impl Extension for QuestLog {
+    const KEY: &'static str = "quests";
+
+    type Data = Vec<Option<Quest>>;
+
+    fn summary(data: &Self::Data) -> String {
+        let quests = data.iter().flatten();
+        let complete = quests.clone().filter(|x| x.is_complete()).count();
+        format!("{complete} of {} quests complete", quests.count())
+    }
+}
@@ -0,0 +1,130 @@
+use super::Json;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// `DataManager._globalId` in MV
+pub const MV_GLOBAL_ID: &str = "RPGMV";
+
+#[derive(Deserialize, Serialize)]
+struct SpriteInner(String, usize);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SpriteInfo {
+    pub file_name: String,
+    pub sprite_index: usize,
+}
+impl<'de> Deserialize<'de> for SpriteInfo {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let SpriteInner(file_name, sprite_index) = SpriteInner::deserialize(deserializer)?;
+        Ok(Self {
+            file_name,
+            sprite_index,
+        })
+    }
+}
+impl Serialize for SpriteInfo {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        SpriteInner(self.file_name.clone(), self.sprite_index).serialize(serializer)
+    }
+}
+
+/// Milliseconds since the epoch, as written by `Date.now()`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DateTime(pub chrono::DateTime<chrono::Utc>);
+impl<'de> Deserialize<'de> for DateTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = i64::deserialize(deserializer)?;
+        chrono::DateTime::from_timestamp_millis(value)
+            .map(Self)
+            .ok_or_else(|| serde::de::Error::custom(format!("Invalid timestamp: {value}")))
+    }
+}
+impl Serialize for DateTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let timestamp = self.0.timestamp_millis();
+        timestamp.serialize(serializer)
+    }
+}
+
+/// Entry written by `DataManager.makeSavefileInfo`, fields in the order the engine writes them
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalEntry {
+    /// MV only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_id: Option<String>,
+    pub title: String,
+    pub characters: Vec<SpriteInfo>,
+    pub faces: Vec<SpriteInfo>,
+    pub playtime: String,
+    pub timestamp: DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Value>,
+    /// Keys added by plugins
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl GlobalEntry {
+    /// Same check as `DataManager.isThisGameFile` for non-local (web) saves.
+    /// MZ doesn't write a global id, so only the title is compared there.
+    pub fn is_this_game(&self, game_title: &str) -> bool {
+        let id_matches = match &self.global_id {
+            Some(id) => id == MV_GLOBAL_ID,
+            None => true,
+        };
+
+        id_matches && self.title == game_title
+    }
+}
+
+/// `global.rpgsave`, indexed by save file id. Index 0 is always empty.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct Global(pub Vec<Option<GlobalEntry>>);
+impl Global {
+    pub fn from_json(json: &Json) -> Result<Self, Error> {
+        Self::deserialize(json.value()).map_err(|e| Error::Save(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<Json, Error> {
+        serde_json::to_value(self)
+            .map(Json::new)
+            .map_err(|e| e.into())
+    }
+
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        Self::from_json(&Json::read_file(path)?)
+    }
+
+    pub fn write_file(&self, path: &Path) -> Result<(), Error> {
+        self.to_json()?.write_file(path)
+    }
+
+    pub fn get(&self, file_id: usize) -> Option<&GlobalEntry> {
+        self.0.get(file_id).and_then(Option::as_ref)
+    }
+
+    /// Sets the entry for `file_id`, growing the list as needed
+    pub fn set(&mut self, file_id: usize, entry: Option<GlobalEntry>) {
+        if entry.is_none() && self.0.len() <= file_id {
+            return;
+        }
+        if self.0.len() <= file_id {
+            self.0.resize(file_id + 1, None);
+        }
+        self.0[file_id] = entry;
+    }
+}
@@ -0,0 +1,327 @@
+pub mod backup;
+pub mod config;
+pub mod convert;
+pub mod diff;
+pub mod extension;
+pub mod global;
+pub mod model;
+pub mod patch;
+pub mod rebuild;
+pub mod slots;
+pub mod switches;
+pub mod teleport;
+pub mod unpack;
+pub mod validate;
+
+use crate::{
+    error::{
+        Error::{self, *},
+        LzErrorKind,
+    },
+    layout::Engine,
+    util::write_atomic,
+};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+pub fn decompress(data: &str) -> Result<String, Error> {
+    let decompressed =
+        lz_str::decompress_from_base64(data).ok_or(Lz(LzErrorKind::Decompression))?;
+    String::from_utf16(&decompressed).map_err(|_| Lz(LzErrorKind::Decompression))
+}
+
+pub fn compress(data: &str) -> String {
+    lz_str::compress_to_base64(data)
+}
+
+/// Zlib compressed (MZ). The compressed bytes are stored one per character
+/// as a UTF-8 string, the way `pako.deflate(json, { to: "string" })` output
+/// ends up on disk.
+pub fn decompress_zlib(data: &[u8]) -> Result<String, Error> {
+    let s = std::str::from_utf8(data).map_err(|_| Lz(LzErrorKind::Decompression))?;
+    let bytes = s
+        .chars()
+        .map(|c| u8::try_from(c).map_err(|_| Lz(LzErrorKind::Decompression)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut output = String::new();
+    ZlibDecoder::new(bytes.as_slice())
+        .read_to_string(&mut output)
+        .map_err(|_| Lz(LzErrorKind::Decompression))?;
+    Ok(output)
+}
+
+pub fn compress_zlib(data: &str) -> Result<Vec<u8>, Error> {
+    // MZ saves with level 1
+    let mut encoder = ZlibEncoder::new(vec![], Compression::fast());
+    encoder
+        .write_all(data.as_bytes())
+        .map_err(|_| Lz(LzErrorKind::Compression))?;
+    let bytes = encoder.finish().map_err(|_| Lz(LzErrorKind::Compression))?;
+
+    Ok(bytes.into_iter().map(char::from).collect::<String>().into_bytes())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Format {
+    /// `.rpgsave`, lzstring base64
+    #[default]
+    Mv,
+    /// `.rmmzsave`, zlib
+    Mz,
+}
+impl From<Engine> for Format {
+    fn from(value: Engine) -> Self {
+        match value {
+            Engine::Mv => Self::Mv,
+            Engine::Mz => Self::Mz,
+        }
+    }
+}
+impl Format {
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Mv => "rpgsave",
+            Self::Mz => "rmmzsave",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("rpgsave") => Ok(Self::Mv),
+            Some("rmmzsave") => Ok(Self::Mz),
+            _ => Err(Save(format!("Unknown save format: {}", path.display()))),
+        }
+    }
+
+    /// `fileN.rpgsave` / `fileN.rmmzsave`
+    pub fn file_path(self, save_dir: &Path, file_id: usize) -> PathBuf {
+        save_dir.join(format!("file{file_id}.{}", self.extension()))
+    }
+
+    pub fn global_path(self, save_dir: &Path) -> PathBuf {
+        save_dir.join(format!("global.{}", self.extension()))
+    }
+
+    pub fn config_path(self, save_dir: &Path) -> PathBuf {
+        save_dir.join(format!("config.{}", self.extension()))
+    }
+
+    /// Save files in `save_dir` with their file ids, sorted by id
+    pub fn save_files(self, save_dir: &Path) -> Result<Vec<(usize, PathBuf)>, Error> {
+        let mut files = std::fs::read_dir(save_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let file_id = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_prefix("file")?
+                    .strip_suffix(self.extension())?
+                    .strip_suffix('.')?
+                    .parse()
+                    .ok()?;
+                Some((file_id, path))
+            })
+            .collect::<Vec<_>>();
+
+        files.sort();
+        Ok(files)
+    }
+
+    pub fn decode(self, data: &[u8]) -> Result<String, Error> {
+        match self {
+            Self::Mv => {
+                let s = std::str::from_utf8(data).map_err(|_| Lz(LzErrorKind::Decompression))?;
+                decompress(s)
+            }
+            Self::Mz => decompress_zlib(data),
+        }
+    }
+
+    pub fn encode(self, data: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Mv => Ok(compress(data).into_bytes()),
+            Self::Mz => compress_zlib(data),
+        }
+    }
+}
+
+/// Decoded save JSON.
+///
+/// Object key order and the original text of numbers are kept, so an unedited
+/// save compresses back to the exact bytes it was read from.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct Json(serde_json::Value);
+impl Json {
+    pub fn decompress(data: &str) -> Result<Self, Error> {
+        let decompressed = decompress(data)?;
+        serde_json::from_str(&decompressed)
+            .map(Json)
+            .map_err(|e| e.into())
+    }
+
+    pub fn new(value: serde_json::Value) -> Self {
+        Self(value)
+    }
+
+    /// Reads a save file, picking the format from its extension
+    pub fn read_file(path: &Path) -> Result<Self, Error> {
+        let format = Format::from_path(path)?;
+        let data = std::fs::read(path)?;
+        Self::from_string(&format.decode(&data)?)
+    }
+
+    /// Writes a save file. Overwriting a file in a save directory snapshots
+    /// it with [`backup::snapshot`] first.
+    pub fn write_file(&self, path: &Path) -> Result<(), Error> {
+        let save_dir = path.parent().unwrap_or(Path::new("."));
+        if path.exists() && backup::is_save_dir(save_dir) {
+            backup::snapshot(save_dir, &[path.to_path_buf()])?;
+        }
+        self.write_file_without_backup(path)
+    }
+
+    /// For operations that snapshot all the files they touch beforehand
+    pub(crate) fn write_file_without_backup(&self, path: &Path) -> Result<(), Error> {
+        let format = Format::from_path(path)?;
+        let data = format.encode(&self.to_string()?)?;
+        write_atomic(path, &data)
+    }
+
+    pub fn from_string(s: &str) -> Result<Self, Error> {
+        serde_json::from_str(s).map(Self).map_err(|e| e.into())
+    }
+
+    pub fn compress(&self) -> Result<String, Error> {
+        let s = self.to_string();
+        s.map(|x| compress(&x))
+    }
+
+    pub fn inner(self) -> serde_json::Value {
+        self.0
+    }
+
+    pub fn value(&self) -> &serde_json::Value {
+        &self.0
+    }
+
+    pub fn value_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.0
+    }
+
+    pub fn to_string(&self) -> Result<String, Error> {
+        serde_json::to_string(&self.0).map_err(|e| e.into())
+    }
+
+    pub fn to_string_pretty(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(&self.0).map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed `file1.rpgsave` from MV 1.6, as written by `JsonEx.stringify`
+    const MV_SAVE: &str = concat!(
+        r#"{"system":{"_saveEnabled":true,"_menuEnabled":true,"_encounterEnabled":true,"#,
+        r#""_formationEnabled":true,"_battleCount":3,"_winCount":2,"_escapeCount":1,"#,
+        r#""_saveCount":4,"_versionId":48531017,"_framesOnSave":108000,"#,
+        r#""_bgmOnSave":{"name":"Town1","volume":90,"pitch":100,"pan":0,"pos":12.345},"#,
+        r#""_windowTone":null,"_battleBgm":null,"_victoryMe":null,"_defeatMe":null,"#,
+        r#""_savedBgm":null,"_walkingBgm":null,"@c":1},"#,
+        r#""switches":{"_data":{"@a":[null,true,false,null,true]},"@c":3},"#,
+        r#""variables":{"_data":{"@a":[null,1.0,1e+21,12345678901234567890,-0.5,"x"]},"@c":4},"#,
+        r#""actors":{"_data":{"@a":[null,{"_name":"Harold","_nickname":"","#,
+        r#""_profile":"","_classId":1,"_level":5,"_characterName":"Actor1","#,
+        r#""_characterIndex":0,"_faceName":"Actor1","_faceIndex":0,"#,
+        r#""_equips":{"@a":[{"_dataClass":"weapon","_itemId":1,"@c":7},"#,
+        r#"{"_dataClass":"armor","_itemId":0,"@c":7}]},"#,
+        r#""_exp":{"1":0,"2":0},"_skills":{"@a":[8,10]},"_hp":450,"_mp":90,"#,
+        r#""_states":{"@a":[]},"_buffs":{"@a":[0,0,0,0,0,0,0,0]},"@c":6}]},"@c":5},"#,
+        r#""party":{"_inBattle":false,"_gold":1200,"_steps":842,"#,
+        r#""_actors":{"@a":[1]},"_items":{"7":2,"1":5},"_weapons":{},"_armors":{},"@c":8},"#,
+        r#""map":{"_mapId":3,"_displayX":4.5,"_displayY":0,"@c":9},"#,
+        r#""player":{"_x":10,"_y":8,"_realX":10,"_realY":8,"_direction":2,"#,
+        r#""_followers":{"_visible":true,"_gathering":false,"#,
+        r#""_data":{"@a":[{"_memberIndex":1,"@c":11}]},"@c":10},"@c":2},"#,
+        r#""selfSwitches":{"_data":{"3,1,A":true},"@c":12},"#,
+        r#""shared":{"@r":6},"#,
+        r#""text":"\"Harold\" \\ 勇者 🗡️ 😀 \u0000"}"#,
+    );
+
+    #[test]
+    fn json_text_round_trips() {
+        for text in [
+            MV_SAVE,
+            r#"{"z":1,"a":2,"m":[1.0,1e+21,-1E-7,0.10,18446744073709551616]}"#,
+            r#"{"@c":1,"@a":[],"@r":2}"#,
+            r#"["😀","𝄞","𝄞"]"#,
+        ] {
+            let json = Json::from_string(text).unwrap();
+            let value = json.to_string().unwrap();
+            assert_eq!(value, text);
+        }
+    }
+
+    #[test]
+    fn numbers_and_key_order_are_kept() {
+        let json = Json::from_string(MV_SAVE).unwrap();
+        let text = json.to_string().unwrap();
+        assert!(text.contains(r#"[null,1.0,1e+21,12345678901234567890,-0.5,"x"]"#));
+        assert!(text.contains(r#""_items":{"7":2,"1":5}"#));
+        assert!(text.starts_with(r#"{"system":{"_saveEnabled":true"#));
+        assert!(text.contains(r#""shared":{"@r":6}"#));
+    }
+
+    #[test]
+    fn non_bmp_strings_survive_lz_string() {
+        let decoded = Format::Mv
+            .decode(&Format::Mv.encode(MV_SAVE).unwrap())
+            .unwrap();
+        assert_eq!(decoded, MV_SAVE);
+        assert!(decoded.contains("🗡️ 😀"));
+    }
+
+    #[test]
+    fn unedited_saves_encode_to_the_same_bytes() {
+        for format in [Format::Mv, Format::Mz] {
+            let bytes = format.encode(MV_SAVE).unwrap();
+
+            let json = Json::from_string(&format.decode(&bytes).unwrap()).unwrap();
+            let again = format.encode(&json.to_string().unwrap()).unwrap();
+            assert_eq!(again, bytes, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn read_and_write_file_keep_the_bytes() {
+        let dir = std::env::temp_dir().join(format!("lib-save-round-trip-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for format in [Format::Mv, Format::Mz] {
+            let bytes = format.encode(MV_SAVE).unwrap();
+            let path = format.file_path(&dir, 1);
+            std::fs::write(&path, &bytes).unwrap();
+
+            let json = Json::read_file(&path).unwrap();
+            json.write_file_without_backup(&path).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), bytes, "{format:?}");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mz_bytes_are_one_char_each() {
+        let bytes = Format::Mz.encode(MV_SAVE).unwrap();
+        let s = std::str::from_utf8(&bytes).unwrap();
+        assert!(s.chars().all(|x| u32::from(x) <= 0xff));
+        assert_eq!(decompress_zlib(&bytes).unwrap(), MV_SAVE);
+    }
+}
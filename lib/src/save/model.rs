@@ -0,0 +1,360 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use super::Json;
+use crate::error::Error;
+
+/// Array as written by JsonEx, either plain or wrapped as `{ "@c": id, "@a": [...] }` (MV 1.6+)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JsonExArray<T>(pub Vec<T>);
+impl<'de, T> Deserialize<'de> for JsonExArray<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Inner<T> {
+            Plain(Vec<T>),
+            Wrapped {
+                #[serde(rename = "@a")]
+                array: Vec<T>,
+            },
+        }
+
+        match Inner::deserialize(deserializer)? {
+            Inner::Plain(array) | Inner::Wrapped { array } => Ok(Self(array)),
+        }
+    }
+}
+impl<T> std::ops::Deref for JsonExArray<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Object keyed by database id, e.g. `Game_Party._items`. JsonEx bookkeeping keys are skipped.
+fn id_map<'de, D, T>(deserializer: D) -> Result<BTreeMap<u32, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let map = Map::deserialize(deserializer)?;
+    map.into_iter()
+        .filter(|(key, _)| !key.starts_with('@'))
+        .map(|(key, value)| {
+            let id = key.parse().map_err(serde::de::Error::custom)?;
+            let value = T::deserialize(value).map_err(serde::de::Error::custom)?;
+            Ok((id, value))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct System {
+    #[serde(rename = "_framesOnSave")]
+    pub frames_on_save: Option<u64>,
+    #[serde(rename = "_saveCount")]
+    pub save_count: Option<u64>,
+    #[serde(rename = "_versionId")]
+    pub version_id: Option<i64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Switches {
+    #[serde(rename = "_data")]
+    pub data: JsonExArray<Value>,
+}
+impl Switches {
+    pub fn get(&self, id: usize) -> bool {
+        self.data.get(id).and_then(Value::as_bool).unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Variables {
+    #[serde(rename = "_data")]
+    pub data: JsonExArray<Value>,
+}
+impl Variables {
+    /// Unset variables read as 0 in game
+    pub fn get(&self, id: usize) -> Value {
+        match self.data.get(id) {
+            None | Some(Value::Null) => Value::from(0),
+            Some(value) => value.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SelfSwitchKey {
+    pub map_id: u32,
+    pub event_id: u32,
+    pub letter: String,
+}
+impl std::str::FromStr for SelfSwitchKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Save(format!("Invalid self switch key: {s}"));
+
+        let mut parts = s.split(',');
+        let mut next_id = || -> Result<u32, Error> {
+            parts
+                .next()
+                .and_then(|x| x.parse().ok())
+                .ok_or_else(invalid)
+        };
+        let map_id = next_id()?;
+        let event_id = next_id()?;
+        let letter = parts.next().ok_or_else(invalid)?.to_string();
+
+        Ok(Self {
+            map_id,
+            event_id,
+            letter,
+        })
+    }
+}
+impl std::fmt::Display for SelfSwitchKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.map_id, self.event_id, self.letter)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelfSwitches(pub BTreeMap<SelfSwitchKey, bool>);
+impl<'de> Deserialize<'de> for SelfSwitches {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Inner {
+            #[serde(rename = "_data")]
+            data: Map<String, Value>,
+        }
+
+        let Inner { data } = Inner::deserialize(deserializer)?;
+        data.into_iter()
+            .filter(|(key, _)| !key.starts_with('@'))
+            .map(|(key, value)| {
+                let key = key.parse().map_err(serde::de::Error::custom)?;
+                Ok((key, value.as_bool().unwrap_or(false)))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+pub struct GameItem {
+    #[serde(rename = "_dataClass")]
+    pub data_class: String,
+    #[serde(rename = "_itemId")]
+    pub item_id: u32,
+}
+
+/// Stats are `f64` for the same reason as in [`Party`]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Actor {
+    #[serde(rename = "_actorId")]
+    pub actor_id: u32,
+    #[serde(rename = "_name")]
+    pub name: String,
+    #[serde(rename = "_nickname", default)]
+    pub nickname: String,
+    #[serde(rename = "_classId")]
+    pub class_id: u32,
+    #[serde(rename = "_level")]
+    pub level: f64,
+    #[serde(rename = "_exp", deserialize_with = "id_map", default)]
+    pub exp: BTreeMap<u32, f64>,
+    #[serde(rename = "_hp")]
+    pub hp: f64,
+    #[serde(rename = "_mp")]
+    pub mp: f64,
+    #[serde(rename = "_tp", default)]
+    pub tp: f64,
+    #[serde(rename = "_skills", default)]
+    pub skills: JsonExArray<u32>,
+    #[serde(rename = "_equips", default)]
+    pub equips: JsonExArray<GameItem>,
+    #[serde(rename = "_states", default)]
+    pub states: JsonExArray<u32>,
+    #[serde(rename = "_paramPlus", default)]
+    pub param_plus: JsonExArray<f64>,
+    #[serde(rename = "_characterName", default)]
+    pub character_name: String,
+    #[serde(rename = "_characterIndex", default)]
+    pub character_index: usize,
+    #[serde(rename = "_faceName", default)]
+    pub face_name: String,
+    #[serde(rename = "_faceIndex", default)]
+    pub face_index: usize,
+    #[serde(rename = "_hidden", default)]
+    pub hidden: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Actors {
+    #[serde(rename = "_data")]
+    pub data: JsonExArray<Option<Actor>>,
+}
+impl Actors {
+    pub fn get(&self, id: usize) -> Option<&Actor> {
+        self.data.get(id).and_then(Option::as_ref)
+    }
+}
+
+/// Amounts are `f64` as scripts can leave fractions in them, e.g. `gainGold(x * 1.5)`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Party {
+    #[serde(rename = "_gold")]
+    pub gold: f64,
+    #[serde(rename = "_steps", default)]
+    pub steps: f64,
+    #[serde(rename = "_actors")]
+    pub actors: JsonExArray<u32>,
+    #[serde(rename = "_items", deserialize_with = "id_map")]
+    pub items: BTreeMap<u32, f64>,
+    #[serde(rename = "_weapons", deserialize_with = "id_map")]
+    pub weapons: BTreeMap<u32, f64>,
+    #[serde(rename = "_armors", deserialize_with = "id_map")]
+    pub armors: BTreeMap<u32, f64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GameMap {
+    #[serde(rename = "_mapId")]
+    pub map_id: u32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Player {
+    #[serde(rename = "_x")]
+    pub x: i64,
+    #[serde(rename = "_y")]
+    pub y: i64,
+    #[serde(rename = "_direction")]
+    pub direction: u64,
+    #[serde(rename = "_transferring", default)]
+    pub transferring: bool,
+    #[serde(rename = "_newMapId", default)]
+    pub new_map_id: u32,
+    #[serde(rename = "_newX", default)]
+    pub new_x: i64,
+    #[serde(rename = "_newY", default)]
+    pub new_y: i64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Typed view over the sections of a `fileN.rpgsave` the tools care about.
+/// Everything else is kept in `extra`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSave {
+    pub system: System,
+    pub switches: Switches,
+    pub variables: Variables,
+    pub self_switches: SelfSwitches,
+    pub actors: Actors,
+    pub party: Party,
+    pub map: GameMap,
+    pub player: Player,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+impl GameSave {
+    pub fn from_json(json: &Json) -> Result<Self, Error> {
+        Self::deserialize(json.value()).map_err(|e| Error::Save(e.to_string()))
+    }
+
+    /// Map the player will be on once loaded, taking a pending transfer into account
+    pub fn map_id(&self) -> u32 {
+        if self.player.transferring {
+            self.player.new_map_id
+        } else {
+            self.map.map_id
+        }
+    }
+
+    pub fn position(&self) -> (i64, i64) {
+        if self.player.transferring {
+            (self.player.new_x, self.player.new_y)
+        } else {
+            (self.player.x, self.player.y)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Smallest save [`GameSave`] reads, with the given party and actor 1
+    pub(crate) fn save(party: &str, actor: &str) -> Json {
+        let text = format!(
+            concat!(
+                r#"{{"system":{{"@c":1}},"switches":{{"_data":{{"@a":[]}},"@c":2}},"#,
+                r#""variables":{{"_data":{{"@a":[]}},"@c":3}},"#,
+                r#""selfSwitches":{{"_data":{{}},"@c":4}},"#,
+                r#""actors":{{"_data":{{"@a":[null,{actor}]}},"@c":5}},"#,
+                r#""party":{party},"#,
+                r#""map":{{"_mapId":3,"@c":7}},"#,
+                r#""player":{{"_x":1,"_y":2,"_direction":2,"@c":8}}}}"#,
+            ),
+            party = party,
+            actor = actor,
+        );
+        Json::from_string(&text).unwrap()
+    }
+
+    #[test]
+    fn fractional_amounts_are_read() {
+        let json = save(
+            r#"{"_gold":10.5,"_steps":3.0,"_actors":{"@a":[1]},"_items":{"1":2.5,"@c":9},"_weapons":{},"_armors":{"2":1e1},"@c":6}"#,
+            r#"{"_actorId":1,"_name":"Harold","_classId":1,"_level":5.0,"_exp":{"1":12.25},"_hp":450.5,"_mp":90,"_paramPlus":{"@a":[0,1.5]},"@c":10}"#,
+        );
+        let save = GameSave::from_json(&json).unwrap();
+
+        assert_eq!(save.party.gold, 10.5);
+        assert_eq!(save.party.steps, 3.0);
+        assert_eq!(save.party.items, BTreeMap::from([(1, 2.5)]));
+        assert_eq!(save.party.armors, BTreeMap::from([(2, 10.0)]));
+
+        let actor = save.actors.get(1).unwrap();
+        assert_eq!(actor.level, 5.0);
+        assert_eq!(actor.exp, BTreeMap::from([(1, 12.25)]));
+        assert_eq!((actor.hp, actor.mp), (450.5, 90.0));
+        assert_eq!(actor.param_plus.0, vec![0.0, 1.5]);
+    }
+
+    #[test]
+    fn integer_amounts_are_read() {
+        let json = save(
+            r#"{"_gold":1200,"_actors":[1],"_items":{"7":2},"_weapons":{},"_armors":{}}"#,
+            r#"{"_actorId":1,"_name":"Harold","_classId":1,"_level":5,"_hp":450,"_mp":90}"#,
+        );
+        let save = GameSave::from_json(&json).unwrap();
+
+        assert_eq!(save.party.gold, 1200.0);
+        assert_eq!(save.party.items, BTreeMap::from([(7, 2.0)]));
+        assert_eq!(save.map_id(), 3);
+    }
+}
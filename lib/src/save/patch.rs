@@ -0,0 +1,502 @@
+//! Editing saves by JSON Pointer (RFC 6901) and JSON Patch (RFC 6902).
+//!
+//! Paths can also be given in a small JSONPath subset (`$.party._gold`,
+//! `actors._data[1]["_name"]`), which looks through JsonEx's `{"@a": [...]}`
+//! array wrappers so paths read the same for MV and MZ saves.
+
+use super::Json;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+fn patch_error(msg: impl Into<String>) -> Error {
+    Error::Patch(msg.into())
+}
+
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Splits a JSON Pointer into unescaped reference tokens
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, Error> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(patch_error(format!("Invalid JSON Pointer: {pointer}")));
+    };
+
+    Ok(rest
+        .split('/')
+        .map(|x| x.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn to_pointer(tokens: &[String]) -> String {
+    tokens.iter().map(|x| format!("/{}", escape(x))).collect()
+}
+
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn parse_path_segments(path: &str) -> Result<Vec<Segment>, Error> {
+    let invalid = || patch_error(format!("Invalid JSON path: {path}"));
+
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = &after[..end];
+            let quoted = inner
+                .strip_prefix('"')
+                .and_then(|x| x.strip_suffix('"'))
+                .or_else(|| inner.strip_prefix('\'').and_then(|x| x.strip_suffix('\'')));
+
+            segments.push(match quoted {
+                Some(key) => Segment::Key(key.to_string()),
+                None => Segment::Index(inner.parse().map_err(|_| invalid())?),
+            });
+            rest = &after[end + 1..];
+        } else {
+            // The first key may leave out the leading dot
+            let after = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(Segment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Resolves a JSONPath against `value` into a JSON Pointer, stepping into
+/// `@a` wrappers where an index is used on an object
+pub fn path_to_pointer(value: &Value, path: &str) -> Result<String, Error> {
+    let mut tokens = vec![];
+    let mut current = Some(value);
+
+    for segment in parse_path_segments(path)? {
+        match segment {
+            Segment::Key(key) => {
+                current = current.and_then(|x| x.get(&key));
+                tokens.push(key);
+            }
+            Segment::Index(index) => {
+                if let Some(array) = current.and_then(|x| x.get("@a")) {
+                    current = Some(array);
+                    tokens.push("@a".to_string());
+                }
+                current = current.and_then(|x| x.get(index));
+                tokens.push(index.to_string());
+            }
+        }
+    }
+
+    Ok(to_pointer(&tokens))
+}
+
+/// Anything starting with `/` (or empty) is a JSON Pointer, anything else a JSONPath
+pub fn resolve(value: &Value, path: &str) -> Result<String, Error> {
+    if path.is_empty() || path.starts_with('/') {
+        parse_pointer(path)?;
+        Ok(path.to_string())
+    } else {
+        path_to_pointer(value, path)
+    }
+}
+
+fn array_index(token: &str, len: usize, allow_end: bool) -> Result<usize, Error> {
+    if allow_end && token == "-" {
+        return Ok(len);
+    }
+
+    // No leading zeros, per RFC 6901
+    let index = token
+        .parse::<usize>()
+        .ok()
+        .filter(|_| token == "0" || !token.starts_with('0'))
+        .ok_or_else(|| patch_error(format!("Invalid array index: {token}")))?;
+
+    let max = if allow_end {
+        len
+    } else {
+        len.saturating_sub(1)
+    };
+    if index > max || (!allow_end && len == 0) {
+        return Err(patch_error(format!("Array index {index} out of bounds")));
+    }
+    Ok(index)
+}
+
+/// Parent container of `pointer` and the last token
+fn parent_mut<'a>(value: &'a mut Value, pointer: &str) -> Result<(&'a mut Value, String), Error> {
+    let mut tokens = parse_pointer(pointer)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| patch_error("The root has no parent"))?;
+
+    let parent = value
+        .pointer_mut(&to_pointer(&tokens))
+        .ok_or_else(|| patch_error(format!("Parent of {pointer} doesn't exist")))?;
+    Ok((parent, last))
+}
+
+fn add(value: &mut Value, pointer: &str, new: Value) -> Result<(), Error> {
+    if pointer.is_empty() {
+        *value = new;
+        return Ok(());
+    }
+
+    let (parent, token) = parent_mut(value, pointer)?;
+    match parent {
+        Value::Object(object) => {
+            object.insert(token, new);
+        }
+        Value::Array(array) => {
+            let index = array_index(&token, array.len(), true)?;
+            array.insert(index, new);
+        }
+        _ => {
+            return Err(patch_error(format!(
+                "Parent of {pointer} isn't a container"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn remove(value: &mut Value, pointer: &str) -> Result<Value, Error> {
+    let (parent, token) = parent_mut(value, pointer)?;
+    let missing = || patch_error(format!("{pointer} doesn't exist"));
+
+    match parent {
+        Value::Object(object) => object.shift_remove(&token).ok_or_else(missing),
+        Value::Array(array) => {
+            let index = array_index(&token, array.len(), false)?;
+            Ok(array.remove(index))
+        }
+        _ => Err(missing()),
+    }
+}
+
+fn replace(value: &mut Value, pointer: &str, new: Value) -> Result<(), Error> {
+    let target = value
+        .pointer_mut(pointer)
+        .ok_or_else(|| patch_error(format!("{pointer} doesn't exist")))?;
+    *target = new;
+    Ok(())
+}
+
+fn get(value: &Value, pointer: &str) -> Result<Value, Error> {
+    value
+        .pointer(pointer)
+        .cloned()
+        .ok_or_else(|| patch_error(format!("{pointer} doesn't exist")))
+}
+
+/// JSON equality as RFC 6902's `test` defines it, where `1` and `1.0` are equal.
+/// Plain `==` compares the number text, as `arbitrary_precision` is on.
+pub(crate) fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+                a == b
+            } else if let (Some(a), Some(b)) = (a.as_u64(), b.as_u64()) {
+                a == b
+            } else {
+                a.as_f64() == b.as_f64()
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| json_equal(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+impl Operation {
+    fn apply(&self, value: &mut Value) -> Result<(), Error> {
+        match self {
+            Self::Add { path, value: new } => add(value, path, new.clone()),
+            Self::Remove { path } => remove(value, path).map(|_| ()),
+            Self::Replace { path, value: new } => replace(value, path, new.clone()),
+            Self::Move { from, path } => {
+                if path.starts_with(&format!("{from}/")) {
+                    return Err(patch_error(format!("Can't move {from} into itself")));
+                }
+                // Removing and adding back would move an object key to the end
+                if path == from {
+                    return get(value, from).map(|_| ());
+                }
+                let moved = remove(value, from)?;
+                add(value, path, moved)
+            }
+            Self::Copy { from, path } => {
+                let copied = get(value, from)?;
+                add(value, path, copied)
+            }
+            Self::Test {
+                path,
+                value: expected,
+            } => {
+                let actual = get(value, path)?;
+                if json_equal(&actual, expected) {
+                    Ok(())
+                } else {
+                    Err(patch_error(format!(
+                        "Test failed at {path}: expected {expected}, found {actual}"
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// A JSON Patch document
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct Patch(pub Vec<Operation>);
+impl FromStr for Patch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|e| patch_error(format!("Invalid JSON Patch: {e}")))
+    }
+}
+impl Patch {
+    /// Applies every operation, or none of them if one fails
+    pub fn apply(&self, save: &mut Json) -> Result<(), Error> {
+        let mut value = save.value().clone();
+        for (i, operation) in self.0.iter().enumerate() {
+            operation.apply(&mut value).map_err(|e| match e {
+                Error::Patch(msg) => patch_error(format!("Operation {i}: {msg}")),
+                e => e,
+            })?;
+        }
+        *save.value_mut() = value;
+        Ok(())
+    }
+}
+
+/// Value at `path` (JSON Pointer or JSONPath)
+pub fn get_path<'a>(save: &'a Json, path: &str) -> Result<&'a Value, Error> {
+    let pointer = resolve(save.value(), path)?;
+    save.value()
+        .pointer(&pointer)
+        .ok_or_else(|| patch_error(format!("{path} doesn't exist")))
+}
+
+/// Replaces the value at `path`, or adds it if the parent exists but the key doesn't
+pub fn set_path(save: &mut Json, path: &str, new: Value) -> Result<(), Error> {
+    let pointer = resolve(save.value(), path)?;
+    let value = save.value_mut();
+    if value.pointer(&pointer).is_some() {
+        replace(value, &pointer, new)
+    } else {
+        add(value, &pointer, new)
+    }
+}
+
+/// Removes the value at `path`, returning it
+pub fn delete_path(save: &mut Json, path: &str) -> Result<Value, Error> {
+    let pointer = resolve(save.value(), path)?;
+    remove(save.value_mut(), &pointer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save(text: &str) -> Json {
+        Json::from_string(text).unwrap()
+    }
+
+    fn patched(text: &str, patch: &str) -> Result<String, Error> {
+        let mut json = save(text);
+        patch.parse::<Patch>()?.apply(&mut json)?;
+        json.to_string()
+    }
+
+    const SAVE: &str = r#"{"party":{"_gold":10,"_items":{"1":2}},"actors":{"_data":{"@a":[null,{"_name":"Harold","_hp":12.5}]}},"a/b":{"~":1}}"#;
+
+    #[test]
+    fn test_compares_numbers_by_value() {
+        for value in ["10", "10.0", "1e1", "1.0E+1"] {
+            let patch = format!(r#"[{{"op":"test","path":"/party/_gold","value":{value}}}]"#);
+            patched(SAVE, &patch).unwrap();
+        }
+        let nested = r#"[{"op":"test","path":"/party","value":{"_items":{"1":2.0},"_gold":1e1}}]"#;
+        patched(SAVE, nested).unwrap();
+
+        for value in ["11", "10.5", r#""10""#] {
+            let patch = format!(r#"[{{"op":"test","path":"/party/_gold","value":{value}}}]"#);
+            assert!(patched(SAVE, &patch).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn move_to_the_same_path_changes_nothing() {
+        let patch = r#"[{"op":"move","from":"/party/_gold","path":"/party/_gold"}]"#;
+        assert_eq!(patched(SAVE, patch).unwrap(), SAVE);
+
+        let missing = r#"[{"op":"move","from":"/party/_steps","path":"/party/_steps"}]"#;
+        assert!(patched(SAVE, missing).is_err());
+
+        let into_itself = r#"[{"op":"move","from":"/party","path":"/party/_items"}]"#;
+        assert!(patched(SAVE, into_itself).is_err());
+    }
+
+    #[test]
+    fn dash_appends_to_arrays() {
+        let patch = r#"[
+            {"op":"add","path":"/actors/_data/@a/-","value":{"_name":"Therese"}},
+            {"op":"copy","from":"/actors/_data/@a/1","path":"/actors/_data/@a/-"}
+        ]"#;
+        let mut json = save(SAVE);
+        patch.parse::<Patch>().unwrap().apply(&mut json).unwrap();
+        assert_eq!(get_path(&json, "actors._data[2]._name").unwrap(), "Therese");
+        assert_eq!(get_path(&json, "actors._data[3]._name").unwrap(), "Harold");
+
+        // Only where something is added
+        let replace = r#"[{"op":"replace","path":"/actors/_data/@a/-","value":1}]"#;
+        assert!(patched(SAVE, replace).is_err());
+        let remove = r#"[{"op":"remove","path":"/actors/_data/@a/-"}]"#;
+        assert!(patched(SAVE, remove).is_err());
+    }
+
+    #[test]
+    fn failed_patches_change_nothing() {
+        let mut json = save(SAVE);
+        let patch = r#"[
+            {"op":"replace","path":"/party/_gold","value":99},
+            {"op":"remove","path":"/party/_steps"}
+        ]"#
+        .parse::<Patch>()
+        .unwrap();
+
+        let e = patch.apply(&mut json).unwrap_err();
+        assert!(
+            matches!(&e, Error::Patch(msg) if msg.starts_with("Operation 1: ")),
+            "{e:?}"
+        );
+        assert_eq!(json.to_string().unwrap(), SAVE);
+    }
+
+    #[test]
+    fn invalid_operations_are_errors() {
+        for patch in [
+            r#"[{"op":"add","path":"party","value":1}]"#,
+            r#"[{"op":"add","path":"/nothing/here","value":1}]"#,
+            r#"[{"op":"add","path":"/party/_gold/x","value":1}]"#,
+            r#"[{"op":"add","path":"/actors/_data/@a/3","value":1}]"#,
+            r#"[{"op":"add","path":"/actors/_data/@a/01","value":1}]"#,
+            r#"[{"op":"remove","path":"/actors/_data/@a/2"}]"#,
+            r#"[{"op":"remove","path":""}]"#,
+            r#"[{"op":"copy","from":"/party/_steps","path":"/x"}]"#,
+            r#"[{"op":"test","path":"/party/_steps","value":null}]"#,
+        ] {
+            assert!(
+                matches!(patched(SAVE, patch), Err(Error::Patch(_))),
+                "{patch}"
+            );
+        }
+
+        for patch in [r#"{"op":"add"}"#, r#"[{"op":"rename","path":"/x"}]"#] {
+            assert!(patch.parse::<Patch>().is_err(), "{patch}");
+        }
+    }
+
+    #[test]
+    fn paths_step_into_array_wrappers() {
+        let json = save(SAVE);
+        for path in [
+            "actors._data[1]._name",
+            "$.actors._data[1]._name",
+            r#"actors._data[1]["_name"]"#,
+            "$['actors']['_data'][1]['_name']",
+            "/actors/_data/@a/1/_name",
+        ] {
+            assert_eq!(get_path(&json, path).unwrap(), "Harold", "{path}");
+        }
+        assert_eq!(
+            resolve(json.value(), "actors._data[1]._hp").unwrap(),
+            "/actors/_data/@a/1/_hp"
+        );
+
+        // Keys holding `/` and `~` are escaped in the pointer
+        assert_eq!(
+            resolve(json.value(), r#"$["a/b"]["~"]"#).unwrap(),
+            "/a~1b/~0"
+        );
+        assert_eq!(get_path(&json, "/a~1b/~0").unwrap(), 1);
+
+        // Plain arrays aren't wrapped
+        let plain = save(r#"{"list":[[1,2],[3,4]]}"#);
+        assert_eq!(resolve(plain.value(), "list[1][0]").unwrap(), "/list/1/0");
+    }
+
+    #[test]
+    fn invalid_paths_are_errors() {
+        let json = save(SAVE);
+        for path in [
+            "party..x",
+            "party[",
+            "party[x]",
+            "party[-1]",
+            "party.",
+            "$.",
+            "party._steps",
+        ] {
+            assert!(
+                matches!(get_path(&json, path), Err(Error::Patch(_))),
+                "{path}"
+            );
+        }
+        assert!(parse_pointer("party").is_err());
+    }
+
+    #[test]
+    fn set_path_replaces_or_adds() {
+        let mut json = save(SAVE);
+        set_path(&mut json, "party._gold", Value::from(5)).unwrap();
+        set_path(&mut json, "party._steps", Value::from(7)).unwrap();
+        set_path(&mut json, "actors._data[2]", Value::Null).unwrap();
+        assert_eq!(get_path(&json, "party._gold").unwrap(), 5);
+        assert_eq!(get_path(&json, "/party/_steps").unwrap(), 7);
+        assert_eq!(
+            get_path(&json, "actors._data").unwrap()["@a"]
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
+
+        assert!(set_path(&mut json, "party._nothing._here", Value::Null).is_err());
+        assert!(set_path(&mut json, "actors._data[5]", Value::Null).is_err());
+
+        assert_eq!(delete_path(&mut json, "party._steps").unwrap(), 7);
+        assert!(delete_path(&mut json, "party._steps").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use super::{
+    Format, Json,
+    global::{DateTime, Global, GlobalEntry, MV_GLOBAL_ID, SpriteInfo},
+    model::GameSave,
+};
+use crate::{
+    data::{DataSource, system::System},
+    error::Error,
+};
+use std::path::{Path, PathBuf};
+
+/// `Game_Party.maxBattleMembers`
+const MAX_BATTLE_MEMBERS: usize = 4;
+
+/// `Game_System.playtimeText`
+pub fn playtime_text(frames: u64) -> String {
+    let seconds = frames / 60;
+    let hour = seconds / 3600;
+    let min = (seconds / 60) % 60;
+    let sec = seconds % 60;
+    format!("{hour:02}:{min:02}:{sec:02}")
+}
+
+/// Builds the entry `DataManager.makeSavefileInfo` would have written for `save`
+pub fn entry_from_save(
+    save: &GameSave,
+    format: Format,
+    title: &str,
+    timestamp: DateTime,
+) -> GlobalEntry {
+    // Game_Party.battleMembers
+    let members = save
+        .party
+        .actors
+        .iter()
+        .filter_map(|id| save.actors.get(*id as usize))
+        .take(MAX_BATTLE_MEMBERS)
+        .filter(|actor| !actor.hidden)
+        .collect::<Vec<_>>();
+
+    let characters = members
+        .iter()
+        .map(|actor| SpriteInfo {
+            file_name: actor.character_name.clone(),
+            sprite_index: actor.character_index,
+        })
+        .collect();
+
+    let faces = members
+        .iter()
+        .map(|actor| SpriteInfo {
+            file_name: actor.face_name.clone(),
+            sprite_index: actor.face_index,
+        })
+        .collect();
+
+    GlobalEntry {
+        global_id: (format == Format::Mv).then(|| MV_GLOBAL_ID.to_string()),
+        title: title.to_string(),
+        characters,
+        faces,
+        playtime: playtime_text(save.system.frames_on_save.unwrap_or(0)),
+        timestamp,
+        version: None,
+        extra: Default::default(),
+    }
+}
+
+/// `gameTitle` from `System.json`
+pub fn game_title(source: &DataSource) -> Result<String, Error> {
+    source.read::<System>("System.json").map(|x| x.game_title)
+}
+
+fn read_entry(path: &Path, format: Format, title: &str) -> Result<GlobalEntry, Error> {
+    let save = GameSave::from_json(&Json::read_file(path)?)?;
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(entry_from_save(&save, format, title, DateTime(modified.into())))
+}
+
+#[derive(Debug)]
+pub struct Rebuilt {
+    pub global: Global,
+    /// Save files that couldn't be read, and are left out of `global`
+    pub errors: Vec<(PathBuf, Error)>,
+}
+
+/// Rebuilds the global index from the save files in `save_dir`, using each
+/// file's modification time as its timestamp
+pub fn rebuild_global(
+    save_dir: &Path,
+    source: &DataSource,
+    format: Format,
+) -> Result<Rebuilt, Error> {
+    let title = game_title(source)?;
+
+    let mut global = Global(vec![None]);
+    let mut errors = vec![];
+
+    for (file_id, path) in format.save_files(save_dir)? {
+        match read_entry(&path, format, &title) {
+            Ok(entry) => global.set(file_id, Some(entry)),
+            Err(e) => errors.push((path, e)),
+        }
+    }
+
+    Ok(Rebuilt { global, errors })
+}
+
+/// [`rebuild_global`] and write the result to `global.rpgsave` / `global.rmmzsave`
+pub fn regenerate_global(
+    save_dir: &Path,
+    source: &DataSource,
+    format: Format,
+) -> Result<Rebuilt, Error> {
+    let rebuilt = rebuild_global(save_dir, source, format)?;
+    rebuilt.global.write_file(&format.global_path(save_dir))?;
+    Ok(rebuilt)
+}
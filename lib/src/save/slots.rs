@@ -0,0 +1,170 @@
+//! Copying, moving, swapping and deleting save files while keeping the
+//! global index in sync. Both the save files and the index are replaced by
+//! renaming, so an interrupted operation never leaves a half written file.
+//! Every operation takes a single backup snapshot of the files it touches,
+//! and puts them back from it if a later step fails, so the files and the
+//! index don't end up out of sync.
+
+use super::{Format, backup, global::Global};
+use crate::{error::Error, util::write_atomic};
+use std::path::{Path, PathBuf};
+
+/// `DataManager.maxSavefiles`
+pub const MAX_SAVE_FILES: usize = 20;
+
+fn read_global(save_dir: &Path, format: Format) -> Result<Global, Error> {
+    let path = format.global_path(save_dir);
+    if path.exists() {
+        Global::read_file(&path)
+    } else {
+        Ok(Global(vec![None]))
+    }
+}
+
+fn write_global(save_dir: &Path, format: Format, global: &Global) -> Result<(), Error> {
+    global
+        .to_json()?
+        .write_file_without_backup(&format.global_path(save_dir))
+}
+
+/// Snapshots the saves `file_ids` and the index, then runs `operation`,
+/// rolling back to the snapshot if it fails
+fn with_backup(
+    save_dir: &Path,
+    format: Format,
+    file_ids: &[usize],
+    operation: impl FnOnce() -> Result<(), Error>,
+) -> Result<(), Error> {
+    let files = file_ids
+        .iter()
+        .map(|id| format.file_path(save_dir, *id))
+        .chain([format.global_path(save_dir)])
+        .collect::<Vec<_>>();
+    let snapshot = backup::snapshot(save_dir, &files)?;
+
+    operation().inspect_err(|_| {
+        if let Some(snapshot) = &snapshot {
+            let _ = backup::roll_back(save_dir, snapshot);
+        }
+    })
+}
+
+fn check_file_id(file_id: usize) -> Result<(), Error> {
+    if file_id == 0 {
+        return Err(Error::Save("Save file ids start at 1".into()));
+    }
+    Ok(())
+}
+
+fn existing_file(save_dir: &Path, format: Format, file_id: usize) -> Result<PathBuf, Error> {
+    check_file_id(file_id)?;
+    let path = format.file_path(save_dir, file_id);
+    if !path.exists() {
+        return Err(Error::Save(format!("Save file {file_id} doesn't exist")));
+    }
+    Ok(path)
+}
+
+fn empty_file(save_dir: &Path, format: Format, file_id: usize) -> Result<PathBuf, Error> {
+    check_file_id(file_id)?;
+    let path = format.file_path(save_dir, file_id);
+    if path.exists() {
+        return Err(Error::Save(format!("Save file {file_id} already exists")));
+    }
+    Ok(path)
+}
+
+/// Lowest file id with neither a save file nor an index entry
+pub fn first_free_slot(save_dir: &Path, format: Format) -> Result<Option<usize>, Error> {
+    let global = read_global(save_dir, format)?;
+    let slot = (1..=MAX_SAVE_FILES.max(global.0.len()))
+        .find(|id| global.get(*id).is_none() && !format.file_path(save_dir, *id).exists());
+    Ok(slot)
+}
+
+/// Copies save `from` into the empty slot `to`
+pub fn copy(save_dir: &Path, format: Format, from: usize, to: usize) -> Result<(), Error> {
+    let from_path = existing_file(save_dir, format, from)?;
+    let to_path = empty_file(save_dir, format, to)?;
+    let mut global = read_global(save_dir, format)?;
+
+    with_backup(save_dir, format, &[to], || {
+        write_atomic(&to_path, &std::fs::read(&from_path)?)?;
+
+        global.set(to, global.get(from).cloned());
+        write_global(save_dir, format, &global)
+    })
+}
+
+/// Moves save `from` into the empty slot `to`
+pub fn move_to(save_dir: &Path, format: Format, from: usize, to: usize) -> Result<(), Error> {
+    let from_path = existing_file(save_dir, format, from)?;
+    let to_path = empty_file(save_dir, format, to)?;
+    let mut global = read_global(save_dir, format)?;
+
+    with_backup(save_dir, format, &[from, to], || {
+        std::fs::rename(&from_path, &to_path)?;
+
+        let entry = global.get(from).cloned();
+        global.set(from, None);
+        global.set(to, entry);
+        write_global(save_dir, format, &global)
+    })
+}
+
+/// Exchanges saves `a` and `b`. Either slot may be empty.
+pub fn swap(save_dir: &Path, format: Format, a: usize, b: usize) -> Result<(), Error> {
+    check_file_id(a)?;
+    check_file_id(b)?;
+    if a == b {
+        return Ok(());
+    }
+
+    let mut global = read_global(save_dir, format)?;
+
+    let a_path = format.file_path(save_dir, a);
+    let b_path = format.file_path(save_dir, b);
+
+    let mut temp = a_path.as_os_str().to_owned();
+    temp.push(".swap");
+    let temp = PathBuf::from(temp);
+
+    let rename_if_exists = |from: &Path, to: &Path| -> Result<(), Error> {
+        if from.exists() {
+            std::fs::rename(from, to)?;
+        }
+        Ok(())
+    };
+
+    with_backup(save_dir, format, &[a, b], || {
+        rename_if_exists(&a_path, &temp)?;
+        rename_if_exists(&b_path, &a_path)?;
+        rename_if_exists(&temp, &b_path)?;
+
+        let a_entry = global.get(a).cloned();
+        let b_entry = global.get(b).cloned();
+        global.set(a, b_entry);
+        global.set(b, a_entry);
+        write_global(save_dir, format, &global)
+    })
+    .inspect_err(|_| {
+        // The snapshot puts both saves back, only the temporary file is left over
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Removes save `file_id` and its index entry
+pub fn delete(save_dir: &Path, format: Format, file_id: usize) -> Result<(), Error> {
+    check_file_id(file_id)?;
+    let mut global = read_global(save_dir, format)?;
+
+    with_backup(save_dir, format, &[file_id], || {
+        let path = format.file_path(save_dir, file_id);
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+
+        global.set(file_id, None);
+        write_global(save_dir, format, &global)
+    })
+}
@@ -0,0 +1,88 @@
+//! Switches and variables of a save, labelled with their names from
+//! `System.json`.
+
+use super::model::GameSave;
+use crate::data::system::System;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    Switch,
+    Variable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NamedValue {
+    pub kind: Kind,
+    pub id: u32,
+    /// Empty if the editor never named it
+    pub name: String,
+    /// A bool for switches. Variables are usually numbers, but scripts can
+    /// store anything in them.
+    pub value: Value,
+}
+impl NamedValue {
+    /// Whether it still has the value a new game starts with
+    pub fn is_default(&self) -> bool {
+        match self.kind {
+            Kind::Switch => self.value == Value::Bool(false),
+            Kind::Variable => self.value.as_f64() == Some(0.0),
+        }
+    }
+}
+
+fn name(names: &[String], id: usize) -> String {
+    names.get(id).cloned().unwrap_or_default()
+}
+
+/// Every switch named in `system` or set in `save`, starting from id 1
+pub fn switches(save: &GameSave, system: &System) -> Vec<NamedValue> {
+    let count = system.switches.len().max(save.switches.data.len());
+    (1..count)
+        .map(|id| NamedValue {
+            kind: Kind::Switch,
+            id: id as u32,
+            name: name(&system.switches, id),
+            value: Value::Bool(save.switches.get(id)),
+        })
+        .collect()
+}
+
+/// Every variable named in `system` or set in `save`, starting from id 1
+pub fn variables(save: &GameSave, system: &System) -> Vec<NamedValue> {
+    let count = system.variables.len().max(save.variables.data.len());
+    (1..count)
+        .map(|id| NamedValue {
+            kind: Kind::Variable,
+            id: id as u32,
+            name: name(&system.variables, id),
+            value: save.variables.get(id),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// Skip values a new game starts with
+    pub non_default_only: bool,
+    /// Case insensitive text in the name, or an exact id
+    pub search: String,
+}
+impl Filter {
+    pub fn matches(&self, value: &NamedValue) -> bool {
+        if self.non_default_only && value.is_default() {
+            return false;
+        }
+
+        let search = self.search.trim();
+        search.is_empty()
+            || value.id.to_string() == search
+            || value.name.to_lowercase().contains(&search.to_lowercase())
+    }
+
+    pub fn apply<'a>(&'a self, values: &'a [NamedValue]) -> impl Iterator<Item = &'a NamedValue> {
+        values.iter().filter(|x| self.matches(x))
+    }
+}
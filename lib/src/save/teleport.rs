@@ -0,0 +1,167 @@
+use super::Json;
+use crate::{
+    data::{Table, database::Tileset, map::Map as GameMap},
+    error::Error,
+};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
+    Down = 2,
+    Left = 4,
+    Right = 6,
+    Up = 8,
+}
+impl Direction {
+    pub const fn from_code(code: u64) -> Option<Self> {
+        match code {
+            2 => Some(Self::Down),
+            4 => Some(Self::Left),
+            6 => Some(Self::Right),
+            8 => Some(Self::Up),
+            _ => None,
+        }
+    }
+
+    pub const fn code(self) -> u64 {
+        self as u64
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Location {
+    pub map_id: u32,
+    pub x: u32,
+    pub y: u32,
+    pub direction: Direction,
+}
+
+fn object_mut<'a>(save: &'a mut Json, pointer: &str) -> Result<&'a mut Map<String, Value>, Error> {
+    save.value_mut()
+        .pointer_mut(pointer)
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| Error::Save(format!("Missing object at {pointer}")))
+}
+
+/// Moves the player to `target`.
+///
+/// The move is stored as a pending transfer, the same way the Transfer Player
+/// command leaves it, so on load the game sets up the new map itself instead of
+/// keeping the old map's events around.
+pub fn teleport(save: &mut Json, target: &Location) -> Result<(), Error> {
+    let player = object_mut(save, "/player")?;
+
+    let map_id = Value::from(target.map_id);
+    let x = Value::from(target.x);
+    let y = Value::from(target.y);
+    let direction = Value::from(target.direction.code());
+
+    for (key, value) in [
+        ("_x", &x),
+        ("_y", &y),
+        ("_realX", &x),
+        ("_realY", &y),
+        ("_direction", &direction),
+        ("_newMapId", &map_id),
+        ("_newX", &x),
+        ("_newY", &y),
+        ("_newDirection", &direction),
+        ("_fadeType", &Value::from(0)),
+        ("_transferring", &Value::Bool(true)),
+        ("_vehicleType", &Value::from("walk")),
+        ("_vehicleGettingOn", &Value::Bool(false)),
+        ("_vehicleGettingOff", &Value::Bool(false)),
+    ] {
+        player.insert(key.into(), value.clone());
+    }
+
+    let map = object_mut(save, "/map")?;
+
+    // A running event is the usual cause of a softlock, so drop it the same way
+    // Game_Interpreter.clear does
+    if let Some(interpreter) = map.get_mut("_interpreter").and_then(Value::as_object_mut) {
+        for (key, value) in [
+            ("_mapId", Value::from(0)),
+            ("_eventId", Value::from(0)),
+            ("_list", Value::Null),
+            ("_index", Value::from(0)),
+            ("_waitCount", Value::from(0)),
+            ("_waitMode", Value::from("")),
+            ("_comments", Value::from("")),
+            ("_character", Value::Null),
+            ("_childInterpreter", Value::Null),
+        ] {
+            interpreter.insert(key.into(), value);
+        }
+    }
+
+    // Vehicles are arrays of objects, optionally wrapped as { "@a": [...] } by JsonEx
+    let vehicles = map.get_mut("_vehicles").and_then(|v| match v {
+        Value::Object(o) => o.get_mut("@a").and_then(Value::as_array_mut),
+        Value::Array(a) => Some(a),
+        _ => None,
+    });
+    for vehicle in vehicles.into_iter().flatten() {
+        if let Some(vehicle) = vehicle.as_object_mut() {
+            vehicle.insert("_driving".into(), Value::Bool(false));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that `target` is inside the map and that the tile can be stood on,
+/// meaning `Game_Map.checkPassage` lets it be entered or left in at least one
+/// direction
+pub fn validate_location(data_dir: &Path, target: &Location) -> Result<(), Error> {
+    let map = GameMap::load(data_dir, target.map_id)?;
+
+    let (width, height) = (map.width as u64, map.height as u64);
+    let (x, y) = (target.x as u64, target.y as u64);
+
+    if x >= width || y >= height {
+        let msg = format!(
+            "Position ({x}, {y}) is outside of map {} ({width}x{height})",
+            target.map_id
+        );
+        return Err(Error::Data(msg));
+    }
+
+    let tileset_id = map.tileset_id;
+    let tilesets = Table::<Tileset>::read_file(&data_dir.join("Tilesets.json"))?;
+    let flags = &tilesets
+        .get(tileset_id as usize)
+        .ok_or_else(|| Error::Data(format!("Missing tileset {tileset_id}")))?
+        .flags;
+
+    let layer_flags = (0..4)
+        .rev()
+        .map(|z| {
+            let index = ((z * height + y) * width + x) as usize;
+            let tile_id = map.data.get(index).copied().unwrap_or(0) as usize;
+            flags.get(tile_id).copied().unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    // Down, left, right and up, the tile can be stood on if any is open
+    let is_passable = |bit: u32| {
+        layer_flags
+            .iter()
+            // [*] tiles don't affect passage
+            .find(|&&flag| flag & 0x10 == 0)
+            .is_some_and(|flag| flag & bit == 0)
+    };
+    if [0x1, 0x2, 0x4, 0x8].into_iter().any(is_passable) {
+        return Ok(());
+    }
+
+    let msg = format!("Tile ({x}, {y}) on map {} is impassable", target.map_id);
+    Err(Error::Data(msg))
+}
+
+/// [`teleport`] after checking the target with [`validate_location`]
+pub fn teleport_checked(save: &mut Json, target: &Location, data_dir: &Path) -> Result<(), Error> {
+    validate_location(data_dir, target)?;
+    teleport(save, target)
+}
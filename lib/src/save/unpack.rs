@@ -0,0 +1,113 @@
+//! Save files as pretty printed `.json`, for keeping them in version control.
+//!
+//! Keys stay in the order the engine wrote them and numbers keep their
+//! original text, so packing an unedited file gives back the exact save it
+//! was unpacked from.
+
+use super::{Format, Json, backup};
+use crate::{error::Error, util::write_atomic};
+use std::path::{Path, PathBuf};
+
+/// `global`, `config` or `fileN`
+fn is_save_stem(stem: &str) -> bool {
+    match stem {
+        "global" | "config" => true,
+        _ => stem
+            .strip_prefix("file")
+            .is_some_and(|id| id.parse::<usize>().is_ok()),
+    }
+}
+
+fn stem(path: &Path) -> Option<&str> {
+    path.file_stem()?.to_str()
+}
+
+/// Save files of `format` in `save_dir`: global, config, then every fileN
+fn save_paths(save_dir: &Path, format: Format) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = [format.global_path(save_dir), format.config_path(save_dir)]
+        .into_iter()
+        .filter(|x| x.exists())
+        .collect::<Vec<_>>();
+    paths.extend(format.save_files(save_dir)?.into_iter().map(|(_, x)| x));
+    Ok(paths)
+}
+
+/// Writes every save in `save_dir` (MV or MZ) to `<name>.json` in `out_dir`.
+/// Fails without writing anything if the directory has the same save in
+/// both formats, since they'd be unpacked to the same file.
+pub fn unpack_dir(save_dir: &Path, out_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut paths: Vec<(PathBuf, PathBuf)> = vec![];
+    for format in [Format::Mv, Format::Mz] {
+        for path in save_paths(save_dir, format)? {
+            let stem = stem(&path).unwrap_or_default();
+            let out = out_dir.join(format!("{stem}.json"));
+
+            if let Some((other, _)) = paths.iter().find(|(_, x)| *x == out) {
+                let msg = format!(
+                    "{} and {} would both be unpacked to {}",
+                    other.display(),
+                    path.display(),
+                    out.display()
+                );
+                return Err(Error::Save(msg));
+            }
+            paths.push((path, out));
+        }
+    }
+
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut written = vec![];
+    for (path, out) in paths {
+        let json = Json::read_file(&path)?;
+        let mut text = json.to_string_pretty()?;
+        text.push('\n');
+        write_atomic(&out, text.as_bytes())?;
+
+        written.push(out);
+    }
+
+    Ok(written)
+}
+
+/// Compresses every `global.json`, `config.json` and `fileN.json` in
+/// `json_dir` into save files of `format` in `save_dir`. The saves being
+/// replaced are snapshotted first.
+pub fn pack_dir(json_dir: &Path, save_dir: &Path, format: Format) -> Result<Vec<PathBuf>, Error> {
+    let mut files = std::fs::read_dir(json_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|x| x == "json") && stem(path).is_some_and(is_save_stem)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let saves = files
+        .iter()
+        .map(|path| {
+            let text = std::fs::read_to_string(path)?;
+            let json = Json::from_string(&text)
+                .map_err(|e| Error::Save(format!("{}: {e}", path.display())))?;
+            let out = save_dir.join(format!(
+                "{}.{}",
+                stem(path).unwrap_or_default(),
+                format.extension()
+            ));
+            Ok((out, json))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    std::fs::create_dir_all(save_dir)?;
+    let paths = saves
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    backup::snapshot(save_dir, &paths)?;
+
+    for (path, json) in &saves {
+        json.write_file_without_backup(path)?;
+    }
+
+    Ok(paths)
+}
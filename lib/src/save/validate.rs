@@ -0,0 +1,427 @@
+//! Checks a decoded save against the game's database, for saves broken by a
+//! game update removing things they still reference.
+
+use super::Json;
+use crate::data::{DataSource, Database, map::Map};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataKind {
+    Actor,
+    Class,
+    Skill,
+    Item,
+    Weapon,
+    Armor,
+    State,
+    Map,
+}
+impl DataKind {
+    /// Whether `db` has an entry `id` of this kind
+    pub fn exists(self, db: &Database, id: u64) -> bool {
+        let id = id as usize;
+        id != 0
+            && match self {
+                Self::Actor => db.actors.contains(id),
+                Self::Class => db.classes.contains(id),
+                Self::Skill => db.skills.contains(id),
+                Self::Item => db.items.contains(id),
+                Self::Weapon => db.weapons.contains(id),
+                Self::Armor => db.armors.contains(id),
+                Self::State => db.states.contains(id),
+                Self::Map => db.map_infos.contains(id),
+            }
+    }
+}
+
+/// How an issue can be fixed by removing the reference
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Fix {
+    RemoveKey {
+        object: String,
+        key: String,
+    },
+    RemoveElement {
+        array: String,
+        index: usize,
+    },
+    /// Empties a `Game_Item` (an equipment slot)
+    ClearItem {
+        item: String,
+    },
+    /// For entries indexed by id, where removing would shift the others
+    SetNull {
+        value: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Problem {
+    Missing {
+        data: DataKind,
+        id: u64,
+    },
+    OutOfBounds {
+        map_id: u64,
+        x: i64,
+        y: i64,
+        width: u64,
+        height: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    /// JSON Pointer to the offending value
+    pub path: String,
+    pub problem: Problem,
+    pub fix: Option<Fix>,
+}
+impl Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.problem {
+            Problem::Missing { data, id } => {
+                write!(f, "{}: {data:?} {id} doesn't exist", self.path)
+            }
+            Problem::OutOfBounds {
+                map_id,
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "{}: ({x}, {y}) is outside of map {map_id} ({width}x{height})",
+                self.path
+            ),
+        }
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Array at `pointer`, looking through JsonEx's `{ "@a": [...] }` wrapper.
+/// Returns the pointer of the array itself.
+fn array_at<'a>(save: &'a Value, pointer: &str) -> Option<(String, &'a Vec<Value>)> {
+    match save.pointer(pointer)? {
+        Value::Array(array) => Some((pointer.to_string(), array)),
+        Value::Object(object) => {
+            let array = object.get("@a")?.as_array()?;
+            Some((format!("{pointer}/@a"), array))
+        }
+        _ => None,
+    }
+}
+
+struct Validator<'a> {
+    db: &'a Database,
+    source: &'a DataSource,
+    issues: Vec<Issue>,
+}
+impl Validator<'_> {
+    fn check(&mut self, kind: DataKind, id: Option<u64>, path: String, fix: Option<Fix>) {
+        let Some(id) = id else {
+            return;
+        };
+        if !kind.exists(self.db, id) {
+            self.issues.push(Issue {
+                path,
+                problem: Problem::Missing { data: kind, id },
+                fix,
+            });
+        }
+    }
+
+    fn check_id_keys(&mut self, save: &Value, pointer: &str, kind: DataKind) {
+        let Some(object) = save.pointer(pointer).and_then(Value::as_object) else {
+            return;
+        };
+
+        for key in object.keys().filter(|x| !x.starts_with('@')) {
+            let fix = Fix::RemoveKey {
+                object: pointer.to_string(),
+                key: key.clone(),
+            };
+            let path = format!("{pointer}/{}", escape(key));
+            self.check(kind, key.parse().ok(), path, Some(fix));
+        }
+    }
+
+    fn check_id_array(&mut self, save: &Value, pointer: &str, kind: DataKind) {
+        let Some((pointer, array)) = array_at(save, pointer) else {
+            return;
+        };
+
+        for (index, value) in array.iter().enumerate() {
+            let fix = Fix::RemoveElement {
+                array: pointer.clone(),
+                index,
+            };
+            self.check(
+                kind,
+                value.as_u64(),
+                format!("{pointer}/{index}"),
+                Some(fix),
+            );
+        }
+    }
+
+    fn check_actor(&mut self, save: &Value, pointer: &str) {
+        let actor = save.pointer(pointer);
+        let field = |key: &str| actor.and_then(|x| x.get(key)).and_then(Value::as_u64);
+
+        let fix = Fix::SetNull {
+            value: pointer.to_string(),
+        };
+        self.check(
+            DataKind::Actor,
+            field("_actorId"),
+            format!("{pointer}/_actorId"),
+            Some(fix),
+        );
+        self.check(
+            DataKind::Class,
+            field("_classId"),
+            format!("{pointer}/_classId"),
+            None,
+        );
+        self.check_id_array(save, &format!("{pointer}/_skills"), DataKind::Skill);
+        self.check_id_array(save, &format!("{pointer}/_states"), DataKind::State);
+
+        let Some((equips_pointer, equips)) = array_at(save, &format!("{pointer}/_equips")) else {
+            return;
+        };
+        for (index, item) in equips.iter().enumerate() {
+            let kind = match item.get("_dataClass").and_then(Value::as_str) {
+                Some("weapon") => DataKind::Weapon,
+                Some("armor") => DataKind::Armor,
+                _ => continue,
+            };
+            let item_pointer = format!("{equips_pointer}/{index}");
+            let id = item.get("_itemId").and_then(Value::as_u64);
+            let fix = Fix::ClearItem {
+                item: item_pointer.clone(),
+            };
+            self.check(kind, id, format!("{item_pointer}/_itemId"), Some(fix));
+        }
+    }
+
+    fn check_position(&mut self, save: &Value) {
+        let player = |key: &str| save.pointer(&format!("/player/{key}"));
+
+        let transferring = player("_transferring").and_then(Value::as_bool) == Some(true);
+        let (map_pointer, x_key, y_key) = if transferring {
+            ("/player/_newMapId", "_newX", "_newY")
+        } else {
+            ("/map/_mapId", "_x", "_y")
+        };
+
+        let Some(map_id) = save.pointer(map_pointer).and_then(Value::as_u64) else {
+            return;
+        };
+        if !DataKind::Map.exists(self.db, map_id) {
+            self.issues.push(Issue {
+                path: map_pointer.to_string(),
+                problem: Problem::Missing {
+                    data: DataKind::Map,
+                    id: map_id,
+                },
+                fix: None,
+            });
+            return;
+        }
+
+        let x = player(x_key).and_then(Value::as_i64).unwrap_or(0);
+        let y = player(y_key).and_then(Value::as_i64).unwrap_or(0);
+        let Ok(map) = self.source.read::<Map>(&Map::file_name(map_id as u32)) else {
+            return;
+        };
+        let (width, height) = (map.width as u64, map.height as u64);
+
+        if x < 0 || y < 0 || x as u64 >= width || y as u64 >= height {
+            self.issues.push(Issue {
+                path: format!("/player/{x_key}"),
+                problem: Problem::OutOfBounds {
+                    map_id,
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+                fix: None,
+            });
+        }
+    }
+}
+
+/// Lists every reference in `save` that `db` doesn't have. Map sizes are read
+/// from the maps in `source`.
+pub fn validate(save: &Json, db: &Database, source: &DataSource) -> Vec<Issue> {
+    let save = save.value();
+    let mut validator = Validator {
+        db,
+        source,
+        issues: vec![],
+    };
+
+    validator.check_id_keys(save, "/party/_items", DataKind::Item);
+    validator.check_id_keys(save, "/party/_weapons", DataKind::Weapon);
+    validator.check_id_keys(save, "/party/_armors", DataKind::Armor);
+    validator.check_id_array(save, "/party/_actors", DataKind::Actor);
+
+    if let Some((pointer, actors)) = array_at(save, "/actors/_data") {
+        for (index, actor) in actors.iter().enumerate() {
+            if !actor.is_null() {
+                validator.check_actor(save, &format!("{pointer}/{index}"));
+            }
+        }
+    }
+
+    validator.check_position(save);
+
+    validator.issues
+}
+
+/// Applies the fixes of `issues`, stripping the invalid references. Returns how
+/// many issues were fixed; the rest need fixing by hand (e.g. with teleport).
+pub fn fix(save: &mut Json, issues: &[Issue]) -> usize {
+    let mut fixes: Vec<Fix> = vec![];
+    for fix in issues.iter().filter_map(|x| x.fix.as_ref()) {
+        if !fixes.contains(fix) {
+            fixes.push(fix.clone());
+        }
+    }
+
+    // Deepest first, so nulling an actor doesn't come before the fixes inside
+    // it, and array elements back to front so earlier indices stay valid
+    fixes.sort_by_cached_key(|fix| {
+        let (pointer, index) = match fix {
+            Fix::RemoveKey { object, .. } => (object, 0),
+            Fix::RemoveElement { array, index } => (array, *index),
+            Fix::ClearItem { item } => (item, 0),
+            Fix::SetNull { value } => (value, 0),
+        };
+        let depth = pointer.matches('/').count();
+        (
+            std::cmp::Reverse(depth),
+            pointer.clone(),
+            std::cmp::Reverse(index),
+        )
+    });
+
+    let save = save.value_mut();
+    let mut applied_fixes = vec![];
+
+    for fix in &fixes {
+        let applied = match fix {
+            Fix::RemoveKey { object, key } => save
+                .pointer_mut(object)
+                .and_then(Value::as_object_mut)
+                .and_then(|x| x.shift_remove(key))
+                .is_some(),
+            Fix::RemoveElement { array, index } => save
+                .pointer_mut(array)
+                .and_then(Value::as_array_mut)
+                .filter(|x| *index < x.len())
+                .map(|x| x.remove(*index))
+                .is_some(),
+            Fix::ClearItem { item } => save
+                .pointer_mut(item)
+                .and_then(Value::as_object_mut)
+                .map(|x| {
+                    x.insert("_dataClass".into(), Value::from(""));
+                    x.insert("_itemId".into(), Value::from(0));
+                })
+                .is_some(),
+            Fix::SetNull { value } => save
+                .pointer_mut(value)
+                .map(|x| *x = Value::Null)
+                .is_some(),
+        };
+
+        if applied {
+            applied_fixes.push(fix);
+        }
+    }
+
+    // Issues sharing a fix were counted once above
+    issues
+        .iter()
+        .filter(|x| {
+            x.fix
+                .as_ref()
+                .is_some_and(|fix| applied_fixes.contains(&fix))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(path: &str, data: DataKind, id: u64, fix: Option<Fix>) -> Issue {
+        Issue {
+            path: path.into(),
+            problem: Problem::Missing { data, id },
+            fix,
+        }
+    }
+
+    #[test]
+    fn fix_counts_issues_not_fixes() {
+        let mut save = Json::from_string(
+            r#"{"party":{"_items":{"1":2,"9":1},"_actors":[1,7]},"actors":{"_data":[null,{"_actorId":7,"_classId":9}]}}"#,
+        )
+        .unwrap();
+        let remove_actor = Fix::SetNull {
+            value: "/actors/_data/1".into(),
+        };
+        let issues = [
+            issue(
+                "/party/_items/9",
+                DataKind::Item,
+                9,
+                Some(Fix::RemoveKey {
+                    object: "/party/_items".into(),
+                    key: "9".into(),
+                }),
+            ),
+            issue(
+                "/actors/_data/1/_actorId",
+                DataKind::Actor,
+                7,
+                Some(remove_actor.clone()),
+            ),
+            issue(
+                "/actors/_data/1/_actorId",
+                DataKind::Actor,
+                7,
+                Some(remove_actor),
+            ),
+            issue("/actors/_data/1/_classId", DataKind::Class, 9, None),
+            // Already gone
+            issue(
+                "/party/_actors/5",
+                DataKind::Actor,
+                7,
+                Some(Fix::RemoveElement {
+                    array: "/party/_actors".into(),
+                    index: 5,
+                }),
+            ),
+        ];
+
+        assert_eq!(fix(&mut save, &issues), 3);
+        assert_eq!(
+            save.to_string().unwrap(),
+            r#"{"party":{"_items":{"1":2},"_actors":[1,7]},"actors":{"_data":[null,null]}}"#
+        );
+    }
+}
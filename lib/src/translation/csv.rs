@@ -0,0 +1,188 @@
+//! CSV files with `context`, `source` and `translation` columns, for
+//! translating in a spreadsheet
+
+use super::{Entry, Translation};
+use crate::error::Error;
+
+const HEADER: [&str; 3] = ["context", "source", "translation"];
+
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Writes the entries with empty translations
+pub fn write(entries: &[Entry]) -> String {
+    let mut csv = HEADER.join(",");
+    csv.push_str("\r\n");
+
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},\r\n",
+            field(&entry.context),
+            field(&entry.text)
+        ));
+    }
+    csv
+}
+
+/// Splits `text` into records of fields
+fn records(text: &str) -> Result<Vec<Vec<String>>, Error> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(Error::Translation("Unterminated quoted field".into()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Reads the translations of a CSV file. The first row must be the header.
+pub fn read(text: &str) -> Result<Vec<Translation>, Error> {
+    let mut records = records(text.trim_start_matches('\u{feff}'))?.into_iter();
+
+    let header = records.next().unwrap_or_default();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|x| x.trim() == name)
+            .ok_or_else(|| Error::Translation(format!("Missing column {name}")))
+    };
+    let [context, source, translation] = [
+        column("context")?,
+        column("source")?,
+        column("translation")?,
+    ];
+
+    let mut translations = vec![];
+    for (i, record) in records.enumerate() {
+        if record.iter().all(String::is_empty) {
+            continue;
+        }
+        let get = |n: usize| {
+            record.get(n).cloned().ok_or_else(|| {
+                Error::Translation(format!("Row {} has {} columns", i + 2, record.len()))
+            })
+        };
+
+        translations.push(Translation {
+            context: get(context)?,
+            source: get(source)?,
+            translation: get(translation)?,
+        });
+    }
+    Ok(translations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translation::{extract, inject, tests::data_dir};
+
+    #[test]
+    fn fields_are_quoted_when_needed() {
+        assert_eq!(field("Potion"), "Potion");
+        assert_eq!(field("Heals 50 HP, once"), "\"Heals 50 HP, once\"");
+        assert_eq!(field("Says \"hi\""), "\"Says \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(field("\r"), "\"\r\"");
+    }
+
+    #[test]
+    fn extracted_strings_are_translated_in_place() {
+        let dir = data_dir("csv");
+        let out = dir.join("out");
+        let entries = extract(&dir).unwrap();
+
+        // What a spreadsheet does: fill in the last column
+        let records = records(&write(&entries)).unwrap();
+        assert_eq!(records[0], HEADER);
+        let csv = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let translation = match i {
+                    0 => "translation".to_string(),
+                    _ => format!("«{}»", record[1]),
+                };
+                [&record[0], &record[1], &translation]
+                    .map(|x| field(x))
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let translations = read(&csv).unwrap();
+        assert_eq!(translations.len(), entries.len());
+        for (entry, translation) in entries.iter().zip(&translations) {
+            assert_eq!(translation.context, entry.context);
+            assert_eq!(translation.source, entry.text);
+            assert_eq!(translation.translation, format!("«{}»", entry.text));
+        }
+
+        let report = inject(&dir, &out, &translations).unwrap();
+        assert_eq!(report.applied, entries.len());
+        let actors: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("Actors.json")).unwrap())
+                .unwrap();
+        assert_eq!(actors[1]["profile"], "«Says \"hi\",\nthen leaves»");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn columns_are_found_by_name() {
+        let csv = "\u{feff}translation, source ,context,notes\r\n\
+                   \"Schleim\",Slime,Enemies.json:/1/name,\"a, b\"\r\n\
+                   ,,,\r\n\
+                   \"\"\"Held\"\"\",Hero,Classes.json:/1/name,\r\n";
+        let translations = read(csv).unwrap();
+        assert_eq!(translations.len(), 2);
+        assert_eq!(translations[0].translation, "Schleim");
+        assert_eq!(translations[1].translation, "\"Held\"");
+        assert_eq!(translations[1].context, "Classes.json:/1/name");
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        for csv in [
+            "context,source\r\nA,B\r\n",
+            "context,source,translation\r\nA,B\r\n",
+            "context,source,translation\r\nA,B,\"unterminated\r\n",
+            "",
+        ] {
+            assert!(matches!(read(csv), Err(Error::Translation(_))), "{csv}");
+        }
+    }
+}
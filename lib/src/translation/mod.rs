@@ -0,0 +1,504 @@
+//! Translatable text of the game's data files.
+//!
+//! [`extract`] collects database names, descriptions and messages, `System`
+//! terms, and the message commands of map events, common events and troops.
+//! Each string is keyed by its file and JSON Pointer (`Actors.json:/1/name`),
+//! which stays the same between runs as long as the data isn't edited.
+//!
+//! [`inject`] writes translations back by replacing just those strings, so
+//! the files keep the editor's formatting and diff cleanly.
+
+pub mod csv;
+pub mod po;
+mod spans;
+
+use crate::{error::Error, util::write_atomic};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// A string to translate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    /// `<file>:<JSON Pointer>`
+    pub context: String,
+    pub text: String,
+    /// Where the string is shown, for translators
+    pub note: String,
+}
+
+/// A translated string, as read back from a PO or CSV file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    pub context: String,
+    /// The text that was extracted, to catch data edited since
+    pub source: String,
+    pub translation: String,
+}
+
+/// Fields shown in game, for files that are lists of database entries
+const DATABASE_FIELDS: &[(&str, &[&str])] = &[
+    ("Actors.json", &["name", "nickname", "profile"]),
+    ("Classes.json", &["name"]),
+    (
+        "Skills.json",
+        &["name", "description", "message1", "message2"],
+    ),
+    ("Items.json", &["name", "description"]),
+    ("Weapons.json", &["name", "description"]),
+    ("Armors.json", &["name", "description"]),
+    ("Enemies.json", &["name"]),
+    (
+        "States.json",
+        &["name", "message1", "message2", "message3", "message4"],
+    ),
+];
+
+/// Lists of names in `System.json`
+const SYSTEM_LISTS: &[&str] = &[
+    "elements",
+    "skillTypes",
+    "weaponTypes",
+    "armorTypes",
+    "equipTypes",
+];
+
+struct Extractor {
+    file: String,
+    entries: Vec<Entry>,
+}
+impl Extractor {
+    fn push(&mut self, pointer: String, value: Option<&Value>, note: impl Into<String>) {
+        let Some(text) = value.and_then(Value::as_str) else {
+            return;
+        };
+        if text.trim().is_empty() {
+            return;
+        }
+
+        self.entries.push(Entry {
+            context: format!("{}:{pointer}", self.file),
+            text: text.to_string(),
+            note: note.into(),
+        });
+    }
+
+    /// Message commands of an event list at `pointer`
+    fn list(&mut self, pointer: &str, list: Option<&Value>, note: &str) {
+        let Some(list) = list.and_then(Value::as_array) else {
+            return;
+        };
+
+        for (i, command) in list.iter().enumerate() {
+            let code = command.get("code").and_then(Value::as_u64).unwrap_or(0);
+            let params = command.get("parameters");
+            let param = |n: usize| params.and_then(|x| x.get(n));
+            let at = |n: usize| format!("{pointer}/{i}/parameters/{n}");
+            let note = |what: &str| format!("{note}, line {}, {what}", i + 1);
+
+            match code {
+                // Show Text, speaker name (MZ)
+                101 => self.push(at(4), param(4), note("speaker name")),
+                401 => self.push(at(0), param(0), note("text")),
+                102 => {
+                    let choices = param(0).and_then(Value::as_array);
+                    for (n, choice) in choices.into_iter().flatten().enumerate() {
+                        let what = format!("choice {}", n + 1);
+                        self.push(format!("{}/{n}", at(0)), Some(choice), note(&what));
+                    }
+                }
+                405 => self.push(at(0), param(0), note("scrolling text")),
+                320 => self.push(at(1), param(1), note("change name")),
+                324 => self.push(at(1), param(1), note("change nickname")),
+                325 => self.push(at(1), param(1), note("change profile")),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<Value, Error> {
+    let file = std::fs::read_to_string(path)
+        .map_err(|e| Error::Data(format!("Failed to read {}: {e}", path.display())))?;
+    serde_json::from_str(file.trim_start_matches('\u{feff}'))
+        .map_err(|e| Error::Data(format!("{}: {e}", path.display())))
+}
+
+/// `MapXXX.json` files in `data_dir`, sorted
+fn map_files(data_dir: &Path) -> Result<Vec<String>, Error> {
+    let mut files = std::fs::read_dir(data_dir)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| {
+            name.strip_prefix("Map")
+                .and_then(|x| x.strip_suffix(".json"))
+                .is_some_and(|id| !id.is_empty() && id.bytes().all(|x| x.is_ascii_digit()))
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+/// Every translatable string in `data_dir`, file by file in a fixed order
+pub fn extract(data_dir: &Path) -> Result<Vec<Entry>, Error> {
+    let mut entries = vec![];
+
+    for (file, fields) in DATABASE_FIELDS {
+        let data = read(&data_dir.join(file))?;
+        let mut extractor = Extractor {
+            file: file.to_string(),
+            entries: vec![],
+        };
+        for (id, entry) in data.as_array().into_iter().flatten().enumerate() {
+            for field in *fields {
+                let note = format!("{file} #{id} {field}");
+                extractor.push(format!("/{id}/{field}"), entry.get(field), note);
+            }
+        }
+        entries.extend(extractor.entries);
+    }
+
+    let system = read(&data_dir.join("System.json"))?;
+    let mut extractor = Extractor {
+        file: "System.json".into(),
+        entries: vec![],
+    };
+    extractor.push("/gameTitle".into(), system.get("gameTitle"), "Game title");
+    extractor.push(
+        "/currencyUnit".into(),
+        system.get("currencyUnit"),
+        "Currency unit",
+    );
+    for list in SYSTEM_LISTS {
+        let values = system.get(list).and_then(Value::as_array);
+        for (i, value) in values.into_iter().flatten().enumerate() {
+            extractor.push(format!("/{list}/{i}"), Some(value), format!("{list} #{i}"));
+        }
+    }
+    let terms = system.get("terms");
+    for list in ["basic", "commands", "params"] {
+        let values = terms.and_then(|x| x.get(list)).and_then(Value::as_array);
+        for (i, value) in values.into_iter().flatten().enumerate() {
+            let note = format!("Terms, {list} #{i}");
+            extractor.push(format!("/terms/{list}/{i}"), Some(value), note);
+        }
+    }
+    let messages = terms
+        .and_then(|x| x.get("messages"))
+        .and_then(Value::as_object);
+    for (key, value) in messages.into_iter().flatten() {
+        let pointer = format!("/terms/messages/{}", spans::escape(key));
+        extractor.push(pointer, Some(value), format!("Terms, message {key}"));
+    }
+    entries.extend(extractor.entries);
+
+    let common_events = read(&data_dir.join("CommonEvents.json"))?;
+    let mut extractor = Extractor {
+        file: "CommonEvents.json".into(),
+        entries: vec![],
+    };
+    for (id, event) in common_events.as_array().into_iter().flatten().enumerate() {
+        let name = event
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let note = format!("Common Event {id:04} {name}");
+        extractor.list(&format!("/{id}/list"), event.get("list"), &note);
+    }
+    entries.extend(extractor.entries);
+
+    let troops = read(&data_dir.join("Troops.json"))?;
+    let mut extractor = Extractor {
+        file: "Troops.json".into(),
+        entries: vec![],
+    };
+    for (id, troop) in troops.as_array().into_iter().flatten().enumerate() {
+        let name = troop
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let pages = troop.get("pages").and_then(Value::as_array);
+        for (page, value) in pages.into_iter().flatten().enumerate() {
+            let note = format!("Troop {id:04} {name}, page {}", page + 1);
+            extractor.list(
+                &format!("/{id}/pages/{page}/list"),
+                value.get("list"),
+                &note,
+            );
+        }
+    }
+    entries.extend(extractor.entries);
+
+    for file in map_files(data_dir)? {
+        let map = read(&data_dir.join(&file))?;
+        let mut extractor = Extractor {
+            file: file.clone(),
+            entries: vec![],
+        };
+        let map_name = file.trim_end_matches(".json");
+        extractor.push(
+            "/displayName".into(),
+            map.get("displayName"),
+            format!("{map_name} display name"),
+        );
+
+        let events = map.get("events").and_then(Value::as_array);
+        for (id, event) in events.into_iter().flatten().enumerate() {
+            let name = event
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let pages = event.get("pages").and_then(Value::as_array);
+            for (page, value) in pages.into_iter().flatten().enumerate() {
+                let note = format!("{map_name} EV{id:03} {name}, page {}", page + 1);
+                let pointer = format!("/events/{id}/pages/{page}/list");
+                extractor.list(&pointer, value.get("list"), &note);
+            }
+        }
+        entries.extend(extractor.entries);
+    }
+
+    Ok(entries)
+}
+
+/// Outcome of [`inject`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub written: Vec<PathBuf>,
+    pub applied: usize,
+    /// Translations whose source text no longer matches the data
+    pub outdated: Vec<String>,
+    /// Translations for strings that no longer exist
+    pub missing: Vec<String>,
+    /// Contexts translated more than once, where the last translation is used
+    pub duplicated: Vec<String>,
+}
+
+/// Writes `translations` into the data files of `data_dir`, saving the
+/// changed files to `out_dir` (which can be `data_dir` itself). Empty
+/// translations are skipped, and of several for the same context the last
+/// one is used.
+pub fn inject(
+    data_dir: &Path,
+    out_dir: &Path,
+    translations: &[Translation],
+) -> Result<Report, Error> {
+    let mut report = Report::default();
+
+    let mut latest: BTreeMap<&str, &Translation> = BTreeMap::new();
+    for translation in translations {
+        if translation.translation.is_empty() {
+            continue;
+        }
+        if let Some(previous) = latest.insert(&translation.context, translation)
+            && previous.translation != translation.translation
+            && !report.duplicated.contains(&translation.context)
+        {
+            report.duplicated.push(translation.context.clone());
+        }
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&Translation>> = BTreeMap::new();
+    for translation in latest.into_values() {
+        match translation.context.split_once(':') {
+            Some((file, _)) if !file.contains(['/', '\\']) => {
+                by_file.entry(file).or_default().push(translation)
+            }
+            _ => report.missing.push(translation.context.clone()),
+        }
+    }
+
+    std::fs::create_dir_all(out_dir)?;
+    for (file, translations) in by_file {
+        let path = data_dir.join(file);
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            let contexts = translations.iter().map(|x| x.context.clone());
+            report.missing.extend(contexts);
+            continue;
+        };
+        let data: Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+            .map_err(|e| Error::Data(format!("{}: {e}", path.display())))?;
+
+        let mut replacements = vec![];
+        for translation in translations {
+            let pointer = &translation.context[file.len() + 1..];
+            match data.pointer(pointer).and_then(Value::as_str) {
+                None => report.missing.push(translation.context.clone()),
+                Some(current) if current != translation.source => {
+                    report.outdated.push(translation.context.clone())
+                }
+                Some(_) => {
+                    replacements.push((pointer.to_string(), translation.translation.clone()))
+                }
+            }
+        }
+        if replacements.is_empty() {
+            continue;
+        }
+
+        let output = spans::replace_strings(&text, &replacements)?;
+        let out = out_dir.join(file);
+        write_atomic(&out, output.as_bytes())?;
+
+        report.applied += replacements.len();
+        report.written.push(out);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Data files laid out the way the MV editor writes them, one database
+    /// entry per line
+    const FILES: &[(&str, &str)] = &[
+        (
+            "Actors.json",
+            "[\n\
+             null,\n\
+             {\"id\":1,\"name\":\"Harold\",\"nickname\":\"\",\"note\":\"<x>\",\"profile\":\"Says \\\"hi\\\",\\nthen leaves\"},\n\
+             {\"id\":2,\"name\":\"Thérèse\",\"nickname\":\"Tess\",\"note\":\"\",\"profile\":\"\"}\n\
+             ]",
+        ),
+        ("Classes.json", "[\nnull,\n{\"id\":1,\"name\":\"Hero\"}\n]"),
+        ("Skills.json", "[\nnull\n]"),
+        (
+            "Items.json",
+            "[\nnull,\n{\"id\":1,\"name\":\"Potion\",\"description\":\"Heals 50 HP, once\"}\n]",
+        ),
+        ("Weapons.json", "[\nnull\n]"),
+        ("Armors.json", "[\nnull\n]"),
+        ("Enemies.json", "[\nnull,\n{\"id\":1,\"name\":\"Slime\"}\n]"),
+        ("States.json", "[\nnull\n]"),
+        (
+            "System.json",
+            "{\"currencyUnit\":\"G\",\"elements\":[\"\",\"Fire\"],\"gameTitle\":\"Demo\",\
+             \"terms\":{\"basic\":[\"Level\"],\"commands\":[null,\"Fight\"],\"params\":[],\
+             \"messages\":{\"actionFailure\":\"There was no effect on %1!\"}}}",
+        ),
+        (
+            "CommonEvents.json",
+            "[\nnull,\n{\"id\":1,\"list\":[],\"name\":\"\"}\n]",
+        ),
+        ("Troops.json", "[\nnull\n]"),
+        (
+            "Map001.json",
+            "{\n\"displayName\":\"Town\",\n\"events\":[\nnull,\n\
+             {\"id\":1,\"name\":\"Guard\",\"pages\":[{\"list\":[\
+             {\"code\":101,\"indent\":0,\"parameters\":[\"Actor1\",0,0,2]},\
+             {\"code\":401,\"indent\":0,\"parameters\":[\"\\\\C[2]Stop!\\\\C[0]\"]},\
+             {\"code\":102,\"indent\":0,\"parameters\":[[\"Yes\",\"No\"],1]},\
+             {\"code\":0,\"indent\":0,\"parameters\":[]}]}]}\n\
+             ]\n}",
+        ),
+    ];
+
+    /// A data directory holding [`FILES`]
+    pub(crate) fn data_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("lib-translation-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, text) in FILES {
+            std::fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    fn translation(context: &str, source: &str, translation: &str) -> Translation {
+        Translation {
+            context: context.into(),
+            source: source.into(),
+            translation: translation.into(),
+        }
+    }
+
+    #[test]
+    fn every_shown_string_is_extracted() {
+        let dir = data_dir("extract");
+        let entries = extract(&dir).unwrap();
+        let contexts = entries
+            .iter()
+            .map(|x| x.context.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contexts,
+            [
+                "Actors.json:/1/name",
+                "Actors.json:/1/profile",
+                "Actors.json:/2/name",
+                "Actors.json:/2/nickname",
+                "Classes.json:/1/name",
+                "Items.json:/1/name",
+                "Items.json:/1/description",
+                "Enemies.json:/1/name",
+                "System.json:/gameTitle",
+                "System.json:/currencyUnit",
+                "System.json:/elements/1",
+                "System.json:/terms/basic/0",
+                "System.json:/terms/commands/1",
+                "System.json:/terms/messages/actionFailure",
+                "Map001.json:/displayName",
+                "Map001.json:/events/1/pages/0/list/1/parameters/0",
+                "Map001.json:/events/1/pages/0/list/2/parameters/0/0",
+                "Map001.json:/events/1/pages/0/list/2/parameters/0/1",
+            ]
+        );
+        assert_eq!(entries[1].text, "Says \"hi\",\nthen leaves");
+        assert_eq!(entries[15].text, "\\C[2]Stop!\\C[0]");
+        assert_eq!(
+            entries[16].note,
+            "Map001 EV001 Guard, page 1, line 3, choice 1"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn repeated_contexts_use_the_last_translation() {
+        let dir = data_dir("repeated");
+        let out = dir.join("out");
+        let translations = [
+            translation("Enemies.json:/1/name", "Slime", "Schleim"),
+            translation("Classes.json:/1/name", "Hero", "Held"),
+            translation("Enemies.json:/1/name", "Slime", "Glibber"),
+            // Same text again, or left empty, isn't a conflict
+            translation("Classes.json:/1/name", "Hero", "Held"),
+            translation("Classes.json:/1/name", "Hero", ""),
+        ];
+
+        let report = inject(&dir, &out, &translations).unwrap();
+        assert_eq!(report.applied, 2);
+        assert_eq!(report.duplicated, ["Enemies.json:/1/name"]);
+        assert_eq!(
+            std::fs::read_to_string(out.join("Enemies.json")).unwrap(),
+            "[\nnull,\n{\"id\":1,\"name\":\"Glibber\"}\n]"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outdated_and_missing_translations_are_skipped() {
+        let dir = data_dir("skipped");
+        let out = dir.join("out");
+        let translations = [
+            translation("Enemies.json:/1/name", "Bat", "Fledermaus"),
+            translation("Enemies.json:/2/name", "Bat", "Fledermaus"),
+            translation("Enemies.json:/1/id", "1", "2"),
+            translation("Nothing.json:/1/name", "Bat", "Fledermaus"),
+            translation("../Enemies.json:/1/name", "Slime", "Schleim"),
+        ];
+
+        let report = inject(&dir, &out, &translations).unwrap();
+        assert_eq!(report.applied, 0);
+        assert!(report.written.is_empty());
+        assert_eq!(report.outdated, ["Enemies.json:/1/name"]);
+        assert_eq!(report.missing.len(), 4);
+        assert!(!out.join("Enemies.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,291 @@
+//! Gettext PO files, one entry per string with its context as `msgctxt`
+
+use super::{Entry, Translation};
+use crate::error::Error;
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn unquote(text: &str, line: usize) -> Result<String, Error> {
+    let error = || Error::Translation(format!("Bad string on line {line}"));
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|x| x.strip_suffix('"'))
+        .ok_or_else(error)?;
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unquoted.push(c);
+            continue;
+        }
+        match chars.next().ok_or_else(error)? {
+            'n' => unquoted.push('\n'),
+            'r' => unquoted.push('\r'),
+            't' => unquoted.push('\t'),
+            c @ ('\\' | '"') => unquoted.push(c),
+            _ => return Err(error()),
+        }
+    }
+    Ok(unquoted)
+}
+
+/// Writes a PO template, with the translations left empty
+pub fn write(entries: &[Entry]) -> String {
+    let mut po = String::new();
+    po.push_str("msgid \"\"\nmsgstr \"\"\n");
+    po.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+
+    for entry in entries {
+        po.push('\n');
+        po.push_str(&format!("#. {}\n", entry.note.replace('\n', " ")));
+        po.push_str(&format!("msgctxt {}\n", quote(&entry.context)));
+        po.push_str(&format!("msgid {}\n", quote(&entry.text)));
+        po.push_str("msgstr \"\"\n");
+    }
+    po
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Context,
+    Id,
+    Str,
+}
+
+#[derive(Default)]
+struct Message {
+    context: Option<String>,
+    id: String,
+    string: String,
+    /// `#, fuzzy`: a guess that still needs checking
+    fuzzy: bool,
+}
+
+/// Reads the translations of a PO file. The header, entries without a
+/// context and fuzzy entries are skipped.
+pub fn read(text: &str) -> Result<Vec<Translation>, Error> {
+    let mut translations = vec![];
+    let mut message = Message::default();
+    let mut field = None;
+
+    let mut finish = |message: Message| {
+        if let Some(context) = message.context
+            && !message.fuzzy
+        {
+            translations.push(Translation {
+                context,
+                source: message.id,
+                translation: message.string,
+            });
+        }
+    };
+
+    for (i, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            // Comments come before the entry they're for
+            if line.starts_with('#') && field == Some(Field::Str) {
+                finish(std::mem::take(&mut message));
+                field = None;
+            }
+            if let Some(flags) = line.strip_prefix("#,") {
+                message.fuzzy |= flags.split(',').any(|x| x.trim() == "fuzzy");
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let next = match keyword {
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgstr" => Field::Str,
+            _ if line.starts_with('"') => {
+                let value = unquote(line, line_number)?;
+                match field {
+                    Some(Field::Context) => {
+                        message.context.get_or_insert_default().push_str(&value)
+                    }
+                    Some(Field::Id) => message.id.push_str(&value),
+                    Some(Field::Str) => message.string.push_str(&value),
+                    None => {
+                        let msg = format!("String outside an entry on line {line_number}");
+                        return Err(Error::Translation(msg));
+                    }
+                }
+                continue;
+            }
+            _ => {
+                let msg = format!("Unsupported line {line_number}: {line}");
+                return Err(Error::Translation(msg));
+            }
+        };
+
+        // A new entry starts at msgctxt, or at msgid when there's no context
+        if next != Field::Str && field == Some(Field::Str) {
+            finish(std::mem::take(&mut message));
+        }
+
+        let value = unquote(rest.trim(), line_number)?;
+        match next {
+            Field::Context => message.context = Some(value),
+            Field::Id => message.id = value,
+            Field::Str => message.string = value,
+        }
+        field = Some(next);
+    }
+    finish(message);
+
+    Ok(translations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translation::{extract, inject, spans::replace_strings, tests::data_dir};
+
+    #[test]
+    fn extracted_strings_are_translated_in_place() {
+        let dir = data_dir("po");
+        let out = dir.join("out");
+        let entries = extract(&dir).unwrap();
+        let template = write(&entries);
+
+        // What a translator does: fill in every msgstr
+        let mut translated = String::new();
+        let mut msgid = String::new();
+        for line in template.lines() {
+            if let Some(id) = line.strip_prefix("msgid ") {
+                msgid = unquote(id, 0).unwrap();
+            }
+            if line == "msgstr \"\"" && !msgid.is_empty() {
+                translated.push_str(&format!("msgstr {}\n", quote(&format!("«{msgid}»"))));
+            } else {
+                translated.push_str(line);
+                translated.push('\n');
+            }
+        }
+
+        let translations = read(&translated).unwrap();
+        assert_eq!(translations.len(), entries.len());
+        for (entry, translation) in entries.iter().zip(&translations) {
+            assert_eq!(translation.context, entry.context);
+            assert_eq!(translation.source, entry.text);
+            assert_eq!(translation.translation, format!("«{}»", entry.text));
+        }
+
+        let report = inject(&dir, &out, &translations).unwrap();
+        assert_eq!(report.applied, entries.len());
+        assert!(report.outdated.is_empty() && report.missing.is_empty());
+
+        // Putting the sources back gives the original files byte for byte
+        for path in &report.written {
+            let file = path.file_name().unwrap().to_str().unwrap();
+            let originals = entries
+                .iter()
+                .filter_map(|x| {
+                    Some((
+                        x.context.strip_prefix(&format!("{file}:"))?.to_string(),
+                        x.text.clone(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            let output = std::fs::read_to_string(path).unwrap();
+            let restored = replace_strings(&output, &originals).unwrap();
+            assert_eq!(restored, std::fs::read_to_string(dir.join(file)).unwrap());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fuzzy_entries_are_skipped() {
+        let po = r#"#, fuzzy
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#. Actors.json #1 name
+msgctxt "Actors.json:/1/name"
+msgid "Harold"
+msgstr "Harald"
+
+#. Actors.json #2 name
+#, fuzzy, c-format
+msgctxt "Actors.json:/2/name"
+msgid "Therese"
+msgstr "Theresa"
+
+#. The entry after a fuzzy one isn't fuzzy
+msgctxt "Classes.json:/1/name"
+msgid "Hero"
+msgstr "Held"
+msgctxt "Enemies.json:/1/name"
+msgid "Slime"
+msgstr "Schleim"
+"#;
+        let contexts = read(po)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.context)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            contexts,
+            [
+                "Actors.json:/1/name",
+                "Classes.json:/1/name",
+                "Enemies.json:/1/name"
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_entries_are_all_read() {
+        let po = "msgctxt \"Items.json:/1/name\"\nmsgid \"Potion\"\nmsgstr \"Trank\"\n\n\
+                  msgctxt \"Items.json:/1/name\"\nmsgid \"Potion\"\nmsgstr \"Heiltrank\"\n";
+        let translations = read(po).unwrap();
+        assert_eq!(translations.len(), 2);
+        assert_eq!(translations[1].translation, "Heiltrank");
+    }
+
+    #[test]
+    fn strings_can_span_lines() {
+        let po = "\u{feff}msgctxt \"Actors.json:/1/profile\"\n\
+                  msgid \"\"\n\"Says \\\"hi\\\",\\n\"\n\"then leaves\"\n\
+                  msgstr \"\"\n\"Sagt \\\"hallo\\\",\\n\"\n\"und\\tgeht\\\\\"\n";
+        let translations = read(po).unwrap();
+        assert_eq!(translations[0].source, "Says \"hi\",\nthen leaves");
+        assert_eq!(translations[0].translation, "Sagt \"hallo\",\nund\tgeht\\");
+
+        for text in ["a\"b\\c\nd\r\te", "", "«»"] {
+            assert_eq!(unquote(&quote(text), 1).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        for po in [
+            "msgid \"unterminated\n",
+            "msgid \"bad \\x escape\"\n",
+            "\"string outside an entry\"\n",
+            "msgid_plural \"plural\"\n",
+        ] {
+            assert!(matches!(read(po), Err(Error::Translation(_))), "{po}");
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Byte ranges of the string values in a JSON document, so single strings
+//! can be replaced without reformatting the rest of the file.
+
+use crate::error::Error;
+use std::{collections::HashMap, ops::Range};
+
+pub(super) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+struct Scanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+    path: Vec<String>,
+    spans: HashMap<String, Range<usize>>,
+}
+impl Scanner<'_> {
+    fn error(&self, msg: &str) -> Error {
+        Error::Translation(format!("{msg} at byte {}", self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .text
+            .get(self.pos)
+            .is_some_and(|x| x.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Result<u8, Error> {
+        self.skip_whitespace();
+        self.text
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error("Unexpected end of JSON"))
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        if self.peek()? != byte {
+            return Err(self.error(&format!("Expected '{}'", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Range of the string at the cursor, quotes included
+    fn string(&mut self) -> Result<Range<usize>, Error> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.text.get(self.pos) {
+                Some(b'"') => break,
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+        self.pos += 1;
+        Ok(start..self.pos)
+    }
+
+    fn pointer(&self) -> String {
+        self.path
+            .iter()
+            .map(|x| format!("/{}", escape(x)))
+            .collect()
+    }
+
+    fn value(&mut self) -> Result<(), Error> {
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                if self.peek()? == b'}' {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                loop {
+                    let key = self.string()?;
+                    let key: String = serde_json::from_slice(&self.text[key])?;
+                    self.expect(b':')?;
+
+                    self.path.push(key);
+                    self.value()?;
+                    self.path.pop();
+
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b'}' => break,
+                        _ => return Err(self.error("Expected ',' or '}'")),
+                    }
+                }
+                self.pos += 1;
+            }
+            b'[' => {
+                self.pos += 1;
+                if self.peek()? == b']' {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                let mut index = 0usize;
+                loop {
+                    self.path.push(index.to_string());
+                    self.value()?;
+                    self.path.pop();
+                    index += 1;
+
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b']' => break,
+                        _ => return Err(self.error("Expected ',' or ']'")),
+                    }
+                }
+                self.pos += 1;
+            }
+            b'"' => {
+                let range = self.string()?;
+                self.spans.insert(self.pointer(), range);
+            }
+            _ => {
+                // Numbers, true, false and null
+                while self
+                    .text
+                    .get(self.pos)
+                    .is_some_and(|x| !matches!(x, b',' | b']' | b'}') && !x.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// JSON Pointer of every string value in `text` to its byte range
+pub fn string_spans(text: &str) -> Result<HashMap<String, Range<usize>>, Error> {
+    let bom = if text.starts_with('\u{feff}') { 3 } else { 0 };
+    let mut scanner = Scanner {
+        text: text.as_bytes(),
+        pos: bom,
+        path: vec![],
+        spans: HashMap::new(),
+    };
+    scanner.value()?;
+    Ok(scanner.spans)
+}
+
+/// Replaces the strings at each pointer of `replacements`, leaving the rest
+/// of `text` byte for byte as it was. Each pointer can only be replaced once.
+pub fn replace_strings(text: &str, replacements: &[(String, String)]) -> Result<String, Error> {
+    let spans = string_spans(text)?;
+
+    let mut edits = replacements
+        .iter()
+        .map(|(pointer, value)| {
+            let range = spans
+                .get(pointer)
+                .ok_or_else(|| Error::Translation(format!("No string at {pointer}")))?;
+            Ok((pointer, range.clone(), serde_json::to_string(value)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    edits.sort_by_key(|(_, range, _)| range.start);
+
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for (pointer, range, value) in edits {
+        if range.start < last {
+            let msg = format!("{pointer} is replaced more than once");
+            return Err(Error::Translation(msg));
+        }
+        output.push_str(&text[last..range.start]);
+        output.push_str(&value);
+        last = range.end;
+    }
+    output.push_str(&text[last..]);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\u{feff}{\n  \"a/b\": {\"~k\": \"x\"},\n  \"list\": [1, -2.5e3, true, null, \"caf\\u00e9 \\\"q\\\"\", [\"\"]],\n  \"n\": \"セーブ\"\n}\n";
+
+    #[test]
+    fn every_string_value_is_found() {
+        let spans = string_spans(TEXT).unwrap();
+        let mut pointers = spans.keys().map(String::as_str).collect::<Vec<_>>();
+        pointers.sort();
+        assert_eq!(pointers, ["/a~1b/~0k", "/list/4", "/list/5/0", "/n"]);
+
+        assert_eq!(&TEXT[spans["/a~1b/~0k"].clone()], "\"x\"");
+        assert_eq!(&TEXT[spans["/list/4"].clone()], "\"caf\\u00e9 \\\"q\\\"\"");
+        assert_eq!(&TEXT[spans["/list/5/0"].clone()], "\"\"");
+        assert_eq!(&TEXT[spans["/n"].clone()], "\"セーブ\"");
+    }
+
+    #[test]
+    fn only_the_replaced_strings_change() {
+        let replacements = [
+            ("/n".to_string(), "Save \"1\"\n".to_string()),
+            ("/a~1b/~0k".to_string(), "y".to_string()),
+        ];
+        let output = replace_strings(TEXT, &replacements).unwrap();
+        assert_eq!(
+            output,
+            TEXT.replace("\"x\"", "\"y\"")
+                .replace("\"セーブ\"", "\"Save \\\"1\\\"\\n\"")
+        );
+
+        let value: serde_json::Value = serde_json::from_str(&output[3..]).unwrap();
+        assert_eq!(value["n"], "Save \"1\"\n");
+        assert_eq!(value["list"][4], "café \"q\"");
+    }
+
+    #[test]
+    fn bad_replacements_are_errors() {
+        let twice = [
+            ("/n".to_string(), "a".to_string()),
+            ("/n".to_string(), "b".to_string()),
+        ];
+        assert!(replace_strings(TEXT, &twice).is_err());
+        // Not a string
+        assert!(replace_strings(TEXT, &[("/list/0".to_string(), "a".to_string())]).is_err());
+
+        for text in ["{\"a\": \"unterminated}", "[1, 2", "{\"a\" 1}", "{1: 2}"] {
+            assert!(string_spans(text).is_err(), "{text}");
+        }
+    }
+}
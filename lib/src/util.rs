@@ -0,0 +1,18 @@
+//! Filesystem helpers shared by everything that writes game or save files.
+
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Writes to a temporary file next to `path` and renames it over `path`, so
+/// readers never see a partially written file
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    std::fs::write(&temp, data)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })?;
+    Ok(())
+}
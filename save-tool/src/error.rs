@@ -1,12 +1,9 @@
-use std::path::PathBuf;
-
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     Global(String),
     Io(String),
     Lib(lib::error::Error),
     Image(String),
-    MissingDirectory(PathBuf),
 }
 impl Error {
     pub fn global_error(s: impl Into<String>) -> Self {
@@ -1,21 +1,44 @@
 mod error;
-mod global;
 mod save_entry;
 mod saves_state;
 mod widgets;
 
-use crate::{saves_state::SavesState, widgets::save_widget};
+use crate::{
+    error::Error,
+    saves_state::{MapIdCache, SavesState},
+    widgets::{
+        backup_list::{self, BackupList},
+        config_widget::{self, ConfigWidget},
+        save_details::SaveDetails,
+        save_widget,
+        switch_table::{self, SwitchTable},
+    },
+};
 use iced::{
     Length, Theme,
-    widget::{button, column, row, scrollable, text, text_input, vertical_space},
+    widget::{
+        button, column, container, horizontal_rule, row, scrollable, text, text_input,
+        vertical_rule, vertical_space,
+    },
+};
+use lib::{
+    data::DataSource,
+    layout::GameLayout,
+    save::{Format, backup, extension::Registry, rebuild, slots},
 };
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     DirectoryChanged(String),
+    GameDirectoryChanged(String),
     OpenDirectoryDialog,
+    RebuildGlobal,
     SaveWidgetMessage(usize, save_widget::Message),
+    ConfigWidgetMessage(config_widget::Message),
+    ToggleBackups,
+    BackupListMessage(backup_list::Message),
+    SwitchTableMessage(switch_table::Message),
     FontLoaded,
 }
 
@@ -24,25 +47,88 @@ pub type Element<'a> = iced::Element<'a, Message>;
 #[derive(Debug, Default)]
 struct App {
     save_directory: PathBuf,
+    /// Where the game is, for saves that aren't inside it
+    game_directory: Option<PathBuf>,
+    /// The game the save directory belongs to
+    layout: Option<GameLayout>,
+    /// Whether the saves are MV's `.rpgsave` or MZ's `.rmmzsave`
+    format: Format,
     saves: SavesState,
+    /// Outlives `saves`, which is loaded again after every change
+    map_ids: MapIdCache,
     selected_save_widget: Option<usize>,
+    details: Option<SaveDetails>,
+    switch_table: Option<SwitchTable>,
+    /// Plugin save data this build knows how to describe
+    extensions: Registry,
+    config: Option<ConfigWidget>,
+    /// Save files exist but the global file couldn't be loaded
+    has_unindexed_saves: bool,
+    error: Option<Error>,
+    backups: BackupList,
+    show_backups: bool,
 }
 impl App {
+    /// The game's database, loose or in its package
+    fn data_source(&self) -> Result<DataSource, Error> {
+        self.layout
+            .clone()
+            .map(DataSource::Game)
+            .ok_or_else(|| {
+                Error::io_error(format!(
+                    "No game found for {}, set the game directory",
+                    self.save_directory.display()
+                ))
+            })
+    }
+
+    fn load_directory(&mut self, dir: &Path) {
+        self.save_directory = dir.into();
+        self.layout =
+            GameLayout::for_saves(&self.save_directory, self.game_directory.as_deref()).ok();
+        // The global file is there unless it's lost, then go by the game
+        self.format = [Format::Mv, Format::Mz]
+            .into_iter()
+            .find(|format| format.global_path(&self.save_directory).is_file())
+            .or_else(|| self.layout.as_ref().map(|layout| layout.engine.into()))
+            .unwrap_or_default();
+        self.saves = SavesState::load_from_global(
+            &self.save_directory,
+            self.format,
+            self.layout.as_ref(),
+            &mut self.map_ids,
+        );
+        self.selected_save_widget = None;
+        self.details = None;
+        self.switch_table = None;
+        self.error = None;
+        self.config = ConfigWidget::load(&self.save_directory, self.format);
+        self.backups = BackupList::load(&self.save_directory);
+        self.has_unindexed_saves = matches!(self.saves, SavesState::NotLoaded)
+            && self
+                .format
+                .save_files(&self.save_directory)
+                .is_ok_and(|files| !files.is_empty());
+    }
+
+    /// Reloads the saves after they were changed on disk
+    fn on_files_changed(&mut self, result: Result<(), lib::error::Error>) {
+        match result {
+            Ok(()) => self.load_directory(&self.save_directory.clone()),
+            Err(e) => self.error = Some(e.into()),
+        }
+    }
+
     fn update(&mut self, msg: Message) {
         use Message::*;
 
-        let mut on_new_dir = |dir: &Path| {
-            self.save_directory = dir.into();
-            self.saves = SavesState::load_from_global(
-                &self.save_directory,
-                &self.save_directory.join("global.rpgsave"),
-            );
-            self.selected_save_widget = None;
-        };
-
         match msg {
             DirectoryChanged(new_dir) => {
-                on_new_dir(Path::new(&new_dir));
+                self.load_directory(Path::new(&new_dir));
+            }
+            GameDirectoryChanged(new_dir) => {
+                self.game_directory = (!new_dir.is_empty()).then(|| new_dir.into());
+                self.load_directory(&self.save_directory.clone());
             }
             OpenDirectoryDialog => {
                 let dir = rfd::FileDialog::default()
@@ -50,33 +136,94 @@ impl App {
                     .pick_folder();
 
                 if let Some(new_dir) = dir {
-                    on_new_dir(&new_dir);
+                    self.load_directory(&new_dir);
+                }
+            }
+            RebuildGlobal => {
+                let result = self.data_source().and_then(|source| {
+                    rebuild::regenerate_global(&self.save_directory, &source, self.format)
+                        .map_err(Error::from)
+                });
+
+                match result {
+                    Ok(_) => self.load_directory(&self.save_directory.clone()),
+                    Err(e) => self.saves = SavesState::Error(e),
                 }
             }
             SaveWidgetMessage(widget_index, msg) => {
+                // Before copying or deleting, which reload the widgets
+                if let SavesState::Loaded { entries, .. } = &mut self.saves
+                    && let Some(widget) = entries.get_mut(widget_index)
+                {
+                    widget.update(msg.clone());
+                }
+
                 match msg {
                     save_widget::Message::Clicked => {
                         self.selected_save_widget = Some(widget_index);
+                        self.details = Some(SaveDetails::load(
+                            &self.save_directory,
+                            self.format,
+                            widget_index,
+                            &self.extensions,
+                        ));
+                        self.switch_table = Some(SwitchTable::load(
+                            &self.save_directory,
+                            self.format,
+                            self.data_source(),
+                            widget_index,
+                        ));
                     }
                     save_widget::Message::Copy => {
-
+                        let result = slots::first_free_slot(&self.save_directory, self.format)
+                            .and_then(|slot| {
+                                let slot = slot.ok_or(lib::error::Error::Save(
+                                    "No free save slot".into(),
+                                ))?;
+                                slots::copy(&self.save_directory, self.format, widget_index, slot)
+                            });
+                        self.on_files_changed(result);
+                    }
+                    save_widget::Message::ConfirmDelete => {
+                        let result = slots::delete(&self.save_directory, self.format, widget_index);
+                        self.on_files_changed(result);
                     }
-                    save_widget::Message::Delete => {}
                     _ => {}
                 }
-
-                if let SavesState::Loaded { entries, .. } = &mut self.saves
-                    && let Some(widget) = entries.get_mut(widget_index)
-                {
-                    widget.update(msg);
+            }
+            ConfigWidgetMessage(msg) => {
+                if let Some(config) = &mut self.config {
+                    config.update(msg);
+                }
+                self.backups = BackupList::load(&self.save_directory);
+            }
+            ToggleBackups => {
+                self.show_backups = !self.show_backups;
+            }
+            BackupListMessage(backup_list::Message::Restore(id)) => {
+                let result = backup::restore(&self.save_directory, &id);
+                self.on_files_changed(result);
+            }
+            SwitchTableMessage(msg) => {
+                if let Some(table) = &mut self.switch_table {
+                    table.update(msg);
                 }
             }
             FontLoaded => {}
         }
     }
 
-    fn view_saves(&self) -> Element {
+    fn view_saves(&self) -> Element<'_> {
         match &self.saves {
+            SavesState::NotLoaded if self.has_unindexed_saves => column![
+                text(format!(
+                    "global.{} is missing or unreadable",
+                    self.format.extension()
+                )),
+                button("Rebuild from save files").on_press(Message::RebuildGlobal),
+            ]
+            .spacing(8)
+            .into(),
             SavesState::NotLoaded => vertical_space().into(),
             SavesState::Error(e) => text(e.to_string())
                 .style(|theme: &Theme| text::Style {
@@ -90,26 +237,75 @@ impl App {
                         .map(move |msg| Message::SaveWidgetMessage(i, msg))
                 });
 
-                scrollable(column(views)).into()
+                let saves = scrollable(column(views)).height(Length::Fill);
+                let mut content = column![saves];
+                if let Some(details) = &self.details {
+                    content = content.push(horizontal_rule(24)).push(details.view());
+                }
+                if let Some(table) = &self.switch_table {
+                    content = content
+                        .push(horizontal_rule(24))
+                        .push(table.view().map(Message::SwitchTableMessage));
+                }
+                content.into()
             }
         }
     }
 
-    fn view(&self) -> iced::Element<Message> {
+    fn view(&self) -> iced::Element<'_, Message> {
         let folder_box = row![
             text_input("Save directory", &self.save_directory.to_string_lossy())
                 .on_input(Message::DirectoryChanged),
             button("...").on_press(Message::OpenDirectoryDialog),
+            text_input(
+                "Game directory, if the saves aren't in it",
+                &self
+                    .game_directory
+                    .as_deref()
+                    .map(Path::to_string_lossy)
+                    .unwrap_or_default(),
+            )
+            .on_input(Message::GameDirectoryChanged),
+            button(if self.show_backups {
+                "Saves"
+            } else {
+                "Backups"
+            })
+            .on_press(Message::ToggleBackups),
         ]
         .width(Length::Fill)
         .spacing(8);
 
-        let content = self.view_saves();
+        let content = if self.show_backups {
+            self.backups.view().map(Message::BackupListMessage)
+        } else {
+            self.view_saves()
+        };
+        let content = match &self.error {
+            Some(e) => column![
+                text(e.to_string()).style(|theme: &Theme| text::Style {
+                    color: Some(theme.palette().danger),
+                }),
+                content,
+            ]
+            .spacing(8)
+            .into(),
+            None => content,
+        };
+        let content = match &self.config {
+            Some(config) => row![
+                container(content).width(Length::Fill),
+                vertical_rule(24),
+                container(config.view().map(Message::ConfigWidgetMessage)).width(240),
+            ]
+            .into(),
+            None => content,
+        };
 
         // let content =
         //     iced::widget::scrollable(row![text(format!("{:#?}", self.saves)),]).width(Length::Fill);
 
-        column![folder_box, iced::widget::horizontal_rule(24), content]
+        column![folder_box, horizontal_rule(24), content]
             .padding(8)
             .width(Length::Fill)
             .height(Length::Fill)
@@ -1,14 +1,14 @@
-use crate::{
-    error::Error,
-    global::{GlobalEntry, SpriteInfo},
-};
+use crate::error::Error;
 use iced::advanced::image::Handle;
 use image::DynamicImage;
-use lib::image::{Spritesheet, SpritesheetKind};
+use lib::{
+    image::{Spritesheet, SpritesheetKind},
+    layout::GameLayout,
+    save::global::{GlobalEntry, SpriteInfo},
+};
 use std::{
     collections::{HashMap, hash_map::Entry},
-    fs::File,
-    io::{BufReader, Cursor},
+    io::Cursor,
     path::{Path, PathBuf},
 };
 
@@ -21,30 +21,28 @@ pub struct SaveEntry {
     pub character_images: Vec<ImageBuffer>,
 }
 impl SaveEntry {
+    /// Without the game's `layout`, the entry has no images
     pub fn new(
         entry: GlobalEntry,
-        save_dir: &Path,
+        layout: Option<&GameLayout>,
         file_cache: &mut HashMap<PathBuf, Spritesheet<DynamicImage>>,
     ) -> Result<Self, Error> {
-        let img_dir = save_dir
-            .parent()
-            .ok_or(Error::io_error(format!(
-                "no parent of {}",
-                save_dir.display()
-            )))?
-            .join("img");
-
-        let characters_dir = img_dir.join("characters");
-        let faces_dir = img_dir.join("faces");
+        let Some(layout) = layout else {
+            return Ok(Self {
+                global: entry,
+                face_images: vec![],
+                character_images: vec![],
+            });
+        };
 
         let load_image_file = |file_path: &Path| -> Result<DynamicImage, Error> {
-            let file =
-                BufReader::new(File::open(file_path).map_err(|_| {
-                    Error::io_error(format!("Missing file: {}", file_path.display()))
-                })?);
-            let decrypted = {
-                let buf = lib::image::decrypt_derive_key(file)?;
-                BufReader::new(Cursor::new(buf))
+            let file = layout
+                .read(file_path)
+                .map_err(|_| Error::io_error(format!("Missing file: {}", file_path.display())))?;
+            let decrypted = if file_path.extension().is_some_and(|x| x == "png") {
+                Cursor::new(file)
+            } else {
+                Cursor::new(lib::image::decrypt_derive_key(file.as_slice())?)
             };
 
             let mut image_reader = image::ImageReader::new(decrypted);
@@ -56,9 +54,8 @@ impl SaveEntry {
         };
 
         let mut get_image_buffer =
-            |kind: SpritesheetKind, spritesheet_dir: &Path, sprite: &SpriteInfo| {
-                let spritesheet_file_path =
-                    spritesheet_dir.join(format!("{}.rpgmvp", &sprite.file_name));
+            |kind: SpritesheetKind, spritesheet_dir: &str, sprite: &SpriteInfo| {
+                let spritesheet_file_path = layout.image_path(spritesheet_dir, &sprite.file_name);
 
                 let sheet = match file_cache.entry(spritesheet_file_path.clone()) {
                     Entry::Vacant(vacant) => {
@@ -84,15 +81,13 @@ impl SaveEntry {
         let face_images = entry
             .faces
             .iter()
-            .map(|face| get_image_buffer(SpritesheetKind::Face, &faces_dir, face))
+            .map(|face| get_image_buffer(SpritesheetKind::Face, "faces", face))
             .collect::<Result<Vec<_>, _>>()?;
 
         let character_images = entry
             .characters
             .iter()
-            .map(|character| {
-                get_image_buffer(SpritesheetKind::Character, &characters_dir, character)
-            })
+            .map(|character| get_image_buffer(SpritesheetKind::Character, "characters", character))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self {
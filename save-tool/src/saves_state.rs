@@ -1,8 +1,37 @@
-use crate::{
-    error::Error, global::GlobalEntry, save_entry::SaveEntry, widgets::save_widget::SaveWidget,
+use crate::{error::Error, save_entry::SaveEntry, widgets::save_widget::SaveWidget};
+use lib::{
+    data::{Table, map::MapInfo},
+    layout::GameLayout,
+    save::{Format, Json as SaveJson, global::GlobalEntry, model::GameSave},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
-use lib::save::Json as SaveJson;
-use std::{collections::HashMap, path::Path};
+
+/// Map ids read out of save files, kept across reloads so only the saves that
+/// changed since are decoded again
+#[derive(Debug, Default)]
+pub struct MapIdCache(HashMap<PathBuf, ((SystemTime, u64), Option<u32>)>);
+impl MapIdCache {
+    fn map_id(&mut self, path: &Path) -> Option<u32> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let version = (metadata.modified().ok()?, metadata.len());
+
+        match self.0.get(path) {
+            Some((cached, map_id)) if *cached == version => *map_id,
+            _ => {
+                let map_id = SaveJson::read_file(path)
+                    .and_then(|save| GameSave::from_json(&save))
+                    .map(|save| save.map_id())
+                    .ok();
+                self.0.insert(path.into(), (version, map_id));
+                map_id
+            }
+        }
+    }
+}
 
 #[derive(Debug, Default)]
 pub enum SavesState {
@@ -14,13 +43,26 @@ pub enum SavesState {
     Error(Error),
 }
 impl SavesState {
-    pub fn load_from_global(save_dir: &Path, global_path: &Path) -> SavesState {
-        let save_json = {
-            let file = std::fs::read_to_string(global_path);
-            file.ok().and_then(|f| SaveJson::decompress(&f).ok())
-        };
+    pub fn load_from_global(
+        save_dir: &Path,
+        format: Format,
+        layout: Option<&GameLayout>,
+        map_ids: &mut MapIdCache,
+    ) -> SavesState {
+        let save_json = SaveJson::read_file(&format.global_path(save_dir)).ok();
 
         let mut file_cache = HashMap::default();
+        let map_infos = layout.and_then(|layout| {
+            let file = layout.read(&layout.data_dir().join("MapInfos.json")).ok()?;
+            serde_json::from_slice::<Table<MapInfo>>(&file).ok()
+        });
+        let mut location = |file_id: usize| {
+            let map_id = map_ids.map_id(&format.file_path(save_dir, file_id))? as usize;
+            match &map_infos {
+                Some(infos) => infos.location(map_id),
+                None => Some(format!("Map {map_id}")),
+            }
+        };
 
         let save_json = match save_json {
             None => return SavesState::NotLoaded,
@@ -33,8 +75,8 @@ impl SavesState {
                 .map(serde_json::from_value::<Option<GlobalEntry>>)
                 .enumerate()
                 .map(|(i, entry)| match entry {
-                    Ok(Some(entry)) => SaveEntry::new(entry, save_dir, &mut file_cache)
-                        .map(|e| SaveWidget::new(i, e)),
+                    Ok(Some(entry)) => SaveEntry::new(entry, layout, &mut file_cache)
+                        .map(|e| SaveWidget::new(i, e, location(i))),
                     Ok(None) => Ok(SaveWidget::empty(i)),
                     Err(e) => Err(Error::Io(e.to_string())),
                 })
@@ -0,0 +1,68 @@
+use iced::{
+    Alignment, Length,
+    widget::{button, column, container, horizontal_space, row, scrollable, text},
+};
+use lib::save::backup::{self, Snapshot};
+use std::path::Path;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Restore(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BackupList {
+    pub snapshots: Result<Vec<Snapshot>, Error>,
+}
+impl Default for BackupList {
+    fn default() -> Self {
+        Self {
+            snapshots: Ok(vec![]),
+        }
+    }
+}
+impl BackupList {
+    pub fn load(save_dir: &Path) -> Self {
+        Self {
+            snapshots: backup::list(save_dir).map_err(Error::from),
+        }
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let snapshots = match &self.snapshots {
+            Ok(snapshots) => snapshots,
+            Err(e) => return text(e.to_string()).into(),
+        };
+
+        if snapshots.is_empty() {
+            return text("No backups").into();
+        }
+
+        // Newest first
+        let rows = snapshots.iter().rev().map(|snapshot| {
+            let created = snapshot
+                .created
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S");
+
+            let mut files = snapshot.files.join(", ");
+            if !snapshot.missing.is_empty() {
+                files = format!("{files} (created: {})", snapshot.missing.join(", "));
+            }
+
+            let content = row![
+                column![text(created.to_string()), text(files).size(12)].spacing(4),
+                horizontal_space(),
+                button("Restore").on_press(Message::Restore(snapshot.id.clone())),
+            ]
+            .align_y(Alignment::Center)
+            .spacing(8);
+
+            container(content).padding(8).width(Length::Fill).into()
+        });
+
+        scrollable(column(rows)).into()
+    }
+}
@@ -0,0 +1,147 @@
+use iced::{
+    Length, Theme,
+    widget::{button, checkbox, column, row, slider, text},
+};
+use lib::save::{Format, config::Config};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Volume {
+    Bgm,
+    Bgs,
+    Me,
+    Se,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    AlwaysDash(bool),
+    CommandRemember(bool),
+    TouchUi(bool),
+    Volume(Volume, u32),
+    Save,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigWidget {
+    pub path: PathBuf,
+    pub config: Result<Config, Error>,
+    pub is_modified: bool,
+    /// Why the last save failed, the edits are kept to try again
+    pub save_error: Option<Error>,
+}
+impl ConfigWidget {
+    /// Loads `config.rpgsave` or `config.rmmzsave` from `save_dir`, if it exists
+    pub fn load(save_dir: &Path, format: Format) -> Option<Self> {
+        let path = format.config_path(save_dir);
+        if !path.exists() {
+            return None;
+        }
+
+        let config = Config::read_file(&path).map_err(Error::from);
+
+        Some(Self {
+            path,
+            config,
+            is_modified: false,
+            save_error: None,
+        })
+    }
+
+    pub fn update(&mut self, msg: Message) {
+        let Ok(config) = &mut self.config else {
+            return;
+        };
+
+        match msg {
+            Message::AlwaysDash(value) => config.always_dash = value,
+            Message::CommandRemember(value) => config.command_remember = value,
+            Message::TouchUi(value) => config.touch_ui = Some(value),
+            Message::Volume(kind, value) => {
+                let volume = match kind {
+                    Volume::Bgm => &mut config.bgm_volume,
+                    Volume::Bgs => &mut config.bgs_volume,
+                    Volume::Me => &mut config.me_volume,
+                    Volume::Se => &mut config.se_volume,
+                };
+                *volume = value;
+            }
+            Message::Save => {
+                match config.write_file(&self.path) {
+                    Ok(()) => {
+                        self.is_modified = false;
+                        self.save_error = None;
+                    }
+                    Err(e) => self.save_error = Some(e.into()),
+                }
+                return;
+            }
+        }
+
+        self.is_modified = true;
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let config = match &self.config {
+            Ok(config) => config,
+            Err(e) => {
+                return text(e.to_string())
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().danger),
+                    })
+                    .into();
+            }
+        };
+
+        let volume = |label: &'static str, kind: Volume, value: u32| {
+            column![
+                text(format!("{label}: {value}%")),
+                // The options menu moves volume in steps of 20
+                slider(0..=100, value, move |v| Message::Volume(kind, v)).step(20u32),
+            ]
+            .spacing(4)
+        };
+
+        let mut content = column![
+            text("Options").size(20),
+            checkbox("Always Dash", config.always_dash).on_toggle(Message::AlwaysDash),
+            checkbox("Command Remember", config.command_remember)
+                .on_toggle(Message::CommandRemember),
+        ]
+        .spacing(8)
+        .width(Length::Fill);
+
+        if let Some(touch_ui) = config.touch_ui {
+            content = content.push(checkbox("Touch UI", touch_ui).on_toggle(Message::TouchUi));
+        }
+
+        content = content.extend([
+            volume("BGM Volume", Volume::Bgm, config.bgm_volume).into(),
+            volume("BGS Volume", Volume::Bgs, config.bgs_volume).into(),
+            volume("ME Volume", Volume::Me, config.me_volume).into(),
+            volume("SE Volume", Volume::Se, config.se_volume).into(),
+        ]);
+
+        if !config.extra.is_empty() {
+            content = content.push(text("Plugin options").size(16));
+            content = content.extend(
+                config
+                    .extra
+                    .iter()
+                    .map(|(key, value)| text(format!("{key}: {value}")).into()),
+            );
+        }
+
+        let save = button("Save").on_press_maybe(self.is_modified.then_some(Message::Save));
+        content = content.push(row![save]);
+
+        if let Some(e) = &self.save_error {
+            content = content.push(text(e.to_string()).style(|theme: &Theme| text::Style {
+                color: Some(theme.palette().danger),
+            }));
+        }
+        content.into()
+    }
+}
@@ -1 +1,5 @@
+pub mod backup_list;
+pub mod config_widget;
+pub mod save_details;
 pub mod save_widget;
+pub mod switch_table;
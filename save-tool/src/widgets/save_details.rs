@@ -0,0 +1,52 @@
+use iced::{
+    Length,
+    widget::{column, container, row, scrollable, text},
+};
+use lib::save::{
+    Format, Json,
+    extension::{Registry, Section},
+};
+use std::path::Path;
+
+use crate::error::Error;
+
+/// Contents of the selected save file
+#[derive(Debug, PartialEq)]
+pub struct SaveDetails {
+    pub file_id: usize,
+    pub sections: Result<Vec<Section>, Error>,
+}
+impl SaveDetails {
+    pub fn load(save_dir: &Path, format: Format, file_id: usize, extensions: &Registry) -> Self {
+        let path = format.file_path(save_dir, file_id);
+        let sections = Json::read_file(&path)
+            .map(|save| extensions.sections(&save))
+            .map_err(Error::from);
+
+        Self { file_id, sections }
+    }
+
+    pub fn view<'a, M: 'a>(&'a self) -> iced::Element<'a, M> {
+        let sections = match &self.sections {
+            Ok(sections) => sections,
+            Err(e) => return text(e.to_string()).into(),
+        };
+
+        let header = text(format!("File {}: plugin data", self.file_id));
+        if sections.is_empty() {
+            return column![header, text("None").size(12)].spacing(8).into();
+        }
+
+        let rows = sections.iter().map(|section| {
+            row![
+                text(&section.key).width(Length::FillPortion(1)),
+                text(&section.summary).width(Length::FillPortion(2)),
+            ]
+            .spacing(8)
+            .into()
+        });
+
+        let list = container(scrollable(column(rows).spacing(4))).max_height(160);
+        column![header, list].spacing(8).into()
+    }
+}
@@ -14,28 +14,37 @@ pub enum Message {
     Clicked,
     Copy,
     Delete,
+    ConfirmDelete,
+    CancelDelete,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct SaveWidget {
     pub file_index: usize,
     pub save_entry: Option<SaveEntry>,
+    /// Where the player is, like `Forest > Cave B1`
+    pub location: Option<String>,
     pub is_hovered: bool,
+    pub is_confirming_delete: bool,
 }
 impl SaveWidget {
     pub fn empty(file_index: usize) -> Self {
         Self {
             file_index,
             save_entry: None,
+            location: None,
             is_hovered: false,
+            is_confirming_delete: false,
         }
     }
 
-    pub fn new(file_index: usize, entry: SaveEntry) -> Self {
+    pub fn new(file_index: usize, entry: SaveEntry, location: Option<String>) -> Self {
         Self {
             file_index,
             save_entry: Some(entry),
+            location,
             is_hovered: false,
+            is_confirming_delete: false,
         }
     }
 
@@ -49,11 +58,16 @@ impl SaveWidget {
             }
             Message::Clicked => {}
             Message::Copy => {}
-            Message::Delete => {}
+            Message::Delete => {
+                self.is_confirming_delete = true;
+            }
+            Message::ConfirmDelete | Message::CancelDelete => {
+                self.is_confirming_delete = false;
+            }
         }
     }
 
-    pub fn view(&self, is_selected: bool) -> iced::Element<Message> {
+    pub fn view(&self, is_selected: bool) -> iced::Element<'_, Message> {
         let background_color = move |theme: &iced::Theme| {
             let palette = theme.extended_palette();
             let color = if is_selected {
@@ -96,7 +110,14 @@ impl SaveWidget {
                 }
             };
 
-            let content = text(format!("File {}", self.file_index));
+            let content = match &self.location {
+                Some(location) => column![
+                    text(format!("File {}", self.file_index)),
+                    text(format!("Current location: {location}")).size(12),
+                ]
+                .spacing(4),
+                None => column![text(format!("File {}", self.file_index))],
+            };
             let content = if let Some(images) = image_col {
                 row![images, vertical_rule(24.0), content]
             } else {
@@ -105,7 +126,23 @@ impl SaveWidget {
             .height(Length::Fixed(IMAGE_HEIGHT * 2.0))
             .align_y(Alignment::Center);
 
-            if self.is_hovered && self.save_entry.is_some() {
+            if self.is_confirming_delete {
+                let buttons = container(
+                    row![
+                        text(format!("Delete File {}?", self.file_index)),
+                        button("Delete")
+                            .style(button::danger)
+                            .on_press(Message::ConfirmDelete),
+                        button("Cancel")
+                            .style(button::secondary)
+                            .on_press(Message::CancelDelete),
+                    ]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                )
+                .padding(16);
+                content.extend([horizontal_space().into(), buttons.into()])
+            } else if self.is_hovered && self.save_entry.is_some() {
                 let font = iced::font::Font {
                     family: iced::font::Family::Name("Font Awesome 6 Free"),
                     weight: iced::font::Weight::Black,
@@ -0,0 +1,104 @@
+use iced::{
+    Length,
+    widget::{checkbox, column, container, row, scrollable, text, text_input},
+};
+use lib::{
+    data::{DataSource, system::System},
+    save::{
+        Format, Json,
+        model::GameSave,
+        switches::{self, Filter, Kind, NamedValue},
+    },
+};
+use std::path::Path;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    SearchChanged(String),
+    NonDefaultOnlyToggled(bool),
+}
+
+/// Switches and variables of the selected save
+#[derive(Debug, PartialEq)]
+pub struct SwitchTable {
+    values: Result<Vec<NamedValue>, Error>,
+    filter: Filter,
+}
+impl SwitchTable {
+    pub fn load(
+        save_dir: &Path,
+        format: Format,
+        source: Result<DataSource, Error>,
+        file_id: usize,
+    ) -> Self {
+        let load = || -> Result<Vec<NamedValue>, Error> {
+            let system: System = source?.read("System.json")?;
+            let save = Json::read_file(&format.file_path(save_dir, file_id))?;
+            let save = GameSave::from_json(&save)?;
+
+            let mut values = switches::switches(&save, &system);
+            values.extend(switches::variables(&save, &system));
+            Ok(values)
+        };
+
+        Self {
+            values: load(),
+            filter: Filter {
+                non_default_only: true,
+                search: String::new(),
+            },
+        }
+    }
+
+    pub fn update(&mut self, msg: Message) {
+        match msg {
+            Message::SearchChanged(search) => self.filter.search = search,
+            Message::NonDefaultOnlyToggled(value) => self.filter.non_default_only = value,
+        }
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let values = match &self.values {
+            Ok(values) => values,
+            Err(e) => return text(e.to_string()).into(),
+        };
+
+        let controls = row![
+            text_input("Search switches and variables", &self.filter.search)
+                .on_input(Message::SearchChanged),
+            checkbox("Changed only", self.filter.non_default_only)
+                .on_toggle(Message::NonDefaultOnlyToggled),
+        ]
+        .spacing(8);
+
+        let rows = self
+            .filter
+            .apply(values)
+            .map(|value| {
+                let kind = match value.kind {
+                    Kind::Switch => "Switch",
+                    Kind::Variable => "Variable",
+                };
+                row![
+                    text(kind).width(80),
+                    text(format!("{:04}", value.id)).width(48),
+                    text(&value.name).width(Length::FillPortion(2)),
+                    text(value.value.to_string()).width(Length::FillPortion(1)),
+                ]
+                .spacing(8)
+                .into()
+            })
+            .collect::<Vec<_>>();
+
+        let table = if rows.is_empty() {
+            column![text("None").size(12)]
+        } else {
+            column(rows).spacing(4)
+        };
+
+        let table = container(scrollable(table)).max_height(240);
+        column![controls, table].spacing(8).into()
+    }
+}